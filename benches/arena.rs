@@ -0,0 +1,91 @@
+// Compares the arena-backed deque against the Box- and Rc-based lists
+//
+// Run with `cargo bench --bench arena`
+mod common;
+
+use common::{report, time, SIZES};
+use learning_rust_with_entirely_too_many_linked_lists::{arena, fourth, second};
+use std::hint::black_box;
+
+// Build a second::List while also allocating (and keeping) a Vec in
+// between each node, so that consecutive nodes don't sit next to each
+// other on the heap the way they would in a fresh process
+fn scattered_second(n: usize) -> (second::List<u64>, Vec<Vec<u64>>) {
+    let mut list = second::List::new();
+    let mut filler = Vec::with_capacity(n);
+
+    for x in 0..n as u64 {
+        list.push(x);
+        filler.push(vec![x; 4]);
+    }
+
+    (list, filler)
+}
+
+fn main() {
+    for n in SIZES {
+        report(
+            "push/pop second::List (Box)",
+            n,
+            time(|| {
+                let mut list = second::List::new();
+                (0..n as u64).for_each(|x| list.push(x));
+                while let Some(x) = list.pop() {
+                    black_box(x);
+                }
+            }),
+        );
+
+        report(
+            "push/pop fourth::List (Rc)",
+            n,
+            time(|| {
+                let mut list = fourth::List::new();
+                (0..n as u64).for_each(|x| list.push_front(x));
+                while let Some(x) = list.pop_front() {
+                    black_box(x);
+                }
+            }),
+        );
+
+        report(
+            "push/pop arena::List",
+            n,
+            time(|| {
+                let mut list = arena::List::new();
+                (0..n as u64).for_each(|x| {
+                    list.push_front(x);
+                });
+                while let Some(x) = list.pop_front() {
+                    black_box(x);
+                }
+            }),
+        );
+
+        // Traversal is where the layout shows up: the arena walks one
+        // contiguous Vec, while the Box nodes are spread across the heap
+        let (boxed, filler) = scattered_second(n);
+        report(
+            "iter second::List (scattered)",
+            n,
+            time(|| {
+                black_box(boxed.iter().sum::<u64>());
+            }),
+        );
+        drop(filler);
+
+        let mut slab = arena::List::new();
+        (0..n as u64).for_each(|x| {
+            slab.push_back(x);
+        });
+        report(
+            "iter arena::List",
+            n,
+            time(|| {
+                black_box(slab.iter().sum::<u64>());
+            }),
+        );
+
+        println!();
+    }
+}
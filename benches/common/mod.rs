@@ -0,0 +1,45 @@
+// The timing harness shared by the arena, unrolled and skip_list benches
+//
+// This is a plain std::time harness rather than #[bench], so it runs on
+// stable. Each bench is its own crate and uses only some of it
+#![allow(dead_code)]
+
+use std::time::{Duration, Instant};
+
+pub const SIZES: [usize; 3] = [1_000, 100_000, 1_000_000];
+const ROUNDS: u32 = 5;
+
+// Run `f` a few times and keep the fastest time, which is the one least
+// disturbed by whatever else the machine is doing. Whatever `f` returns
+// is dropped after the clock stops, so e.g. building a list isn't charged
+// for freeing it
+pub fn time<O, F: FnMut() -> O>(mut f: F) -> Duration {
+    time_with_setup(|| (), |()| f())
+}
+
+// Like time, but `setup` runs outside the timed section
+pub fn time_with_setup<S, T, F, O>(mut setup: S, mut f: F) -> Duration
+where
+    S: FnMut() -> T,
+    F: FnMut(T) -> O,
+{
+    (0..ROUNDS)
+        .map(|_| {
+            let input = setup();
+            let start = Instant::now();
+            let output = f(input);
+            let elapsed = start.elapsed();
+
+            drop(output);
+
+            elapsed
+        })
+        .min()
+        .unwrap_or_default()
+}
+
+pub fn report(name: &str, n: usize, elapsed: Duration) {
+    let per_elem = elapsed.as_nanos() as f64 / n as f64;
+
+    println!("{name:<32} {n:>10} {elapsed:>12.3?} {per_elem:>8.2} ns/elem");
+}
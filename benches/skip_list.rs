@@ -0,0 +1,116 @@
+// Compares SkipListMap against BTreeMap
+//
+// Run with `cargo bench --bench skip_list`
+mod common;
+
+use common::{report, time, time_with_setup, SIZES};
+use learning_rust_with_entirely_too_many_linked_lists::rng::Rng;
+use learning_rust_with_entirely_too_many_linked_lists::skip_list::SkipListMap;
+use std::collections::BTreeMap;
+use std::hint::black_box;
+
+fn main() {
+    for n in SIZES {
+        // the same shuffled-ish keys for both maps
+        let mut rng = Rng::new(n as u64);
+        let keys: Vec<u64> = (0..n).map(|_| rng.below(n as u64 * 4)).collect();
+
+        report(
+            "insert SkipListMap",
+            n,
+            time(|| {
+                let mut map = SkipListMap::new();
+                keys.iter().for_each(|&k| {
+                    map.insert(k, k);
+                });
+                // dropped once the clock stops
+                map
+            }),
+        );
+
+        report(
+            "insert BTreeMap",
+            n,
+            time(|| {
+                let mut map = BTreeMap::new();
+                keys.iter().for_each(|&k| {
+                    map.insert(k, k);
+                });
+                // dropped once the clock stops
+                map
+            }),
+        );
+
+        let skip: SkipListMap<_, _> = keys.iter().map(|&k| (k, k)).collect();
+        let btree: BTreeMap<_, _> = keys.iter().map(|&k| (k, k)).collect();
+
+        report(
+            "get SkipListMap",
+            n,
+            time(|| {
+                keys.iter().for_each(|k| {
+                    black_box(skip.get(k));
+                });
+            }),
+        );
+
+        report(
+            "get BTreeMap",
+            n,
+            time(|| {
+                keys.iter().for_each(|k| {
+                    black_box(btree.get(k));
+                });
+            }),
+        );
+
+        // the middle half of the key space
+        let range = n as u64..n as u64 * 3;
+
+        report(
+            "range SkipListMap",
+            n,
+            time(|| {
+                black_box(skip.range(range.clone()).map(|(_, &v)| v).sum::<u64>());
+            }),
+        );
+
+        report(
+            "range BTreeMap",
+            n,
+            time(|| {
+                black_box(btree.range(range.clone()).map(|(_, &v)| v).sum::<u64>());
+            }),
+        );
+
+        report(
+            "remove SkipListMap",
+            n,
+            time_with_setup(
+                || keys.iter().map(|&k| (k, k)).collect::<SkipListMap<_, _>>(),
+                |mut map| {
+                    keys.iter().for_each(|k| {
+                        black_box(map.remove(k));
+                    });
+                    map
+                },
+            ),
+        );
+
+        report(
+            "remove BTreeMap",
+            n,
+            time_with_setup(
+                || keys.iter().map(|&k| (k, k)).collect::<BTreeMap<_, _>>(),
+                |mut map| {
+                    keys.iter().for_each(|k| {
+                        black_box(map.remove(k));
+                    });
+                    map
+                },
+            ),
+        );
+
+        println!();
+    }
+}
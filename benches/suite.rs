@@ -0,0 +1,356 @@
+// Compares every list in the crate against Vec, VecDeque and LinkedList
+//
+// Run with `cargo bench --bench suite`. Each list is timed at:
+//  - push - building a list of n elements from empty
+//  - pop - popping all n elements off again
+//  - iterate - summing the elements through a borrowing iterator
+//  - append - moving one list of n elements onto the end of another
+//  - drop - freeing a list of n elements
+// and the results are printed as one table per operation, in nanoseconds
+// per element, so that the sizes can be compared with each other.
+//
+// Two environment variables change what gets run:
+//  - BENCH_SIZES - comma-separated list sizes, e.g. BENCH_SIZES=10,1000.
+//      Defaults to every power of ten from 10 to 10 million - the larger
+//      sizes take several minutes, and a few GB of memory for append
+//  - BENCH_FORMAT - `markdown` (the default) or `csv`
+//
+// Left out:
+//  - intrusive::List - it borrows its elements rather than owning them, so
+//      there's nothing to push but references to items stored elsewhere
+//  - fifth_attempt_2::List - it's borrowed mutably by its first push for
+//      the rest of its life, so it can't be pushed to in a loop
+//  - skip_list - it's a sorted map rather than a sequence
+use learning_rust_with_entirely_too_many_linked_lists::{
+    arena, circular, first, fourth, second, third, unrolled, xor,
+};
+use std::collections::{LinkedList, VecDeque};
+use std::env;
+use std::hint::black_box;
+use std::mem;
+use std::time::{Duration, Instant};
+
+const DEFAULT_SIZES: [usize; 7] = [10, 100, 1_000, 10_000, 100_000, 1_000_000, 10_000_000];
+const ROUNDS: u32 = 5;
+
+// Small lists are timed in batches of about this many elements, so that
+// each measurement is long enough for the clock to resolve
+const BATCH_ELEMS: usize = 100_000;
+
+// elements per unrolled node, as in the unrolled bench
+const N: usize = 16;
+
+const OPS: [&str; 5] = ["push", "pop", "iterate", "append", "drop"];
+
+// What the suite needs from each list. Stacks push and pop at the same
+// end, queues push at the back and pop from the front - each list is used
+// the way it's meant to be
+trait Subject: Sized {
+    const NAME: &'static str;
+
+    fn new() -> Self;
+
+    fn push(&mut self, x: i32);
+
+    fn pop(&mut self) -> Option<i32>;
+
+    // None if the list has no borrowing iterator
+    fn sum(&self) -> Option<i64>;
+
+    // None of our lists has an append yet, so by default this moves the
+    // elements over one at a time - what a caller would have to write
+    // today. A stack ends up with other's elements reversed, which doesn't
+    // matter for timing
+    fn append(&mut self, mut other: Self) {
+        while let Some(x) = other.pop() {
+            self.push(x);
+        }
+    }
+}
+
+// For lists with a borrowing .iter(), optionally with their own append
+macro_rules! subject {
+    ($ty:ty, $name:literal, $push:ident, $pop:ident $(, $append:ident)?) => {
+        impl Subject for $ty {
+            const NAME: &'static str = $name;
+
+            fn new() -> Self {
+                <$ty>::new()
+            }
+
+            fn push(&mut self, x: i32) {
+                self.$push(x);
+            }
+
+            fn pop(&mut self) -> Option<i32> {
+                self.$pop()
+            }
+
+            fn sum(&self) -> Option<i64> {
+                Some(self.iter().map(|&x| x as i64).sum())
+            }
+
+            $(
+                fn append(&mut self, mut other: Self) {
+                    self.$append(&mut other);
+                }
+            )?
+        }
+    };
+}
+
+subject!(Vec<i32>, "Vec", push, pop, append);
+subject!(VecDeque<i32>, "VecDeque", push_back, pop_front, append);
+subject!(LinkedList<i32>, "LinkedList", push_back, pop_front, append);
+subject!(second::List<i32>, "second", push, pop);
+subject!(arena::List<i32>, "arena", push_back, pop_front);
+subject!(unrolled::List<i32, N>, "unrolled", push_back, pop_front);
+subject!(xor::List<i32>, "xor", push_back, pop_front);
+// the first element pushed is current, so removing current pops the
+// oldest element, like a queue
+subject!(circular::List<i32>, "circular", push_back, remove_current);
+
+impl Subject for first::List {
+    const NAME: &'static str = "first";
+
+    fn new() -> Self {
+        first::List::new()
+    }
+
+    fn push(&mut self, x: i32) {
+        first::List::push(self, x);
+    }
+
+    fn pop(&mut self) -> Option<i32> {
+        first::List::pop(self)
+    }
+
+    fn sum(&self) -> Option<i64> {
+        None
+    }
+}
+
+// Pushing and popping a persistent list makes a new version each time,
+// replacing the old one
+impl Subject for third::List<i32> {
+    const NAME: &'static str = "third";
+
+    fn new() -> Self {
+        third::List::new()
+    }
+
+    fn push(&mut self, x: i32) {
+        *self = self.prepend(x);
+    }
+
+    fn pop(&mut self) -> Option<i32> {
+        let head = self.head().copied();
+
+        *self = mem::take(self).tail();
+
+        head
+    }
+
+    fn sum(&self) -> Option<i64> {
+        Some(self.iter().map(|&x| x as i64).sum())
+    }
+}
+
+// fourth::List hands out Refs rather than references, so it has no
+// borrowing iterator
+impl Subject for fourth::List<i32> {
+    const NAME: &'static str = "fourth";
+
+    fn new() -> Self {
+        fourth::List::new()
+    }
+
+    fn push(&mut self, x: i32) {
+        self.push_back(x);
+    }
+
+    fn pop(&mut self) -> Option<i32> {
+        self.pop_front()
+    }
+
+    fn sum(&self) -> Option<i64> {
+        None
+    }
+}
+
+fn filled<S: Subject>(n: usize) -> S {
+    let mut list = S::new();
+
+    (0..n as i32).for_each(|x| list.push(x));
+
+    list
+}
+
+// Time `f` on inputs made by `setup`, and return the best of ROUNDS runs
+// in nanoseconds per element. Only `f` is timed - setting up its input and
+// dropping whatever it returns both happen with the clock stopped, so e.g.
+// push isn't charged for freeing the list it built
+fn measure<I, O>(n: usize, mut setup: impl FnMut() -> I, mut f: impl FnMut(I) -> O) -> f64 {
+    let batch = (BATCH_ELEMS / n.max(1)).max(1);
+
+    let best = (0..ROUNDS)
+        .map(|_| {
+            let inputs: Vec<I> = (0..batch).map(|_| setup()).collect();
+            let mut outputs = Vec::with_capacity(batch);
+
+            let start = Instant::now();
+
+            for input in inputs {
+                outputs.push(f(input));
+            }
+
+            let elapsed = start.elapsed();
+
+            drop(outputs);
+
+            elapsed
+        })
+        .min()
+        .unwrap_or(Duration::ZERO);
+
+    best.as_nanos() as f64 / (batch * n.max(1)) as f64
+}
+
+// One result per entry in OPS, None where the list doesn't support it
+fn run<S: Subject>(n: usize) -> [Option<f64>; 5] {
+    let fill = || filled::<S>(n);
+
+    let push = measure(n, || (), |()| fill());
+
+    let pop = measure(n, fill, |mut list| {
+        while let Some(x) = list.pop() {
+            black_box(x);
+        }
+
+        list
+    });
+
+    let iterate = S::new().sum().map(|_| {
+        measure(n, fill, |list| {
+            black_box(list.sum());
+
+            list
+        })
+    });
+
+    let append = measure(
+        n,
+        || (fill(), fill()),
+        |(mut list, other)| {
+            list.append(other);
+
+            list
+        },
+    );
+
+    let free = measure(n, fill, drop);
+
+    [Some(push), Some(pop), iterate, Some(append), Some(free)]
+}
+
+struct Results {
+    sizes: Vec<usize>,
+    names: Vec<&'static str>,
+    // times[name][size][op]
+    times: Vec<Vec<[Option<f64>; 5]>>,
+}
+
+impl Results {
+    fn add<S: Subject>(&mut self) {
+        let times = self
+            .sizes
+            .iter()
+            .map(|&n| {
+                eprintln!("{:<10} {n:>10}", S::NAME);
+
+                run::<S>(n)
+            })
+            .collect();
+
+        self.names.push(S::NAME);
+        self.times.push(times);
+    }
+
+    fn print_markdown(&self) {
+        for (op, op_name) in OPS.iter().enumerate() {
+            println!("### {op_name} (ns/elem)");
+            println!();
+            println!("| n | {} |", self.names.join(" | "));
+            println!("|---:|{}", "---:|".repeat(self.names.len()));
+
+            for (size, n) in self.sizes.iter().enumerate() {
+                let cells: Vec<String> = self
+                    .times
+                    .iter()
+                    .map(|times| match times[size][op] {
+                        Some(ns) => format!("{ns:.2}"),
+                        None => "-".to_string(),
+                    })
+                    .collect();
+
+                println!("| {n} | {} |", cells.join(" | "));
+            }
+
+            println!();
+        }
+    }
+
+    fn print_csv(&self) {
+        println!("op,list,n,ns_per_elem");
+
+        for (op, op_name) in OPS.iter().enumerate() {
+            for (name, times) in self.names.iter().zip(&self.times) {
+                for (n, results) in self.sizes.iter().zip(times) {
+                    if let Some(ns) = results[op] {
+                        println!("{op_name},{name},{n},{ns:.3}");
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn sizes() -> Vec<usize> {
+    match env::var("BENCH_SIZES") {
+        Ok(sizes) => sizes
+            .split(',')
+            .map(|n| n.trim().replace('_', ""))
+            .map(|n| {
+                n.parse()
+                    .unwrap_or_else(|_| panic!("BENCH_SIZES: {n:?} isn't a size"))
+            })
+            .collect(),
+        Err(_) => DEFAULT_SIZES.to_vec(),
+    }
+}
+
+fn main() {
+    let mut results = Results {
+        sizes: sizes(),
+        names: Vec::new(),
+        times: Vec::new(),
+    };
+
+    results.add::<Vec<i32>>();
+    results.add::<VecDeque<i32>>();
+    results.add::<LinkedList<i32>>();
+    results.add::<first::List>();
+    results.add::<second::List<i32>>();
+    results.add::<third::List<i32>>();
+    results.add::<fourth::List<i32>>();
+    results.add::<arena::List<i32>>();
+    results.add::<unrolled::List<i32, N>>();
+    results.add::<xor::List<i32>>();
+    results.add::<circular::List<i32>>();
+
+    match env::var("BENCH_FORMAT").as_deref() {
+        Ok("csv") => results.print_csv(),
+        Ok("markdown") | Err(_) => results.print_markdown(),
+        Ok(other) => panic!("BENCH_FORMAT: expected markdown or csv, got {other:?}"),
+    }
+}
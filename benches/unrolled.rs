@@ -0,0 +1,123 @@
+// Compares the unrolled list against fourth::List and VecDeque
+//
+// Run with `cargo bench --bench unrolled`
+mod common;
+
+use common::{report, time, time_with_setup, SIZES};
+use learning_rust_with_entirely_too_many_linked_lists::{fourth, unrolled};
+use std::collections::VecDeque;
+use std::hint::black_box;
+
+// elements per unrolled node - 16 i32s fill a 64 byte cache line
+const N: usize = 16;
+
+fn main() {
+    for n in SIZES {
+        report(
+            "push/pop fourth::List",
+            n,
+            time(|| {
+                let mut list = fourth::List::new();
+                (0..n as i32).for_each(|x| list.push_back(x));
+                while let Some(x) = list.pop_front() {
+                    black_box(x);
+                }
+            }),
+        );
+
+        report(
+            "push/pop unrolled::List",
+            n,
+            time(|| {
+                let mut list = unrolled::List::<i32, N>::new();
+                (0..n as i32).for_each(|x| list.push_back(x));
+                while let Some(x) = list.pop_front() {
+                    black_box(x);
+                }
+            }),
+        );
+
+        report(
+            "push/pop VecDeque",
+            n,
+            time(|| {
+                let mut deque = VecDeque::new();
+                (0..n as i32).for_each(|x| deque.push_back(x));
+                while let Some(x) = deque.pop_front() {
+                    black_box(x);
+                }
+            }),
+        );
+
+        let mut list = unrolled::List::<i32, N>::new();
+        let mut deque = VecDeque::new();
+
+        (0..n as i32).for_each(|x| {
+            list.push_back(x);
+            deque.push_back(x);
+        });
+
+        report(
+            "iter unrolled::List",
+            n,
+            time(|| {
+                black_box(list.iter().map(|&x| x as i64).sum::<i64>());
+            }),
+        );
+
+        report(
+            "iter VecDeque",
+            n,
+            time(|| {
+                black_box(deque.iter().map(|&x| x as i64).sum::<i64>());
+            }),
+        );
+
+        // fourth::List has no borrowing iterator, so the closest we can
+        // get is consuming it
+        report(
+            "into_iter fourth::List",
+            n,
+            time(|| {
+                let mut fourth = fourth::List::new();
+                (0..n as i32).for_each(|x| fourth.push_back(x));
+                black_box(fourth.into_iter().map(|x| x as i64).sum::<i64>());
+            }),
+        );
+
+        // inserts into the middle - the unrolled list only shifts elements
+        // within one node, but has to walk n / 2 elements' worth of nodes
+        // to find it, while VecDeque finds it at once and shifts n / 2
+        let inserts = 1_000.min(n);
+
+        report(
+            "insert middle unrolled::List",
+            inserts,
+            time_with_setup(
+                || {
+                    let mut list = unrolled::List::<i32, N>::new();
+                    (0..n as i32).for_each(|x| list.push_back(x));
+                    list
+                },
+                |mut list| {
+                    (0..inserts as i32).for_each(|x| list.insert(list.len() / 2, x));
+                    black_box(list.len());
+                },
+            ),
+        );
+
+        report(
+            "insert middle VecDeque",
+            inserts,
+            time_with_setup(
+                || (0..n as i32).collect::<VecDeque<_>>(),
+                |mut deque| {
+                    (0..inserts as i32).for_each(|x| deque.insert(deque.len() / 2, x));
+                    black_box(deque.len());
+                },
+            ),
+        );
+
+        println!();
+    }
+}
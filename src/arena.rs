@@ -0,0 +1,718 @@
+// A doubly-linked deque whose nodes live in a single Vec, the "slab"
+//
+// In `second`, `third` and `fourth` every push calls the global allocator
+// for a new Box or Rc, and the nodes end up wherever the allocator puts
+// them. Here we allocate all nodes out of one Vec<Node<T>> instead, and
+// links are u32 indices into that Vec rather than pointers:
+//  - no Rc<RefCell<_>> is needed for the two-way links, because an index
+//      doesn't own anything - the Vec owns every node
+//  - nodes pushed one after the other sit next to each other in memory
+//  - popped slots go onto a free-list and are handed out again by the next
+//      push, so a list that pushes and pops in a loop stops allocating
+//      once the Vec has grown large enough
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::iter::DoubleEndedIterator;
+use core::str::FromStr;
+
+use crate::dot;
+use crate::text::{self, ParseListError};
+use crate::traits::{Deque, Queue, Stack};
+
+// An index into List::nodes
+type Link = Option<u32>;
+
+struct Node<T> {
+    // None while the slot is sitting on the free-list
+    elem: Option<T>,
+    next: Link,
+    prev: Link,
+    // bumped every time the slot is freed, so that a NodeHandle to a
+    // previous occupant of the slot can't be used to reach the new one
+    generation: u32,
+}
+
+// A stable reference to an element, returned by the push methods
+//
+// A handle stays valid while its element is in the list, no matter what
+// else is pushed or popped, and allows the element to be read or removed
+// in O(1). Once the element leaves the list the handle goes stale, and
+// every method taking a handle returns None for it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NodeHandle {
+    index: u32,
+    generation: u32,
+}
+
+pub struct List<T> {
+    nodes: Vec<Node<T>>,
+    head: Link,
+    tail: Link,
+    // head of the free-list - vacant slots are chained together through
+    // their .next links
+    free: Link,
+    len: usize,
+}
+
+pub struct IntoIter<T>(List<T>);
+
+pub struct Iter<'a, T> {
+    nodes: &'a [Node<T>],
+    front: Link,
+    back: Link,
+    // the number of elements left between front and back, which tells us
+    // when the two ends have met
+    len: usize,
+}
+
+impl<T> List<T> {
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    // Pre-allocate room for `capacity` nodes, so that the first `capacity`
+    // pushes don't need to grow the slab
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            nodes: Vec::with_capacity(capacity),
+            head: None,
+            tail: None,
+            free: None,
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn push_front(&mut self, elem: T) -> NodeHandle {
+        let index = self.alloc(elem);
+
+        self.link_front(index);
+
+        self.handle(index)
+    }
+
+    pub fn push_back(&mut self, elem: T) -> NodeHandle {
+        let index = self.alloc(elem);
+
+        match self.tail {
+            Some(old_tail) => {
+                self.node_mut(index).prev = Some(old_tail);
+                self.node_mut(old_tail).next = Some(index);
+            }
+            None => self.head = Some(index),
+        }
+
+        self.tail = Some(index);
+        self.len += 1;
+
+        self.handle(index)
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.head.map(|index| self.remove_index(index))
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.tail.map(|index| self.remove_index(index))
+    }
+
+    // Because the Vec owns the nodes, we can hand out plain references
+    // here - there's no RefCell to borrow through as there is in
+    // fourth::List::peek_front
+    pub fn peek_front(&self) -> Option<&T> {
+        self.head.and_then(|index| self.node(index).elem.as_ref())
+    }
+
+    pub fn peek_front_mut(&mut self) -> Option<&mut T> {
+        self.head
+            .and_then(|index| self.node_mut(index).elem.as_mut())
+    }
+
+    pub fn peek_back(&self) -> Option<&T> {
+        self.tail.and_then(|index| self.node(index).elem.as_ref())
+    }
+
+    pub fn peek_back_mut(&mut self) -> Option<&mut T> {
+        self.tail
+            .and_then(|index| self.node_mut(index).elem.as_mut())
+    }
+
+    // Handles to the elements at either end of the list
+    pub fn front_handle(&self) -> Option<NodeHandle> {
+        self.head.map(|index| self.handle(index))
+    }
+
+    pub fn back_handle(&self) -> Option<NodeHandle> {
+        self.tail.map(|index| self.handle(index))
+    }
+
+    pub fn contains(&self, handle: NodeHandle) -> bool {
+        self.index_of(handle).is_some()
+    }
+
+    pub fn get(&self, handle: NodeHandle) -> Option<&T> {
+        self.index_of(handle)
+            .and_then(|index| self.node(index).elem.as_ref())
+    }
+
+    pub fn get_mut(&mut self, handle: NodeHandle) -> Option<&mut T> {
+        self.index_of(handle)
+            .and_then(|index| self.node_mut(index).elem.as_mut())
+    }
+
+    // Remove the element a handle points to in O(1), wherever it is in
+    // the list
+    pub fn remove(&mut self, handle: NodeHandle) -> Option<T> {
+        self.index_of(handle).map(|index| self.remove_index(index))
+    }
+
+    // Move the element a handle points to to the front of the list in
+    // O(1). The handle stays valid. Returns false if the handle is stale
+    pub fn move_to_front(&mut self, handle: NodeHandle) -> bool {
+        let Some(index) = self.index_of(handle) else {
+            return false;
+        };
+
+        if self.head != Some(index) {
+            self.unlink(index);
+            self.link_front(index);
+        }
+
+        true
+    }
+
+    // Remove every element. The slots aren't thrown away: like any other
+    // freed slot, each one has its generation bumped and goes onto the
+    // free-list. Starting the slab again from scratch would restart the
+    // generations too, and a handle taken before the clear would reach
+    // whatever was pushed into its slot next
+    pub fn clear(&mut self) {
+        while let Some(index) = self.head {
+            drop(self.remove_index(index));
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            nodes: &self.nodes,
+            front: self.head,
+            back: self.tail,
+            len: self.len,
+        }
+    }
+
+    // The node graph in Graphviz's DOT format - see dot.rs. Each node is
+    // labelled with its slot in the Vec. Vacant slots on the free-list
+    // aren't part of the list, so they're left out
+    pub fn to_dot(&self) -> String
+    where
+        T: fmt::Debug,
+    {
+        let mut graph = dot::Graph::new();
+        let mut from = None;
+        let mut next = self.head;
+
+        graph.root("list", "arena::List");
+
+        while let Some(index) = next {
+            let node = self.node(index);
+            let (id, new) = graph.id(node);
+
+            match from {
+                Some(from) => graph.edge(from, id, "next"),
+                None => graph.edge("list", id, "head"),
+            }
+
+            if !new {
+                break;
+            }
+
+            if let Some(elem) = &node.elem {
+                graph.node(id, &format!("{elem:?}\nslot {index}"));
+            }
+
+            if let Some(prev) = node.prev {
+                let (prev_id, _) = graph.id(self.node(prev));
+
+                graph.edge(id, prev_id, "prev");
+            }
+
+            from = Some(id);
+            next = node.next;
+        }
+
+        if let Some(tail) = self.tail {
+            let (id, _) = graph.id(self.node(tail));
+
+            graph.edge("list", id, "tail");
+        }
+
+        graph.finish()
+    }
+
+    fn node(&self, index: u32) -> &Node<T> {
+        &self.nodes[index as usize]
+    }
+
+    fn node_mut(&mut self, index: u32) -> &mut Node<T> {
+        &mut self.nodes[index as usize]
+    }
+
+    fn handle(&self, index: u32) -> NodeHandle {
+        NodeHandle {
+            index,
+            generation: self.node(index).generation,
+        }
+    }
+
+    // Resolve a handle to the index of a live node, or None if the handle
+    // is stale or belongs to a slot we've never handed out
+    fn index_of(&self, handle: NodeHandle) -> Option<u32> {
+        self.nodes
+            .get(handle.index as usize)
+            .filter(|node| node.generation == handle.generation && node.elem.is_some())
+            .map(|_| handle.index)
+    }
+
+    // Find a slot for a new, unlinked node - either the first slot on the
+    // free-list, or a new one at the end of the slab
+    fn alloc(&mut self, elem: T) -> u32 {
+        match self.free {
+            Some(index) => {
+                let node = self.node_mut(index);
+                let next_free = node.next.take();
+
+                node.elem = Some(elem);
+                self.free = next_free;
+
+                index
+            }
+            None => {
+                let index = u32::try_from(self.nodes.len())
+                    .expect("arena::List cannot hold more than u32::MAX nodes");
+
+                self.nodes.push(Node {
+                    elem: Some(elem),
+                    next: None,
+                    prev: None,
+                    generation: 0,
+                });
+
+                index
+            }
+        }
+    }
+
+    // Link an unlinked node in at the head
+    fn link_front(&mut self, index: u32) {
+        // the same steps as fourth::List::push_front, only with indices
+        // instead of Rc::clone
+        match self.head {
+            Some(old_head) => {
+                self.node_mut(index).next = Some(old_head);
+                self.node_mut(old_head).prev = Some(index);
+            }
+            None => self.tail = Some(index),
+        }
+
+        self.head = Some(index);
+        self.len += 1;
+    }
+
+    // Take a node out of the list, leaving it in its slot
+    fn unlink(&mut self, index: u32) {
+        let (prev, next) = {
+            let node = self.node(index);
+
+            (node.prev, node.next)
+        };
+
+        // point whatever was before the node to whatever was after it...
+        match prev {
+            Some(prev) => self.node_mut(prev).next = next,
+            None => self.head = next,
+        }
+
+        // ...and vice versa
+        match next {
+            Some(next) => self.node_mut(next).prev = prev,
+            None => self.tail = prev,
+        }
+
+        let node = self.node_mut(index);
+
+        node.prev = None;
+        node.next = None;
+        self.len -= 1;
+    }
+
+    // Unlink a live node from its neighbours, and put its slot on the
+    // free-list
+    fn remove_index(&mut self, index: u32) -> T {
+        self.unlink(index);
+
+        let free = self.free;
+        let node = self.node_mut(index);
+
+        node.generation = node.generation.wrapping_add(1);
+        node.next = free;
+        self.free = Some(index);
+
+        self.node_mut(index)
+            .elem
+            .take()
+            .expect("a linked node always holds an element")
+    }
+}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Stack<T> for List<T> {
+    fn push(&mut self, elem: T) {
+        List::push_front(self, elem);
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        List::pop_front(self)
+    }
+}
+
+impl<T> Queue<T> for List<T> {
+    fn push_back(&mut self, elem: T) {
+        List::push_back(self, elem);
+    }
+
+    fn pop_front(&mut self) -> Option<T> {
+        List::pop_front(self)
+    }
+}
+
+impl<T> Deque<T> for List<T> {
+    fn push_front(&mut self, elem: T) {
+        List::push_front(self, elem);
+    }
+
+    fn pop_back(&mut self) -> Option<T> {
+        List::pop_back(self)
+    }
+}
+
+// There's no Drop implementation - dropping the Vec drops every node in a
+// loop, so there's no recursion to worry about like there is with Box
+// links in second::List
+
+// Written front to back
+impl<T: fmt::Display> fmt::Display for List<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        text::write_list(f, self.iter())
+    }
+}
+
+impl<T: FromStr> FromStr for List<T> {
+    type Err = ParseListError<T::Err>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let elems = text::parse_list(s)?;
+        let mut list = Self::with_capacity(elems.len());
+
+        elems.into_iter().for_each(|elem| {
+            list.push_back(elem);
+        });
+
+        Ok(list)
+    }
+}
+
+impl<T> IntoIterator for List<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self)
+    }
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.pop_front()
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.pop_back()
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+
+        self.front.and_then(|index| {
+            let node = &self.nodes[index as usize];
+
+            self.front = node.next;
+            self.len -= 1;
+
+            node.elem.as_ref()
+        })
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+
+        self.back.and_then(|index| {
+            let node = &self.nodes[index as usize];
+
+            self.back = node.prev;
+            self.len -= 1;
+
+            node.elem.as_ref()
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::List;
+    use crate::test_utils::{panics, DropCounter, Tracked};
+
+    crate::conformance_tests!(Stack, stack_conformance, List::new());
+    crate::conformance_tests!(Deque, deque_conformance, List::new());
+
+    #[test]
+    fn push_and_pop_front() {
+        let mut list = List::new();
+        let xs = [0, 1, 2];
+
+        xs.into_iter().for_each(|x| {
+            list.push_front(x);
+        });
+
+        for &x in xs.iter().rev() {
+            assert_eq!(list.pop_front(), Some(x));
+        }
+
+        assert_eq!(list.pop_front(), None);
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn push_and_pop_back() {
+        let mut list = List::new();
+        let xs = [1, 2, 3];
+
+        xs.iter().for_each(|&x| {
+            list.push_back(x);
+        });
+
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.pop_back(), Some(3));
+        assert_eq!(list.pop_front(), Some(1));
+        assert_eq!(list.pop_back(), Some(2));
+        assert_eq!(list.pop_back(), None);
+    }
+
+    #[test]
+    fn peek() {
+        let mut list = List::new();
+
+        assert!(list.peek_front().is_none());
+        assert!(list.peek_back().is_none());
+
+        [1, 2, 3].into_iter().for_each(|x| {
+            list.push_back(x);
+        });
+
+        if let Some(x) = list.peek_front_mut() {
+            *x *= 10;
+        }
+
+        if let Some(x) = list.peek_back_mut() {
+            *x *= 10;
+        }
+
+        assert_eq!(list.peek_front(), Some(&10));
+        assert_eq!(list.peek_back(), Some(&30));
+    }
+
+    #[test]
+    fn remove_by_handle() {
+        let mut list = List::new();
+
+        let one = list.push_back(1);
+        let two = list.push_back(2);
+        let three = list.push_back(3);
+
+        assert_eq!(list.remove(two), Some(2));
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [1, 3]);
+
+        assert_eq!(list.remove(one), Some(1));
+        assert_eq!(list.remove(three), Some(3));
+        assert!(list.is_empty());
+        assert_eq!(list.pop_front(), None);
+    }
+
+    #[test]
+    fn stale_handles() {
+        let mut list = List::new();
+
+        let one = list.push_back(1);
+        list.pop_back();
+
+        // the slot is reused by the next push...
+        let two = list.push_back(2);
+
+        // ...but the old handle doesn't reach the new element
+        assert!(!list.contains(one));
+        assert_eq!(list.get(one), None);
+        assert_eq!(list.remove(one), None);
+
+        if let Some(x) = list.get_mut(two) {
+            *x += 1;
+        }
+
+        assert_eq!(list.get(two), Some(&3));
+    }
+
+    #[test]
+    fn clear_makes_handles_stale() {
+        let mut list = List::new();
+        let old: Vec<_> = (0..3).map(|x| list.push_back(x)).collect();
+
+        list.clear();
+        assert!(list.is_empty());
+
+        // the slots are reused...
+        let new: Vec<_> = (10..13).map(|x| list.push_back(x)).collect();
+        assert_eq!(list.nodes.len(), 3);
+
+        // ...but the handles from before the clear don't reach into them
+        for handle in old {
+            assert!(!list.contains(handle));
+            assert_eq!(list.get(handle), None);
+            assert_eq!(list.remove(handle), None);
+            assert!(!list.move_to_front(handle));
+        }
+
+        assert_eq!(list.get(new[0]), Some(&10));
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [10, 11, 12]);
+    }
+
+    #[test]
+    fn reuses_freed_slots() {
+        let mut list = List::with_capacity(4);
+
+        (0..4).for_each(|x| {
+            list.push_back(x);
+        });
+
+        for x in 4..100 {
+            list.pop_front();
+            list.push_back(x);
+        }
+
+        assert_eq!(list.nodes.len(), 4);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [96, 97, 98, 99]);
+    }
+
+    #[test]
+    fn move_to_front() {
+        let mut list = List::new();
+        let handles: Vec<_> = (1..=3).map(|x| list.push_back(x)).collect();
+
+        assert!(list.move_to_front(handles[2]));
+        assert!(list.move_to_front(handles[2]));
+        assert!(list.move_to_front(handles[1]));
+
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [2, 3, 1]);
+        assert_eq!(list.iter().rev().copied().collect::<Vec<_>>(), [1, 3, 2]);
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.get(handles[1]), Some(&2));
+
+        list.remove(handles[0]);
+
+        assert!(!list.move_to_front(handles[0]));
+        assert_eq!(list.peek_back(), Some(&3));
+    }
+
+    #[test]
+    fn iter() {
+        let mut list = List::new();
+
+        [2, 3].into_iter().for_each(|x| {
+            list.push_back(x);
+        });
+        list.push_front(1);
+
+        let mut iter = list.iter();
+
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.next_back(), Some(&3));
+        assert_eq!(iter.next(), Some(&2));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+    }
+
+    #[test]
+    fn into_iter_rev() {
+        let mut list = List::new();
+        let xs = [1, 2, 3];
+
+        xs.iter().for_each(|&x| {
+            list.push_front(x);
+        });
+
+        let mut iter = list.into_iter().rev();
+
+        for &x in xs.iter() {
+            assert_eq!(iter.next(), Some(x));
+        }
+
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn drop_carries_on_after_a_panic() {
+        let counter = DropCounter::new();
+        let mut list = List::new();
+
+        list.push_back(counter.tracked());
+        list.push_back(counter.panicking());
+        list.push_back(counter.tracked());
+
+        let first = list.push_front(counter.tracked());
+
+        assert_eq!(list.get(first).map(Tracked::id), Some(3));
+
+        // there's no Drop impl to make panic-safe here - the nodes live in
+        // a Vec, and dropping a Vec already carries on past a panicking
+        // element
+        assert!(panics(|| drop(list)));
+
+        counter.assert_all_dropped_once();
+    }
+}
@@ -0,0 +1,835 @@
+// A small stack language in the style of Forth, to put second::List to
+// work as a real stack
+//
+//      $ echo '2 3 + 4 * .' | cargo run --bin rpn
+//      20
+//
+// Run a script with `cargo run --bin rpn -- script.rpn`, or pipe one in on
+// stdin.
+//
+// A program is a sequence of words separated by whitespace. Numbers push
+// themselves - integers like 42 or -7, floats like 1.5 or 2e3 - and every
+// other word pops its arguments off the stack and pushes its results:
+//
+//      + - * / mod     arithmetic, on integers if both sides are integers,
+//                      otherwise on floats
+//      negate abs      ( a -- -a ), ( a -- |a| )
+//      int float       convert, truncating a float towards zero
+//      = <> < > <= >=  comparisons, pushing -1 for true and 0 for false
+//      not             ( a -- -1 ) if a is 0, otherwise ( a -- 0 )
+//      dup drop swap over rot pick roll
+//                      the Forth stack words, see second.rs
+//      . .s cr         print and drop the top, print the whole stack, print
+//                      a newline
+//
+// `: name ... ;` defines a new word, and `cond if ... else ... then` runs
+// one branch or the other depending on whether cond is non-zero. The
+// `else` part is optional. `\` comments to the end of the line, and `( ...
+// )` is a comment that can span lines:
+//
+//      : fact ( n -- n! ) dup 1 > if dup 1 - fact * then ;
+//      10 fact .       \ 3628800
+//
+// The dictionary of words is a third::List, most recent first. Defining a
+// word prepends to it, shadowing any older definition with the same name -
+// but the older one is still there, shared by every version of the list
+// from before the new one.
+//
+// A word can call itself, but otherwise only words defined before it. Each
+// call is looked up once, when the word is compiled, and the compiled word
+// keeps an Rc to the definition it found rather than the name. So
+// redefining a word later doesn't change the words already using it:
+//
+//      : two 2 ;
+//      : four two two + ;
+//      : two 3 ;
+//      four .          \ still 4
+//
+// Defining a word with the same name as a builtin replaces the builtin,
+// but because the name means the new word inside its own definition, it
+// can't call the builtin it's replacing.
+//
+// The first error stops the program, and is reported with the line and
+// column of the word that caused it:
+//
+//      script.rpn:3:5: unknown word "fo"
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io::{self, BufWriter, Read, Write};
+use std::process::ExitCode;
+use std::rc::Rc;
+
+use learning_rust_with_entirely_too_many_linked_lists::second::{self, StackUnderflow};
+use learning_rust_with_entirely_too_many_linked_lists::third;
+
+// How deeply words can call each other and ifs can nest - a word
+// recursing forever, or a script of a million nested ifs, is reported as
+// an error rather than overflowing Rust's own stack. Compiling and running
+// both recurse once per level
+const MAX_DEPTH: usize = 1_000;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let (name, source) = match args.as_slice() {
+        [] => ("<stdin>".to_string(), read_stdin()),
+        [path] if path == "-" => ("<stdin>".to_string(), read_stdin()),
+        [path] => (path.clone(), fs::read_to_string(path)),
+        _ => {
+            eprintln!("usage: rpn [FILE]");
+            return ExitCode::from(2);
+        }
+    };
+
+    let source = match source {
+        Ok(source) => source,
+        Err(err) => {
+            eprintln!("rpn: couldn't read {name}: {err}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let mut interpreter = Interpreter::new(BufWriter::new(io::stdout().lock()));
+    let result = interpreter.run(&source);
+
+    // whatever was printed before an error should still come out, and
+    // before the error itself
+    if let Err(err) = interpreter.out.flush() {
+        eprintln!("rpn: failed to write output: {err}");
+        return ExitCode::FAILURE;
+    }
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{name}:{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn read_stdin() -> io::Result<String> {
+    let mut source = String::new();
+
+    io::stdin().read_to_string(&mut source)?;
+
+    Ok(source)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Value {
+    Int(i64),
+    Float(f64),
+}
+
+impl Value {
+    fn is_true(self) -> bool {
+        match self {
+            Value::Int(n) => n != 0,
+            Value::Float(x) => x != 0.0,
+        }
+    }
+
+    fn as_f64(self) -> f64 {
+        match self {
+            Value::Int(n) => n as f64,
+            Value::Float(x) => x,
+        }
+    }
+
+    fn from_bool(b: bool) -> Self {
+        Value::Int(if b { -1 } else { 0 })
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Int(n) => write!(f, "{n}"),
+            // Debug keeps the `.0` on whole floats, so 2.0 doesn't print
+            // as if it were the integer 2
+            Value::Float(x) => write!(f, "{x:?}"),
+        }
+    }
+}
+
+// Where a word is in the source, both counting from 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Pos {
+    line: usize,
+    col: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Token<'a> {
+    text: &'a str,
+    pos: Pos,
+}
+
+// Split the source into words, dropping comments
+fn tokenize(source: &str) -> Result<Vec<Token<'_>>, Error> {
+    let mut tokens = Vec::new();
+    // the start of a `(` comment we're inside of
+    let mut comment: Option<Pos> = None;
+
+    for (line_index, line) in source.lines().enumerate() {
+        for token in words(line, line_index + 1) {
+            if comment.is_some() {
+                if token.text == ")" {
+                    comment = None;
+                }
+                continue;
+            }
+
+            match token.text {
+                "\\" => break,
+                "(" => comment = Some(token.pos),
+                _ => tokens.push(token),
+            }
+        }
+    }
+
+    match comment {
+        Some(pos) => Err(Error {
+            pos,
+            kind: ErrorKind::Unterminated("comment"),
+        }),
+        None => Ok(tokens),
+    }
+}
+
+// The whitespace-separated words of one line, with their columns counted
+// in chars rather than bytes
+fn words(line: &str, line_number: usize) -> impl Iterator<Item = Token<'_>> {
+    let mut chars = line.char_indices().enumerate().peekable();
+
+    std::iter::from_fn(move || {
+        // skip to the start of the next word...
+        while chars.next_if(|(_, (_, c))| c.is_whitespace()).is_some() {}
+
+        let (col, (start, _)) = chars.next()?;
+        let mut end = line.len();
+
+        // ...and then to its end
+        for (_, (i, c)) in chars.by_ref() {
+            if c.is_whitespace() {
+                end = i;
+                break;
+            }
+        }
+
+        Some(Token {
+            text: &line[start..end],
+            pos: Pos {
+                line: line_number,
+                col: col + 1,
+            },
+        })
+    })
+}
+
+fn parse_number(text: &str) -> Option<Value> {
+    if let Ok(n) = text.parse() {
+        return Some(Value::Int(n));
+    }
+
+    // f64's FromStr also takes "inf" and "NaN", which we'd rather were
+    // words, so a float has to have at least one digit in it
+    if text.chars().any(|c| c.is_ascii_digit()) {
+        return text.parse().ok().map(Value::Float);
+    }
+
+    None
+}
+
+// A word compiled down to what it does
+#[derive(Debug)]
+enum Op {
+    Push(Value),
+    Builtin(Builtin, Pos),
+    Call(Rc<Word>, Pos),
+    // a call to the word being defined, which isn't in the dictionary yet
+    Recurse(Pos),
+    If {
+        then: Vec<Op>,
+        otherwise: Vec<Op>,
+        pos: Pos,
+    },
+}
+
+// A word defined with `: name ... ;`
+#[derive(Debug)]
+struct Word {
+    name: String,
+    body: Vec<Op>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Builtin {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Negate,
+    Abs,
+    Int,
+    Float,
+    Eq,
+    Ne,
+    Lt,
+    Gt,
+    Le,
+    Ge,
+    Not,
+    Dup,
+    Drop,
+    Swap,
+    Over,
+    Rot,
+    Pick,
+    Roll,
+    Print,
+    PrintStack,
+    Cr,
+}
+
+impl Builtin {
+    fn from_name(name: &str) -> Option<Self> {
+        use Builtin::*;
+
+        Some(match name {
+            "+" => Add,
+            "-" => Sub,
+            "*" => Mul,
+            "/" => Div,
+            "mod" => Mod,
+            "negate" => Negate,
+            "abs" => Abs,
+            "int" => Int,
+            "float" => Float,
+            "=" => Eq,
+            "<>" => Ne,
+            "<" => Lt,
+            ">" => Gt,
+            "<=" => Le,
+            ">=" => Ge,
+            "not" => Not,
+            "dup" => Dup,
+            "drop" => Drop,
+            "swap" => Swap,
+            "over" => Over,
+            "rot" => Rot,
+            "pick" => Pick,
+            "roll" => Roll,
+            "." => Print,
+            ".s" => PrintStack,
+            "cr" => Cr,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct Error {
+    pos: Pos,
+    kind: ErrorKind,
+}
+
+#[derive(Debug)]
+enum ErrorKind {
+    UnknownWord(String),
+    // a word that only makes sense inside something else, like `then`
+    // without an `if`
+    Unexpected(String),
+    // a `:`, `if` or `(` that's never closed
+    Unterminated(&'static str),
+    MissingName,
+    StackUnderflow(StackUnderflow),
+    DivisionByZero,
+    Overflow,
+    NotAnIndex(Value),
+    TooDeep,
+    Io(io::Error),
+}
+
+impl From<StackUnderflow> for ErrorKind {
+    fn from(err: StackUnderflow) -> Self {
+        ErrorKind::StackUnderflow(err)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: ", self.pos.line, self.pos.col)?;
+
+        match &self.kind {
+            ErrorKind::UnknownWord(word) => write!(f, "unknown word {word:?}"),
+            ErrorKind::Unexpected(word) => write!(f, "unexpected {word:?}"),
+            ErrorKind::Unterminated(what) => write!(f, "unterminated {what}"),
+            ErrorKind::MissingName => write!(f, "expected a name after \":\""),
+            ErrorKind::StackUnderflow(err) => write!(f, "{err}"),
+            ErrorKind::DivisionByZero => write!(f, "division by zero"),
+            ErrorKind::Overflow => write!(f, "integer overflow"),
+            ErrorKind::NotAnIndex(value) => {
+                write!(f, "expected a non-negative integer, found {value}")
+            }
+            ErrorKind::TooDeep => {
+                write!(f, "calls and ifs nested more than {MAX_DEPTH} deep")
+            }
+            ErrorKind::Io(err) => write!(f, "failed to write output: {err}"),
+        }
+    }
+}
+
+struct Interpreter<W> {
+    stack: second::List<Value>,
+    // every word defined so far, most recent first
+    words: third::List<Rc<Word>>,
+    // how many calls and ifs are currently being run, one inside the other
+    depth: usize,
+    out: W,
+}
+
+impl<W: Write> Interpreter<W> {
+    fn new(out: W) -> Self {
+        Self {
+            // a small pool saves an allocation for most pushes, as the
+            // stack goes up and down
+            stack: second::List::with_node_pool(16),
+            words: third::List::new(),
+            depth: 0,
+            out,
+        }
+    }
+
+    // Run a whole program, one top-level word at a time, so everything up
+    // to an error has already happened
+    fn run(&mut self, source: &str) -> Result<(), Error> {
+        let mut tokens = tokenize(source)?.into_iter();
+
+        while let Some(token) = tokens.next() {
+            if token.text == ":" {
+                self.define(token.pos, &mut tokens)?;
+            } else {
+                let op = self.compile(token, &mut tokens, None, 0)?;
+                self.execute(std::slice::from_ref(&op), None)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Compile `: name ... ;` and add it to the dictionary
+    fn define<'a>(
+        &mut self,
+        pos: Pos,
+        tokens: &mut impl Iterator<Item = Token<'a>>,
+    ) -> Result<(), Error> {
+        let name = match tokens.next() {
+            Some(token) if !is_keyword(token.text) && parse_number(token.text).is_none() => {
+                token.text
+            }
+            _ => {
+                return Err(Error {
+                    pos,
+                    kind: ErrorKind::MissingName,
+                })
+            }
+        };
+
+        let (body, end) = self.compile_until(tokens, &[";"], Some(name), 0)?;
+
+        if end.is_none() {
+            return Err(Error {
+                pos,
+                kind: ErrorKind::Unterminated("definition"),
+            });
+        }
+
+        self.words = self.words.prepend(Rc::new(Word {
+            name: name.to_string(),
+            body,
+        }));
+
+        Ok(())
+    }
+
+    // Compile words until one of `ends`, returning the ops and the word
+    // that ended them - or None if the source ran out first
+    fn compile_until<'a>(
+        &self,
+        tokens: &mut impl Iterator<Item = Token<'a>>,
+        ends: &[&str],
+        defining: Option<&str>,
+        nesting: usize,
+    ) -> Result<(Vec<Op>, Option<Token<'a>>), Error> {
+        let mut ops = Vec::new();
+
+        while let Some(token) = tokens.next() {
+            if ends.contains(&token.text) {
+                return Ok((ops, Some(token)));
+            }
+
+            ops.push(self.compile(token, tokens, defining, nesting)?);
+        }
+
+        Ok((ops, None))
+    }
+
+    // Compile a single word - or a whole `if ... then`, which reads ahead
+    // to its `then`. `nesting` is how many ifs the word is inside
+    fn compile<'a>(
+        &self,
+        token: Token<'a>,
+        tokens: &mut impl Iterator<Item = Token<'a>>,
+        defining: Option<&str>,
+        nesting: usize,
+    ) -> Result<Op, Error> {
+        let pos = token.pos;
+
+        if token.text == "if" {
+            if nesting == MAX_DEPTH {
+                return Err(Error {
+                    pos,
+                    kind: ErrorKind::TooDeep,
+                });
+            }
+
+            let nesting = nesting + 1;
+            let (then, end) = self.compile_until(tokens, &["else", "then"], defining, nesting)?;
+
+            let (otherwise, end) = match end {
+                Some(end) if end.text == "else" => {
+                    self.compile_until(tokens, &["then"], defining, nesting)?
+                }
+                end => (Vec::new(), end),
+            };
+
+            if end.is_none() {
+                return Err(Error {
+                    pos,
+                    kind: ErrorKind::Unterminated("if"),
+                });
+            }
+
+            return Ok(Op::If {
+                then,
+                otherwise,
+                pos,
+            });
+        }
+
+        if is_keyword(token.text) {
+            return Err(Error {
+                pos,
+                kind: ErrorKind::Unexpected(token.text.to_string()),
+            });
+        }
+
+        if let Some(value) = parse_number(token.text) {
+            return Ok(Op::Push(value));
+        }
+
+        if defining == Some(token.text) {
+            return Ok(Op::Recurse(pos));
+        }
+
+        // user-defined words come first, so they can shadow the builtins
+        if let Some(word) = self.words.iter().find(|word| word.name == token.text) {
+            return Ok(Op::Call(Rc::clone(word), pos));
+        }
+
+        match Builtin::from_name(token.text) {
+            Some(builtin) => Ok(Op::Builtin(builtin, pos)),
+            None => Err(Error {
+                pos,
+                kind: ErrorKind::UnknownWord(token.text.to_string()),
+            }),
+        }
+    }
+
+    // `current` is the word whose body `ops` is, for Recurse
+    fn execute(&mut self, ops: &[Op], current: Option<&Rc<Word>>) -> Result<(), Error> {
+        for op in ops {
+            match op {
+                Op::Push(value) => self.stack.push(*value),
+                Op::Builtin(builtin, pos) => {
+                    self.builtin(*builtin)
+                        .map_err(|kind| Error { pos: *pos, kind })?;
+                }
+                Op::Call(word, pos) => self.call(word, *pos)?,
+                Op::Recurse(pos) => {
+                    let word = current.expect("Recurse is only compiled inside a definition");
+                    self.call(word, *pos)?;
+                }
+                Op::If {
+                    then,
+                    otherwise,
+                    pos,
+                } => {
+                    let cond = self.pop().map_err(|kind| Error { pos: *pos, kind })?;
+
+                    let branch = if cond.is_true() { then } else { otherwise };
+
+                    self.nested(*pos, branch, current)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn call(&mut self, word: &Rc<Word>, pos: Pos) -> Result<(), Error> {
+        self.nested(pos, &word.body, Some(word))
+    }
+
+    // Execute the body of a call or an if, one level deeper
+    fn nested(&mut self, pos: Pos, ops: &[Op], current: Option<&Rc<Word>>) -> Result<(), Error> {
+        if self.depth == MAX_DEPTH {
+            return Err(Error {
+                pos,
+                kind: ErrorKind::TooDeep,
+            });
+        }
+
+        self.depth += 1;
+        let result = self.execute(ops, current);
+        self.depth -= 1;
+
+        result
+    }
+
+    fn builtin(&mut self, builtin: Builtin) -> Result<(), ErrorKind> {
+        use Builtin::*;
+
+        match builtin {
+            Add | Sub | Mul | Div | Mod => {
+                let (a, b) = self.pop2()?;
+                self.stack.push(arithmetic(builtin, a, b)?);
+            }
+            Eq | Ne | Lt | Gt | Le | Ge => {
+                let (a, b) = self.pop2()?;
+                self.stack.push(Value::from_bool(compare(builtin, a, b)));
+            }
+            Negate => {
+                let value = match self.pop()? {
+                    Value::Int(n) => Value::Int(n.checked_neg().ok_or(ErrorKind::Overflow)?),
+                    Value::Float(x) => Value::Float(-x),
+                };
+                self.stack.push(value);
+            }
+            Abs => {
+                let value = match self.pop()? {
+                    Value::Int(n) => Value::Int(n.checked_abs().ok_or(ErrorKind::Overflow)?),
+                    Value::Float(x) => Value::Float(x.abs()),
+                };
+                self.stack.push(value);
+            }
+            Int => {
+                let value = match self.pop()? {
+                    Value::Float(x) => {
+                        // `as` would saturate, and turn NaN into 0
+                        if x.is_nan() || x < i64::MIN as f64 || x >= i64::MAX as f64 {
+                            return Err(ErrorKind::Overflow);
+                        }
+                        Value::Int(x as i64)
+                    }
+                    int => int,
+                };
+                self.stack.push(value);
+            }
+            Float => {
+                let value = self.pop()?;
+                self.stack.push(Value::Float(value.as_f64()));
+            }
+            Not => {
+                let value = self.pop()?;
+                self.stack.push(Value::from_bool(!value.is_true()));
+            }
+            Dup => self.stack.dup()?,
+            Drop => self.stack.drop_n(1)?,
+            Swap => self.stack.swap()?,
+            Over => self.stack.over()?,
+            Rot => self.stack.rot()?,
+            Pick => {
+                let n = self.pop_index()?;
+                self.stack.pick(n)?;
+            }
+            Roll => {
+                let n = self.pop_index()?;
+                self.stack.roll(n)?;
+            }
+            Print => {
+                let value = self.pop()?;
+                write!(self.out, "{value} ").map_err(ErrorKind::Io)?;
+            }
+            PrintStack => {
+                // Forth prints the depth and then the stack bottom first, so
+                // the top is nearest whatever gets printed next
+                let values: Vec<Value> = self.stack.iter().copied().collect();
+
+                write!(self.out, "<{}> ", values.len()).map_err(ErrorKind::Io)?;
+
+                for value in values.iter().rev() {
+                    write!(self.out, "{value} ").map_err(ErrorKind::Io)?;
+                }
+            }
+            Cr => writeln!(self.out).map_err(ErrorKind::Io)?,
+        }
+
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Result<Value, ErrorKind> {
+        self.stack
+            .pop()
+            .ok_or(ErrorKind::StackUnderflow(StackUnderflow {
+                needed: 1,
+                available: 0,
+            }))
+    }
+
+    // ( a b -- ), checking both are there first so an underflow reports
+    // that two were needed
+    fn pop2(&mut self) -> Result<(Value, Value), ErrorKind> {
+        self.stack.peek_nth(1)?;
+
+        let b = self.pop()?;
+        let a = self.pop()?;
+
+        Ok((a, b))
+    }
+
+    fn pop_index(&mut self) -> Result<usize, ErrorKind> {
+        match self.pop()? {
+            // negative, or too big for a usize on a 32-bit target - either
+            // way not an index
+            Value::Int(n) => usize::try_from(n).map_err(|_| ErrorKind::NotAnIndex(Value::Int(n))),
+            value => Err(ErrorKind::NotAnIndex(value)),
+        }
+    }
+}
+
+// Words that are part of the syntax rather than things to run
+fn is_keyword(text: &str) -> bool {
+    matches!(text, ":" | ";" | "if" | "else" | "then")
+}
+
+fn arithmetic(op: Builtin, a: Value, b: Value) -> Result<Value, ErrorKind> {
+    match (a, b) {
+        (Value::Int(a), Value::Int(b)) => {
+            if matches!(op, Builtin::Div | Builtin::Mod) && b == 0 {
+                return Err(ErrorKind::DivisionByZero);
+            }
+
+            let result = match op {
+                Builtin::Add => a.checked_add(b),
+                Builtin::Sub => a.checked_sub(b),
+                Builtin::Mul => a.checked_mul(b),
+                Builtin::Div => a.checked_div(b),
+                Builtin::Mod => a.checked_rem(b),
+                _ => unreachable!("{op:?} isn't arithmetic"),
+            };
+
+            result.map(Value::Int).ok_or(ErrorKind::Overflow)
+        }
+        // if either side is a float, both are
+        (a, b) => {
+            let (a, b) = (a.as_f64(), b.as_f64());
+
+            Ok(Value::Float(match op {
+                Builtin::Add => a + b,
+                Builtin::Sub => a - b,
+                Builtin::Mul => a * b,
+                Builtin::Div => a / b,
+                Builtin::Mod => a % b,
+                _ => unreachable!("{op:?} isn't arithmetic"),
+            }))
+        }
+    }
+}
+
+fn compare(op: Builtin, a: Value, b: Value) -> bool {
+    // compare integers as integers - not every i64 survives the trip
+    // through f64
+    let ordering = match (a, b) {
+        (Value::Int(a), Value::Int(b)) => Some(a.cmp(&b)),
+        (a, b) => a.as_f64().partial_cmp(&b.as_f64()),
+    };
+
+    // NaN compares as neither less, greater nor equal, so only <> is true
+    match op {
+        Builtin::Eq => ordering.is_some_and(|o| o.is_eq()),
+        Builtin::Ne => !ordering.is_some_and(|o| o.is_eq()),
+        Builtin::Lt => ordering.is_some_and(|o| o.is_lt()),
+        Builtin::Gt => ordering.is_some_and(|o| o.is_gt()),
+        Builtin::Le => ordering.is_some_and(|o| o.is_le()),
+        Builtin::Ge => ordering.is_some_and(|o| o.is_ge()),
+        _ => unreachable!("{op:?} isn't a comparison"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_number, tokenize, Interpreter, Pos, Value};
+
+    fn run(source: &str) -> Result<String, String> {
+        let mut interpreter = Interpreter::new(Vec::new());
+        let result = interpreter.run(source);
+        let out = String::from_utf8(interpreter.out).unwrap();
+
+        result.map(|()| out).map_err(|err| err.to_string())
+    }
+
+    #[test]
+    fn tokens_know_where_they_are() {
+        let tokens = tokenize("1 2\n  é +  \\ a comment\n( spans\nlines ) .").unwrap();
+        let found: Vec<_> = tokens
+            .iter()
+            .map(|t| (t.text, t.pos.line, t.pos.col))
+            .collect();
+
+        assert_eq!(
+            found,
+            [
+                ("1", 1, 1),
+                ("2", 1, 3),
+                // columns count chars, not bytes
+                ("é", 2, 3),
+                ("+", 2, 5),
+                (".", 4, 9)
+            ]
+        );
+    }
+
+    #[test]
+    fn unterminated_comment() {
+        let err = tokenize("1 (\n2").unwrap_err();
+
+        assert_eq!(err.pos, Pos { line: 1, col: 3 });
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(parse_number("-7"), Some(Value::Int(-7)));
+        assert_eq!(parse_number("1.5"), Some(Value::Float(1.5)));
+        assert_eq!(parse_number("2e3"), Some(Value::Float(2000.0)));
+        assert_eq!(parse_number("inf"), None);
+        assert_eq!(parse_number("dup"), None);
+    }
+
+    #[test]
+    fn output_before_an_error_is_kept() {
+        let mut interpreter = Interpreter::new(Vec::new());
+
+        assert!(interpreter.run("1 . 2 . oops").is_err());
+        assert_eq!(interpreter.out, b"1 2 ");
+    }
+
+    #[test]
+    fn else_without_then() {
+        assert_eq!(run("1 if 2 else 3").unwrap_err(), "1:3: unterminated if");
+    }
+}
@@ -0,0 +1,617 @@
+// A circular doubly-linked list - the tail links back round to the head
+//
+// There's no fixed first or last element in a ring, just a cursor called
+// "current", which is handy for round-robin scheduling:
+//  - current() is the element whose turn it is
+//  - advance() hands the turn to the next element
+//  - rotate_forward(n) / rotate_backward(n) move the cursor n places
+//  - push_back adds an element at the end of the round, i.e. just before
+//      current, so it's the last to get a turn
+//
+// With Rc links as in fourth::List a ring would be an Rc cycle that never
+// gets freed on its own. We use raw pointers instead, so the list owns its
+// nodes outright, but the cycle still matters when dropping: there's no
+// None at the end to stop at, so Drop has to count its way round.
+//
+// The invariants the unsafe code below relies on:
+//  - every node came from Box::leak and is freed exactly once with
+//      Box::from_raw, when it's removed or the list is dropped
+//  - every node's next and prev point to live nodes in the same ring,
+//      with node.next.prev == node - a ring of one points to itself
+//  - current is None exactly when len == 0
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use core::fmt;
+use core::marker::PhantomData;
+use core::mem;
+use core::ptr::NonNull;
+use core::str::FromStr;
+
+use crate::dot;
+use crate::text::{self, ParseListError};
+use crate::traits::Queue;
+
+struct Node<T> {
+    elem: T,
+    next: NonNull<Node<T>>,
+    prev: NonNull<Node<T>>,
+}
+
+pub struct List<T> {
+    current: Option<NonNull<Node<T>>>,
+    len: usize,
+    // we own Boxed nodes, as far as the drop checker is concerned
+    _owns: PhantomData<Box<Node<T>>>,
+}
+
+unsafe impl<T: Send> Send for List<T> {}
+unsafe impl<T: Sync> Sync for List<T> {}
+
+pub struct IntoIter<T>(List<T>);
+
+// Visits each element once, going round the ring from wherever it started
+pub struct Iter<'a, T> {
+    next: Option<NonNull<Node<T>>>,
+    remaining: usize,
+    _marker: PhantomData<&'a T>,
+}
+
+pub struct IterMut<'a, T> {
+    next: Option<NonNull<Node<T>>>,
+    remaining: usize,
+    _marker: PhantomData<&'a mut T>,
+}
+
+impl<T> List<T> {
+    pub fn new() -> Self {
+        Self {
+            current: None,
+            len: 0,
+            _owns: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn current(&self) -> Option<&T> {
+        // SAFETY: current is a live node, borrowed for as long as self is
+        self.current.map(|node| unsafe { &(*node.as_ptr()).elem })
+    }
+
+    pub fn current_mut(&mut self) -> Option<&mut T> {
+        // SAFETY: as above, and &mut self makes the reference unique
+        self.current
+            .map(|node| unsafe { &mut (*node.as_ptr()).elem })
+    }
+
+    // Move current on to the next element, returning the new current
+    pub fn advance(&mut self) -> Option<&T> {
+        self.rotate_forward(1);
+        self.current()
+    }
+
+    // Move current n places forward round the ring
+    //
+    // Going all the way round gets us back where we started, so we only
+    // ever need to take n % len steps - and if that's more than half way
+    // round, it's quicker to go backwards
+    pub fn rotate_forward(&mut self, n: usize) {
+        if self.len == 0 {
+            return;
+        }
+
+        let n = n % self.len;
+
+        if n > self.len / 2 {
+            self.step_backward(self.len - n);
+        } else {
+            self.step_forward(n);
+        }
+    }
+
+    pub fn rotate_backward(&mut self, n: usize) {
+        if self.len == 0 {
+            return;
+        }
+
+        let n = n % self.len;
+
+        if n > self.len / 2 {
+            self.step_forward(self.len - n);
+        } else {
+            self.step_backward(n);
+        }
+    }
+
+    fn step_forward(&mut self, n: usize) {
+        for _ in 0..n {
+            // SAFETY: current is a live node, and so is its next
+            self.current = self.current.map(|node| unsafe { node.as_ref().next });
+        }
+    }
+
+    fn step_backward(&mut self, n: usize) {
+        for _ in 0..n {
+            // SAFETY: current is a live node, and so is its prev
+            self.current = self.current.map(|node| unsafe { node.as_ref().prev });
+        }
+    }
+
+    // Insert an element straight after current, so that it's next in line.
+    // In an empty list the new element becomes current
+    pub fn insert_after_current(&mut self, elem: T) {
+        match self.current {
+            Some(current) => {
+                // SAFETY: current is live, so its next is too
+                let next = unsafe { current.as_ref().next };
+
+                self.link_between(elem, current, next);
+            }
+            None => self.link_alone(elem),
+        }
+    }
+
+    // Add an element at the end of the round, just before current, so
+    // that it's the last one to become current
+    pub fn push_back(&mut self, elem: T) {
+        match self.current {
+            Some(current) => {
+                // SAFETY: current is live, so its prev is too
+                let prev = unsafe { current.as_ref().prev };
+
+                self.link_between(elem, prev, current);
+            }
+            None => self.link_alone(elem),
+        }
+    }
+
+    // Remove the current element, and move current on to the next one
+    pub fn remove_current(&mut self) -> Option<T> {
+        self.current.map(|node| {
+            // SAFETY: node is live and came from Box::leak. Once its
+            // neighbours are linked to each other, nothing points to it
+            unsafe {
+                let node = Box::from_raw(node.as_ptr());
+
+                if self.len == 1 {
+                    self.current = None;
+                } else {
+                    (*node.prev.as_ptr()).next = node.next;
+                    (*node.next.as_ptr()).prev = node.prev;
+                    self.current = Some(node.next);
+                }
+
+                self.len -= 1;
+
+                node.elem
+            }
+        })
+    }
+
+    // Visit every element once, starting from current
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.iter_from(0)
+    }
+
+    // Visit every element once, starting `offset` places after current
+    pub fn iter_from(&self, offset: usize) -> Iter<'_, T> {
+        Iter {
+            next: self.nth_node(offset),
+            remaining: self.len,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            next: self.current,
+            remaining: self.len,
+            _marker: PhantomData,
+        }
+    }
+
+    // The node graph in Graphviz's DOT format - see dot.rs. The ring is
+    // drawn starting from current, and the last node's next edge closes it
+    pub fn to_dot(&self) -> String
+    where
+        T: fmt::Debug,
+    {
+        let mut graph = dot::Graph::new();
+
+        graph.root("list", "circular::List");
+
+        if let Some(current) = self.current {
+            let (id, _) = graph.id(current.as_ptr());
+
+            graph.edge("list", id, "current");
+        }
+
+        let mut next = self.current;
+
+        for _ in 0..self.len {
+            let Some(node) = next else { break };
+            let (id, _) = graph.id(node.as_ptr());
+            // SAFETY: every node in the ring is live, and &self means
+            // nothing can change it while we look
+            let node = unsafe { node.as_ref() };
+            let (next_id, _) = graph.id(node.next.as_ptr());
+            let (prev_id, _) = graph.id(node.prev.as_ptr());
+
+            graph.node(id, &format!("{:?}", node.elem));
+            graph.edge(id, next_id, "next");
+            graph.edge(id, prev_id, "prev");
+
+            next = Some(node.next);
+        }
+
+        graph.finish()
+    }
+
+    fn nth_node(&self, n: usize) -> Option<NonNull<Node<T>>> {
+        let mut node = self.current?;
+
+        for _ in 0..n % self.len {
+            // SAFETY: node is live, and so is its next
+            node = unsafe { node.as_ref().next };
+        }
+
+        Some(node)
+    }
+
+    fn link_alone(&mut self, elem: T) {
+        // a ring of one, where the node is its own neighbour both ways
+        let node = NonNull::from(Box::leak(Box::new(Node {
+            elem,
+            next: NonNull::dangling(),
+            prev: NonNull::dangling(),
+        })));
+
+        // SAFETY: node has just been created
+        unsafe {
+            (*node.as_ptr()).next = node;
+            (*node.as_ptr()).prev = node;
+        }
+
+        self.current = Some(node);
+        self.len += 1;
+    }
+
+    fn link_between(&mut self, elem: T, prev: NonNull<Node<T>>, next: NonNull<Node<T>>) {
+        let node = NonNull::from(Box::leak(Box::new(Node { elem, next, prev })));
+
+        // SAFETY: prev and next are live neighbours in the ring. In a ring
+        // of one they're the same node, which is fine as we only write to
+        // one field of each
+        unsafe {
+            (*prev.as_ptr()).next = node;
+            (*next.as_ptr()).prev = node;
+        }
+
+        self.len += 1;
+    }
+
+    // Following .next until we hit the end, as second::List's Drop does,
+    // would go round forever - a ring has no end. So we count instead, and
+    // free exactly len nodes
+    //
+    // current and len move past each node before it's freed, so if an
+    // element's drop panics, Drop's guard can pick up where we left off
+    fn free_nodes(&mut self) {
+        while let Some(node) = self.current {
+            // SAFETY: each of the len nodes is visited, and freed, exactly
+            // once
+            let node = unsafe { Box::from_raw(node.as_ptr()) };
+
+            self.len -= 1;
+            self.current = if self.len == 0 { None } else { Some(node.next) };
+        }
+    }
+}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// push_back puts an element just behind current, and remove_current takes
+// current itself - so current is the front of the queue
+impl<T> Queue<T> for List<T> {
+    fn push_back(&mut self, elem: T) {
+        List::push_back(self, elem);
+    }
+
+    fn pop_front(&mut self) -> Option<T> {
+        self.remove_current()
+    }
+}
+
+impl<T> Drop for List<T> {
+    fn drop(&mut self) {
+        // If an element's drop panics, the guard carries on freeing the
+        // rest of the ring while the panic unwinds, as in second::List's
+        // Drop
+        struct DropGuard<'a, T>(&'a mut List<T>);
+
+        impl<T> Drop for DropGuard<'_, T> {
+            fn drop(&mut self) {
+                self.0.free_nodes();
+            }
+        }
+
+        let guard = DropGuard(self);
+
+        guard.0.free_nodes();
+        mem::forget(guard);
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for List<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+// Written once round the ring, starting from current
+impl<T: fmt::Display> fmt::Display for List<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        text::write_list(f, self.iter())
+    }
+}
+
+// The first element parsed becomes current, and the rest follow it round
+// the ring in order
+impl<T: FromStr> FromStr for List<T> {
+    type Err = ParseListError<T::Err>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut list = Self::new();
+
+        text::parse_list(s)?
+            .into_iter()
+            .for_each(|elem| list.push_back(elem));
+
+        Ok(list)
+    }
+}
+
+impl<T> IntoIterator for List<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self)
+    }
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.remove_current()
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // counting down is what stops us going round a second time
+        if self.remaining == 0 {
+            return None;
+        }
+
+        self.next.map(|node| {
+            // SAFETY: node is live, and the list is borrowed for 'a
+            let node = unsafe { &*node.as_ptr() };
+
+            self.next = Some(node.next);
+            self.remaining -= 1;
+
+            &node.elem
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        self.next.map(|node| {
+            // SAFETY: node is live, and each node is yielded once, so the
+            // &mut references never alias. We only read .next through the
+            // raw pointer rather than making a &mut Node
+            unsafe {
+                self.next = Some((*node.as_ptr()).next);
+                self.remaining -= 1;
+
+                &mut (*node.as_ptr()).elem
+            }
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::List;
+    use crate::test_utils::{panics, DropCounter};
+    use std::rc::Rc;
+
+    crate::conformance_tests!(Queue, queue_conformance, List::new());
+
+    fn from_iter<I: IntoIterator<Item = i32>>(xs: I) -> List<i32> {
+        let mut list = List::new();
+
+        xs.into_iter().for_each(|x| list.push_back(x));
+
+        list
+    }
+
+    #[test]
+    fn push_back_keeps_order() {
+        let list = from_iter(0..4);
+
+        assert_eq!(list.current(), Some(&0));
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn advance_wraps_around() {
+        let mut list = from_iter(0..3);
+
+        assert_eq!(list.advance(), Some(&1));
+        assert_eq!(list.advance(), Some(&2));
+        assert_eq!(list.advance(), Some(&0));
+
+        let mut empty: List<i32> = List::new();
+
+        assert_eq!(empty.advance(), None);
+    }
+
+    #[test]
+    fn rotate() {
+        let mut list = from_iter(0..5);
+
+        list.rotate_forward(2);
+        assert_eq!(list.current(), Some(&2));
+
+        list.rotate_forward(4);
+        assert_eq!(list.current(), Some(&1));
+
+        list.rotate_backward(3);
+        assert_eq!(list.current(), Some(&3));
+
+        list.rotate_backward(11);
+        assert_eq!(list.current(), Some(&2));
+
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [2, 3, 4, 0, 1]);
+    }
+
+    #[test]
+    fn insert_after_current() {
+        let mut list = List::new();
+
+        list.insert_after_current(0);
+        list.insert_after_current(2);
+        list.insert_after_current(1);
+
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [0, 1, 2]);
+
+        list.advance();
+        list.insert_after_current(10);
+
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [1, 10, 2, 0]);
+    }
+
+    #[test]
+    fn remove_current() {
+        let mut list = from_iter(0..4);
+
+        list.advance();
+
+        assert_eq!(list.remove_current(), Some(1));
+        assert_eq!(list.current(), Some(&2));
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [2, 3, 0]);
+
+        assert_eq!(list.remove_current(), Some(2));
+        assert_eq!(list.remove_current(), Some(3));
+        assert_eq!(list.remove_current(), Some(0));
+        assert_eq!(list.remove_current(), None);
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn iter_from_any_position() {
+        let list = from_iter(0..4);
+
+        for start in 0..8 {
+            let visited: Vec<_> = list.iter_from(start).copied().collect();
+            let expected: Vec<_> = (0..4).map(|x| (x + start as i32) % 4).collect();
+
+            assert_eq!(visited, expected);
+        }
+    }
+
+    #[test]
+    fn iter_mut() {
+        let mut list = from_iter(0..4);
+
+        list.rotate_forward(1);
+        list.iter_mut().for_each(|x| *x *= 10);
+
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [10, 20, 30, 0]);
+    }
+
+    #[test]
+    fn into_iter() {
+        let mut list = from_iter(0..4);
+
+        list.rotate_backward(1);
+
+        assert_eq!(list.into_iter().collect::<Vec<_>>(), [3, 0, 1, 2]);
+    }
+
+    #[test]
+    fn drop_frees_the_whole_ring() {
+        let value = Rc::new(());
+        let mut list = List::new();
+
+        (0..5).for_each(|_| list.push_back(Rc::clone(&value)));
+        list.rotate_forward(3);
+        list.remove_current();
+
+        assert_eq!(Rc::strong_count(&value), 5);
+
+        drop(list);
+
+        assert_eq!(Rc::strong_count(&value), 1);
+    }
+
+    #[test]
+    fn display_and_parse() {
+        let mut list: List<i32> = "[1, 2, 3]".parse().unwrap();
+
+        assert_eq!(list.current(), Some(&1));
+
+        list.advance();
+
+        // starting from current
+        assert_eq!(list.to_string(), "[2, 3, 1]");
+        assert_eq!(List::<i32>::new().to_string(), "[]");
+    }
+
+    #[test]
+    fn drop_carries_on_after_a_panic() {
+        let counter = DropCounter::new();
+        let mut list = List::new();
+
+        list.push_back(counter.tracked());
+        list.push_back(counter.panicking());
+        list.push_back(counter.tracked());
+        list.push_back(counter.tracked());
+        list.rotate_forward(3);
+
+        assert!(panics(|| drop(list)));
+
+        counter.assert_all_dropped_once();
+    }
+}
@@ -0,0 +1,143 @@
+// Graphviz DOT output shared by every list's to_dot
+//
+// When a list misbehaves - third sharing less than it should, fourth
+// leaking a cycle - it helps to look at the actual node graph rather than
+// the elements. Each list draws itself the same way:
+//  - a plaintext vertex for the List struct itself, with edges to the
+//      nodes it points at, labelled head, tail, current etc.
+//  - one box per node, labelled with its element, plus its reference
+//      counts if the node is reference-counted
+//  - an edge for each link between nodes, labelled next or prev
+//
+// Nodes are identified by address, so two links to the same node - or two
+// lists sharing a node - point at the same box. Addresses change from run
+// to run, though, so each node is named after the order it was first seen
+// in: n0, n1, and so on. That keeps the output the same for the same
+// shape of list, and lets tests compare it against golden files.
+//
+// Render the output with e.g.
+//
+//      dot -Tsvg list.dot > list.svg
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use core::fmt::{self, Write};
+
+pub(crate) struct Graph {
+    out: String,
+    // the name given to each node address seen so far
+    ids: BTreeMap<*const (), usize>,
+}
+
+// The name of a node's vertex, n0, n1, ...
+#[derive(Clone, Copy)]
+pub(crate) struct NodeId(usize);
+
+impl fmt::Display for NodeId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "n{}", self.0)
+    }
+}
+
+impl Graph {
+    pub fn new() -> Self {
+        let mut graph = Self {
+            out: String::new(),
+            ids: BTreeMap::new(),
+        };
+
+        graph.line(format_args!("digraph list {{"));
+        graph.line(format_args!("    node [shape=box];"));
+
+        graph
+    }
+
+    // The name for the node at `ptr`, and whether this is the first time
+    // it's been seen - a walk over shared or cyclic nodes stops once it
+    // reaches a node it has already drawn
+    pub fn id<P>(&mut self, ptr: *const P) -> (NodeId, bool) {
+        let next = self.ids.len();
+        let id = *self.ids.entry(ptr.cast()).or_insert(next);
+
+        (NodeId(id), id == next)
+    }
+
+    // A vertex for something that isn't a node, e.g. the List itself
+    pub fn root(&mut self, name: &str, label: &str) {
+        self.line(format_args!(
+            "    {name} [shape=plaintext, label=\"{}\"];",
+            Escaped(label)
+        ));
+    }
+
+    pub fn node(&mut self, id: NodeId, label: &str) {
+        self.line(format_args!("    {id} [label=\"{}\"];", Escaped(label)));
+    }
+
+    pub fn edge(&mut self, from: impl fmt::Display, to: NodeId, label: &str) {
+        self.line(format_args!("    {from} -> {to} [label=\"{label}\"];"));
+    }
+
+    pub fn finish(mut self) -> String {
+        self.line(format_args!("}}"));
+
+        self.out
+    }
+
+    fn line(&mut self, args: fmt::Arguments<'_>) {
+        // writing to a String can't fail
+        let _ = self.out.write_fmt(args);
+        self.out.push('\n');
+    }
+}
+
+// Label text with quotes and backslashes escaped, and newlines turned into
+// DOT's \n
+struct Escaped<'a>(&'a str);
+
+impl fmt::Display for Escaped<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for c in self.0.chars() {
+            match c {
+                '"' => f.write_str("\\\"")?,
+                '\\' => f.write_str("\\\\")?,
+                '\n' => f.write_str("\\n")?,
+                c => f.write_char(c)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Graph;
+
+    #[test]
+    fn same_address_same_id() {
+        let (a, b) = (1, 2);
+        let mut graph = Graph::new();
+
+        let (first, new) = graph.id(&a);
+        assert!(new);
+        assert_eq!(first.to_string(), "n0");
+
+        let (second, new) = graph.id(&b);
+        assert!(new);
+        assert_eq!(second.to_string(), "n1");
+
+        let (again, new) = graph.id(&a);
+        assert!(!new);
+        assert_eq!(again.to_string(), "n0");
+    }
+
+    #[test]
+    fn escapes_labels() {
+        let mut graph = Graph::new();
+        let (id, _) = graph.id(&0);
+
+        graph.node(id, "\"a\\b\"\nc");
+
+        assert!(graph.finish().contains(r#"n0 [label="\"a\\b\"\nc"];"#));
+    }
+}
@@ -1,7 +1,13 @@
-/// List attempt 2 - using a reference without ownership for working with
-/// the tail of the list
-///
-/// Contrast this implementation with ./fifth_attempt_1.rs
+//! List attempt 2 - using a reference without ownership for working with
+//! the tail of the list
+//!
+//! Contrast this implementation with ./fifth_attempt_1.rs
+
+use alloc::boxed::Box;
+use core::fmt;
+use core::iter;
+
+use crate::text;
 
 pub struct List<'a, T> {
     head: Link<T>,
@@ -62,6 +68,17 @@ impl<'a, T> List<'a, T> {
     // We can call .push once, before there is a mutable reference to self. After
     // this, we can no longer call push or pop because both methods require mutable
     // access to self - we've locked our struct!
+    //
+    /// ```compile_fail
+    /// use learning_rust_with_entirely_too_many_linked_lists::fifth_attempt_2::List;
+    ///
+    /// let mut list = List::new();
+    ///
+    /// list.push(1);
+    /// list.push(2);
+    ///
+    /// assert_eq!(list.pop(), Some(1));
+    /// ```
     pub fn push(&'a mut self, elem: T) {
         let new_tail_node = Box::new(Node { elem, next: None });
 
@@ -101,6 +118,18 @@ impl<'a, T> List<'a, T> {
     }
 }
 
+// Written front to back, the order .pop returns them in
+//
+// There's no FromStr - building a list means calling .push more than
+// once, which is exactly what this attempt can't do
+impl<'a, T: fmt::Display> fmt::Display for List<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let elems = iter::successors(self.head.as_deref(), |node| node.next.as_deref());
+
+        text::write_list(f, elems.map(|node| &node.elem))
+    }
+}
+
 impl<'a, T> Default for List<'a, T> {
     fn default() -> Self {
         List {
@@ -110,29 +139,14 @@ impl<'a, T> Default for List<'a, T> {
     }
 }
 
-#[cfg(test)]
-mod test {
-    use super::List;
-
-    #[test]
-    fn basics() {
-        let mut list = List::new();
-
-        assert_eq!(list.pop(), None);
-
-        list.push(1);
-        list.push(2);
-        list.push(3);
-
-        assert_eq!(list.pop(), Some(1));
-        assert_eq!(list.pop(), Some(2));
-        assert_eq!(list.pop(), Some(3));
-
-        list.push(4);
-        list.push(5);
-
-        assert_eq!(list.pop(), Some(4));
-        assert_eq!(list.pop(), Some(5));
-        assert_eq!(list.pop(), None);
-    }
-}
+// The test that used to live here never compiled - every call to .push
+// borrows the list mutably for the rest of its life, so the second .push
+// (or any .pop) is rejected by the borrow checker:
+//
+//      let mut list = List::new();
+//
+//      list.push(1);
+//      list.push(2); // error[E0499]: cannot borrow `list` as mutable more than once
+//
+// It now lives on .push as a `compile_fail` doc test, so `cargo test` both
+// builds and asserts that this implementation locks itself up
@@ -1,4 +1,15 @@
-use std::mem;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use core::fmt;
+use core::iter;
+use core::mem;
+use core::num::ParseIntError;
+use core::str::FromStr;
+
+use crate::dot;
+use crate::text::{self, ParseListError};
+use crate::traits::Stack;
 
 // invalid... a size cannot be determined for a recursive type
 //pub enum List {
@@ -156,6 +167,31 @@ impl List {
         // return type is inferred... in this case, None
         //unimplemented!()
     }
+
+    // The node graph in Graphviz's DOT format - see dot.rs
+    pub fn to_dot(&self) -> String {
+        let mut graph = dot::Graph::new();
+        let mut from = None;
+        let mut link = &self.head;
+
+        graph.root("list", "first::List");
+
+        while let Link::More(node) = link {
+            let (id, _) = graph.id(&**node);
+
+            graph.node(id, &format!("{}", node.elem));
+
+            match from {
+                Some(from) => graph.edge(from, id, "next"),
+                None => graph.edge("list", id, "head"),
+            }
+
+            from = Some(id);
+            link = &node.next;
+        }
+
+        graph.finish()
+    }
 }
 
 impl Default for List {
@@ -164,6 +200,50 @@ impl Default for List {
     }
 }
 
+impl Stack<i32> for List {
+    fn push(&mut self, elem: i32) {
+        List::push(self, elem);
+    }
+
+    fn pop(&mut self) -> Option<i32> {
+        List::pop(self)
+    }
+}
+
+// Written top first, the order .pop would return them in
+//
+// There's no .iter on this list yet, so we follow the links ourselves
+impl fmt::Display for List {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn node_of(link: &Link) -> Option<&Node> {
+            match link {
+                Link::Empty => None,
+                Link::More(node) => Some(node),
+            }
+        }
+
+        let elems = iter::successors(node_of(&self.head), |node| node_of(&node.next));
+
+        text::write_list(f, elems.map(|node| node.elem))
+    }
+}
+
+impl FromStr for List {
+    type Err = ParseListError<ParseIntError>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut list = List::new();
+
+        // push the bottom of the stack first
+        text::parse_list(s)?
+            .into_iter()
+            .rev()
+            .for_each(|elem| list.push(elem));
+
+        Ok(list)
+    }
+}
+
 // only compile the `test` module when running tests
 #[cfg(test)]
 // hide our tests in a non-public `test` module
@@ -173,6 +253,8 @@ mod test {
     // unused
     use super::List;
 
+    crate::conformance_tests!(Stack, stack_conformance, List::new());
+
     // run this function when `cargo test` is run
     #[test]
     fn basics() {
@@ -181,14 +263,14 @@ mod test {
         // popping empty list returns None
         assert_eq!(list.pop(), None);
 
-        [1, 2, 3].map(|x| list.push(x));
+        [1, 2, 3].into_iter().for_each(|x| list.push(x));
 
         // pops work
         assert_eq!(list.pop(), Some(3));
         assert_eq!(list.pop(), Some(2));
 
         // push some more
-        [4, 5].map(|x| list.push(x));
+        [4, 5].into_iter().for_each(|x| list.push(x));
 
         // pop until we stop
         assert_eq!(list.pop(), Some(5));
@@ -1,57 +1,83 @@
-use std::cell::{Ref, RefCell, RefMut};
-use std::iter::DoubleEndedIterator;
-use std::rc::Rc;
-
-type Link<T> = Option<Rc<RefCell<Node<T>>>>;
-
-#[derive(Debug)]
-struct Node<T> {
+use alloc::collections::BTreeSet;
+use alloc::format;
+use alloc::string::String;
+use core::cell::{Ref, RefCell, RefMut};
+use core::convert::Infallible;
+use core::error::Error;
+use core::fmt;
+use core::iter::DoubleEndedIterator;
+use core::mem;
+use core::str::FromStr;
+
+use crate::dot;
+use crate::node_alloc::{Global, NodeAllocator, NodeRc};
+use crate::text::{self, ParseListError};
+use crate::traits::{Deque, Queue, Stack};
+
+// NodeRc is our stand-in for Rc<T, A>, which std only has behind the
+// unstable `allocator_api` feature. It behaves like Rc - .clone bumps the
+// count, NodeRc::try_unwrap only succeeds for the last reference - but the
+// node is allocated by A
+type Link<T, A> = Option<NodeRc<RefCell<Node<T, A>>, A>>;
+
+struct Node<T, A: NodeAllocator> {
     elem: T,
-    next: Link<T>,
-    prev: Link<T>,
+    next: Link<T, A>,
+    prev: Link<T, A>,
 }
 
-impl<T> Node<T> {
-    pub fn new(elem: T) -> Rc<RefCell<Self>> {
-        Rc::new(RefCell::new(Node {
-            elem,
-            next: None,
-            prev: None,
-        }))
+impl<T, A: NodeAllocator> Node<T, A> {
+    pub fn new(elem: T, alloc: A) -> NodeRc<RefCell<Self>, A> {
+        NodeRc::new_in(
+            RefCell::new(Node {
+                elem,
+                next: None,
+                prev: None,
+            }),
+            alloc,
+        )
     }
 }
 
-#[derive(Debug)]
-pub struct List<T> {
-    head: Link<T>,
-    tail: Link<T>,
+pub struct List<T, A: NodeAllocator = Global> {
+    head: Link<T, A>,
+    tail: Link<T, A>,
+    alloc: A,
 }
 
-pub struct IntoIter<T>(List<T>);
+pub struct IntoIter<T, A: NodeAllocator = Global>(List<T, A>);
 
 impl<T> List<T> {
     pub fn new() -> Self {
+        Self::new_in(Global)
+    }
+}
+
+impl<T, A: NodeAllocator> List<T, A> {
+    // Create a list whose nodes are allocated by `alloc`
+    pub fn new_in(alloc: A) -> Self {
         Self {
             head: None,
             tail: None,
+            alloc,
         }
     }
 
-    pub fn into_iter(self) -> IntoIter<T> {
-        IntoIter(self)
+    pub fn allocator(&self) -> &A {
+        &self.alloc
     }
 
     pub fn push_front(&mut self, elem: T) {
-        let node = Node::new(elem);
+        let node = Node::new(elem, self.alloc.clone());
 
         // using the "if let" pattern
         //if let Some(old_head) = self.head.take() {
-        //    self.head = Some(Rc::clone(&node));
-        //    node.borrow_mut().next = Some(Rc::clone(&old_head));
-        //    old_head.borrow_mut().prev = Some(Rc::clone(&node));
+        //    self.head = Some(NodeRc::clone(&node));
+        //    node.borrow_mut().next = Some(NodeRc::clone(&old_head));
+        //    old_head.borrow_mut().prev = Some(NodeRc::clone(&node));
         //} else {
-        //    self.head = Some(Rc::clone(&node));
-        //    self.tail = Some(Rc::clone(&node));
+        //    self.head = Some(NodeRc::clone(&node));
+        //    self.tail = Some(NodeRc::clone(&node));
         //}
 
         // using a more idiomatic "match" pattern
@@ -60,21 +86,34 @@ impl<T> List<T> {
             // head node and the old head node
             Some(old_head) => {
                 // set .next on the new node to the old head's node
-                node.borrow_mut().next = Some(Rc::clone(&old_head));
+                node.borrow_mut().next = Some(NodeRc::clone(&old_head));
                 // set .prev on the old head's node to the new node
-                old_head.borrow_mut().prev = Some(Rc::clone(&node));
+                old_head.borrow_mut().prev = Some(NodeRc::clone(&node));
             }
             // otherwise, point the tail to the new node
             None => {
-                self.tail = Some(Rc::clone(&node));
+                self.tail = Some(NodeRc::clone(&node));
             }
         }
 
         // set the new node as head
-        self.head = Some(Rc::clone(&node));
+        self.head = Some(node);
+
+        self.after_mutation();
     }
 
     pub fn pop_front(&mut self) -> Option<T> {
+        let elem = self.unlink_front();
+
+        self.after_mutation();
+
+        elem
+    }
+
+    // pop_front without the check after it, for Drop - dropping a list
+    // that's already broken should free what it can, not panic, which
+    // would abort the program if it's already unwinding
+    fn unlink_front(&mut self) -> Option<T> {
         // take the old head, setting it to None
         self.head
             .take()
@@ -84,7 +123,7 @@ impl<T> List<T> {
                     // if there is a node, then...
                     Some(next_node) => {
                         // point self.head to the next node of the old node
-                        self.head = Some(Rc::clone(&next_node));
+                        self.head = Some(NodeRc::clone(&next_node));
 
                         // take .prev on the next node, removing the reference
                         next_node.borrow_mut().prev.take()
@@ -94,7 +133,7 @@ impl<T> List<T> {
                     None => self.tail.take(),
                 };
 
-                Rc::try_unwrap(old_head)
+                NodeRc::try_unwrap(old_head)
                     // convert from Result<T, E> to Option<T>
                     .ok()
                     // convert from RefCell<T> to T
@@ -105,28 +144,30 @@ impl<T> List<T> {
     }
 
     pub fn push_back(&mut self, elem: T) {
-        let node = Node::new(elem);
+        let node = Node::new(elem, self.alloc.clone());
 
         match self.tail.take() {
             Some(old_tail) => {
-                node.borrow_mut().prev = Some(Rc::clone(&old_tail));
-                old_tail.borrow_mut().next = Some(Rc::clone(&node));
+                node.borrow_mut().prev = Some(NodeRc::clone(&old_tail));
+                old_tail.borrow_mut().next = Some(NodeRc::clone(&node));
             }
             None => {
-                self.head = Some(Rc::clone(&node));
+                self.head = Some(NodeRc::clone(&node));
             }
         }
 
-        self.tail = Some(Rc::clone(&node));
+        self.tail = Some(node);
+
+        self.after_mutation();
     }
 
     pub fn pop_back(&mut self) -> Option<T> {
-        self.tail.take().and_then(|old_node| {
+        let elem = self.tail.take().and_then(|old_node| {
             let prev_node = old_node.borrow_mut().prev.take();
 
             match prev_node {
                 Some(node) => {
-                    self.tail = Some(Rc::clone(&node));
+                    self.tail = Some(NodeRc::clone(&node));
                     node.borrow_mut().next.take();
                 }
                 None => {
@@ -134,14 +175,18 @@ impl<T> List<T> {
                 }
             };
 
-            Rc::try_unwrap(old_node)
+            NodeRc::try_unwrap(old_node)
                 .ok()
                 .map(|ref_cell| ref_cell.into_inner())
                 .map(|node| node.elem)
-        })
+        });
+
+        self.after_mutation();
+
+        elem
     }
 
-    pub fn peek_front(&self) -> Option<Ref<T>> {
+    pub fn peek_front(&self) -> Option<Ref<'_, T>> {
         self.head
             // don't consume the head - get a reference to its value
             .as_ref()
@@ -168,7 +213,7 @@ impl<T> List<T> {
             })
     }
 
-    pub fn peek_front_mut(&mut self) -> Option<RefMut<T>> {
+    pub fn peek_front_mut(&mut self) -> Option<RefMut<'_, T>> {
         self.head.as_deref().map(|x| {
             let node_ref = x.borrow_mut();
 
@@ -176,28 +221,370 @@ impl<T> List<T> {
         })
     }
 
-    pub fn peek_back(&self) -> Option<Ref<T>> {
+    pub fn peek_back(&self) -> Option<Ref<'_, T>> {
         self.tail
             .as_ref()
             .map(|ref_cell| Ref::map(ref_cell.borrow(), |node| &node.elem))
     }
 
-    pub fn peek_back_mut(&mut self) -> Option<RefMut<T>> {
+    pub fn peek_back_mut(&mut self) -> Option<RefMut<'_, T>> {
         self.tail.as_deref().map(|cell| {
             let node_ref = cell.borrow_mut();
 
             RefMut::map(node_ref, |node| &mut node.elem)
         })
     }
+
+    // The node graph in Graphviz's DOT format - see dot.rs
+    //
+    // Each node shows its strong count. A node in the middle of a healthy
+    // list has two - one from each neighbour - and so do the ends, counting
+    // head and tail. NodeRc has no Weak, so there's no weak count to show.
+    // A node that's reached again, e.g. through a cycle, ends the walk
+    pub fn to_dot(&self) -> String
+    where
+        T: fmt::Debug,
+    {
+        let mut graph = dot::Graph::new();
+        let mut from = None;
+        let mut next = self.head.clone();
+
+        graph.root("list", "fourth::List");
+
+        while let Some(node) = next {
+            let (id, new) = graph.id(NodeRc::as_ptr(&node));
+
+            match from {
+                Some(from) => graph.edge(from, id, "next"),
+                None => graph.edge("list", id, "head"),
+            }
+
+            if !new {
+                break;
+            }
+
+            let node_ref = node.borrow();
+
+            // less the clone we're holding in `node`
+            graph.node(
+                id,
+                &format!(
+                    "{:?}\nstrong: {}",
+                    node_ref.elem,
+                    NodeRc::strong_count(&node) - 1
+                ),
+            );
+
+            if let Some(prev) = &node_ref.prev {
+                let (prev_id, _) = graph.id(NodeRc::as_ptr(prev));
+
+                graph.edge(id, prev_id, "prev");
+            }
+
+            from = Some(id);
+            next = node_ref.next.clone();
+        }
+
+        if let Some(tail) = &self.tail {
+            let (id, _) = graph.id(NodeRc::as_ptr(tail));
+
+            graph.edge("list", id, "tail");
+        }
+
+        graph.finish()
+    }
+
+    // Check that the links between the nodes hold together:
+    //  - head and tail are either both set or both None, head has no prev
+    //      and tail has no next
+    //  - walking next from head never comes back to a node it has already
+    //      passed, and every node's next points back to it with its prev
+    //  - the walk ends at tail. Along with the prev links all pointing
+    //      back, that means walking prev from the tail retraces the same
+    //      nodes, so the list is the same length in both directions
+    //  - every node has a strong count of 2 - one from the node (or the
+    //      head) before it, one from the node (or the tail) after it. More
+    //      means something else is keeping the node alive, which would
+    //      leak it once the list is dropped
+    //
+    // Nodes are numbered from 0 at the head in the error. This walks the
+    // whole list, so it's O(n)
+    pub fn check(&self) -> Result<(), InvariantError> {
+        let Some((head, tail)) = self.ends()? else {
+            return Ok(());
+        };
+
+        // forwards from the head
+        let mut seen = BTreeSet::new();
+        let mut current = NodeRc::clone(head);
+        let mut len = 1;
+
+        seen.insert(NodeRc::as_ptr(head));
+
+        loop {
+            let Some(next) = current.borrow().next.clone() else {
+                break;
+            };
+
+            if !seen.insert(NodeRc::as_ptr(&next)) {
+                return Err(InvariantError::Cycle { node: len - 1 });
+            }
+
+            let points_back = next
+                .borrow()
+                .prev
+                .as_ref()
+                .is_some_and(|prev| NodeRc::ptr_eq(prev, &current));
+
+            if !points_back {
+                return Err(InvariantError::BrokenPrev { node: len });
+            }
+
+            current = next;
+            len += 1;
+        }
+
+        if !NodeRc::ptr_eq(&current, tail) {
+            return Err(InvariantError::TailNotLast { len });
+        }
+
+        // so that it doesn't add to the counts below
+        drop(current);
+
+        // and forwards again for the counts, now that we know the walk
+        // ends
+        let mut next = Some(NodeRc::clone(head));
+        let mut node = 0;
+
+        while let Some(current) = next {
+            // less the clone we're holding in `current`
+            let strong = NodeRc::strong_count(&current) - 1;
+
+            if strong != 2 {
+                return Err(InvariantError::StrongCount { node, strong });
+            }
+
+            next = current.borrow().next.clone();
+            node += 1;
+        }
+
+        Ok(())
+    }
+
+    // Panic if check() fails, in debug builds only - like debug_assert!
+    #[track_caller]
+    pub fn debug_assert_invariants(&self) {
+        if cfg!(debug_assertions) {
+            if let Err(err) = self.check() {
+                panic!("fourth::List is broken: {err}");
+            }
+        }
+    }
+
+    // Run after every change to the list's links. With the debug-invariants
+    // feature on, a change that breaks the list panics straight away,
+    // rather than showing up later as lost elements. That runs the whole of
+    // check(), so every push and pop is O(n)
+    //
+    // debug-invariants-ends runs check_ends() instead, which keeps them
+    // O(1). If both are on, the whole list is checked
+    #[cfg_attr(
+        not(any(feature = "debug-invariants", feature = "debug-invariants-ends")),
+        inline(always)
+    )]
+    fn after_mutation(&self) {
+        #[cfg(feature = "debug-invariants")]
+        let check = Self::check;
+        #[cfg(all(feature = "debug-invariants-ends", not(feature = "debug-invariants")))]
+        let check = Self::check_ends;
+
+        #[cfg(any(feature = "debug-invariants", feature = "debug-invariants-ends"))]
+        if let Err(err) = check(self) {
+            panic!("fourth::List is broken: {err}");
+        }
+    }
+
+    // The part of check() that debug-invariants-ends runs after each
+    // change: the links and counts of the two nodes at each end. A push or
+    // pop only ever touches those, so if the list was whole before it and
+    // they still hold, the list is whole after it
+    //
+    // A list broken some other way, with a cycle or a bad link in the
+    // middle, gets past it
+    #[cfg_attr(
+        any(not(feature = "debug-invariants-ends"), feature = "debug-invariants"),
+        allow(dead_code)
+    )]
+    fn check_ends(&self) -> Result<(), InvariantError> {
+        let Some((head, tail)) = self.ends()? else {
+            return Ok(());
+        };
+
+        // nothing here clones a NodeRc, so the counts are only the list's
+        // own references
+        let strong = |node: &NodeRc<RefCell<Node<T, A>>, A>| NodeRc::strong_count(node);
+
+        // the head and the node after it
+        if strong(head) != 2 {
+            return Err(InvariantError::StrongCount {
+                node: 0,
+                strong: strong(head),
+            });
+        }
+
+        if let Some(next) = &head.borrow().next {
+            let points_back = next
+                .borrow()
+                .prev
+                .as_ref()
+                .is_some_and(|prev| NodeRc::ptr_eq(prev, head));
+
+            if !points_back {
+                return Err(InvariantError::BrokenPrev { node: 1 });
+            }
+
+            if strong(next) != 2 {
+                return Err(InvariantError::StrongCount {
+                    node: 1,
+                    strong: strong(next),
+                });
+            }
+        }
+
+        // and the tail and the node before it
+        if strong(tail) != 2 {
+            return Err(InvariantError::StrongCountFromTail {
+                node: 0,
+                strong: strong(tail),
+            });
+        }
+
+        if let Some(prev) = &tail.borrow().prev {
+            let points_forward = prev
+                .borrow()
+                .next
+                .as_ref()
+                .is_some_and(|next| NodeRc::ptr_eq(next, tail));
+
+            if !points_forward {
+                return Err(InvariantError::BrokenTailLink);
+            }
+
+            if strong(prev) != 2 {
+                return Err(InvariantError::StrongCountFromTail {
+                    node: 1,
+                    strong: strong(prev),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    // The head and tail, after checking they're either both there or both
+    // not, and that nothing is linked in before the head or after the tail
+    #[allow(clippy::type_complexity)]
+    fn ends(
+        &self,
+    ) -> Result<
+        Option<(
+            &NodeRc<RefCell<Node<T, A>>, A>,
+            &NodeRc<RefCell<Node<T, A>>, A>,
+        )>,
+        InvariantError,
+    > {
+        let (head, tail) = match (&self.head, &self.tail) {
+            (None, None) => return Ok(None),
+            (Some(head), Some(tail)) => (head, tail),
+            (Some(_), None) => return Err(InvariantError::MissingTail),
+            (None, Some(_)) => return Err(InvariantError::MissingHead),
+        };
+
+        if head.borrow().prev.is_some() {
+            return Err(InvariantError::HeadHasPrev);
+        }
+
+        if tail.borrow().next.is_some() {
+            return Err(InvariantError::TailHasNext);
+        }
+
+        Ok(Some((head, tail)))
+    }
+
+    // Call `f` on each element from front to back, stopping at the first
+    // error
+    //
+    // There's no borrowing iterator - a Ref to one node can't outlive the
+    // Ref to the node before it - so we walk the nodes by hand, holding
+    // one Ref at a time
+    fn try_for_each<E>(&self, mut f: impl FnMut(&T) -> Result<(), E>) -> Result<(), E> {
+        let mut next = self.head.clone();
+
+        while let Some(node) = next {
+            let node = node.borrow();
+
+            f(&node.elem)?;
+            next = node.next.clone();
+        }
+
+        Ok(())
+    }
+
+    // try_for_each for an `f` that can't fail
+    fn for_each(&self, mut f: impl FnMut(&T)) {
+        let Ok(()) = self.try_for_each(|elem| {
+            f(elem);
+            Ok::<_, Infallible>(())
+        });
+    }
 }
 
-impl<T> Default for List<T> {
+impl<T, A: NodeAllocator + Default> Default for List<T, A> {
     fn default() -> Self {
-        Self::new()
+        Self::new_in(A::default())
+    }
+}
+
+impl<T, A: NodeAllocator> Stack<T> for List<T, A> {
+    fn push(&mut self, elem: T) {
+        List::push_front(self, elem);
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        List::pop_front(self)
     }
 }
 
-impl<T> Iterator for IntoIter<T> {
+impl<T, A: NodeAllocator> Queue<T> for List<T, A> {
+    fn push_back(&mut self, elem: T) {
+        List::push_back(self, elem);
+    }
+
+    fn pop_front(&mut self) -> Option<T> {
+        List::pop_front(self)
+    }
+}
+
+impl<T, A: NodeAllocator> Deque<T> for List<T, A> {
+    fn push_front(&mut self, elem: T) {
+        List::push_front(self, elem);
+    }
+
+    fn pop_back(&mut self) -> Option<T> {
+        List::pop_back(self)
+    }
+}
+
+impl<T, A: NodeAllocator> IntoIterator for List<T, A> {
+    type Item = T;
+    type IntoIter = IntoIter<T, A>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self)
+    }
+}
+
+impl<T, A: NodeAllocator> Iterator for IntoIter<T, A> {
     type Item = T;
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -205,20 +592,20 @@ impl<T> Iterator for IntoIter<T> {
     }
 }
 
-impl<T> DoubleEndedIterator for IntoIter<T> {
+impl<T, A: NodeAllocator> DoubleEndedIterator for IntoIter<T, A> {
     fn next_back(&mut self) -> Option<Self::Item> {
         self.0.pop_back()
     }
 }
 
-impl<T> Drop for List<T> {
+impl<T, A: NodeAllocator> Drop for List<T, A> {
     fn drop(&mut self) {
         //let mut current_node = self.head.take();
 
         //while current_node.is_some() {
         //    if let Some(cell) = current_node
         //        .take()
-        //        .map(Rc::try_unwrap)
+        //        .map(NodeRc::try_unwrap)
         //        .and_then(|result| result.ok())
         //    {
         //        let node = cell.into_inner();
@@ -227,21 +614,200 @@ impl<T> Drop for List<T> {
         //    };
         //}
 
-        while self.pop_front().is_some() {}
+        // Popping frees one node per element, and leaves the list whole in
+        // between. If an element's drop panics, the guard carries on
+        // popping while the panic unwinds - as in second::List's Drop.
+        // Without it the rest of the nodes would leak, as each one is kept
+        // alive by its neighbours' prev and next links
+        struct DropGuard<'a, T, A: NodeAllocator>(&'a mut List<T, A>);
+
+        impl<T, A: NodeAllocator> Drop for DropGuard<'_, T, A> {
+            fn drop(&mut self) {
+                while self.0.unlink_front().is_some() {}
+            }
+        }
+
+        let guard = DropGuard(self);
+
+        while guard.0.unlink_front().is_some() {}
+        mem::forget(guard);
+    }
+}
+
+// How a list failed check(). Nodes are numbered from 0 at the head
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InvariantError {
+    // there's a head but no tail
+    MissingTail,
+    // there's a tail but no head
+    MissingHead,
+    // the head node has a prev
+    HeadHasPrev,
+    // the tail node has a next
+    TailHasNext,
+    // node's next is a node that came before it
+    Cycle { node: usize },
+    // node's prev isn't the node before it
+    BrokenPrev { node: usize },
+    // walking from the head ended, after len nodes, somewhere other than
+    // the tail
+    TailNotLast { len: usize },
+    // node is kept alive by something other than its neighbours
+    StrongCount { node: usize, strong: usize },
+    // the node before the tail doesn't link forward to it
+    BrokenTailLink,
+    // as StrongCount, but with node counted back from 0 at the tail -
+    // the checks run after each change only look at the ends, and don't
+    // know how long the list is
+    StrongCountFromTail { node: usize, strong: usize },
+}
+
+impl fmt::Display for InvariantError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvariantError::MissingTail => f.write_str("the list has a head but no tail"),
+            InvariantError::MissingHead => f.write_str("the list has a tail but no head"),
+            InvariantError::HeadHasPrev => f.write_str("the head node has a prev link"),
+            InvariantError::TailHasNext => f.write_str("the tail node has a next link"),
+            InvariantError::Cycle { node } => {
+                write!(f, "node {node}'s next link goes back to an earlier node")
+            }
+            InvariantError::BrokenPrev { node } => {
+                write!(
+                    f,
+                    "node {node}'s prev link doesn't point to node {}",
+                    node - 1
+                )
+            }
+            InvariantError::TailNotLast { len } => write!(
+                f,
+                "the last node reached from the head, node {}, isn't the tail",
+                len - 1
+            ),
+            InvariantError::StrongCount { node, strong } => {
+                write!(
+                    f,
+                    "node {node} has a strong count of {strong} rather than 2"
+                )
+            }
+            InvariantError::BrokenTailLink => {
+                f.write_str("the node before the tail doesn't link to it")
+            }
+            InvariantError::StrongCountFromTail { node, strong } => write!(
+                f,
+                "node {node} from the tail has a strong count of {strong} rather than 2"
+            ),
+        }
+    }
+}
+
+impl Error for InvariantError {}
+
+// The elements front to back, like a Vec's Debug
+//
+// Deriving Debug would print each node's prev and next links, and each of
+// those prints its own prev and next - back and forth between the first
+// two nodes until the stack overflows. Walking the list one node at a time
+// prints each element once and never follows a prev link
+impl<T: fmt::Debug, A: NodeAllocator> fmt::Debug for List<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut list = f.debug_list();
+
+        self.try_for_each(|elem| {
+            list.entry(elem);
+
+            Ok::<_, fmt::Error>(())
+        })?;
+
+        list.finish()
+    }
+}
+
+// Written front to back
+impl<T: fmt::Display, A: NodeAllocator> fmt::Display for List<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut list = text::ListWriter::new(f);
+
+        self.for_each(|elem| {
+            list.entry(elem);
+        });
+
+        list.finish()
+    }
+}
+
+impl<T: FromStr> FromStr for List<T> {
+    type Err = ParseListError<T::Err>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut list = List::new();
+
+        text::parse_list(s)?
+            .into_iter()
+            .for_each(|elem| list.push_back(elem));
+
+        Ok(list)
+    }
+}
+
+// With the `serde` feature, a list serializes as a sequence, front to back
+#[cfg(feature = "serde")]
+mod serde_impls {
+    use alloc::vec::Vec;
+    use serde::ser::SerializeSeq;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::List;
+    use crate::node_alloc::NodeAllocator;
+
+    impl<T: Serialize, A: NodeAllocator> Serialize for List<T, A> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut len = 0;
+
+            self.for_each(|_| len += 1);
+
+            let mut seq = serializer.serialize_seq(Some(len))?;
+
+            self.try_for_each(|elem| seq.serialize_element(elem))?;
+
+            seq.end()
+        }
+    }
+
+    impl<'de, T: Deserialize<'de>> Deserialize<'de> for List<T> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let mut list = List::new();
+
+            Vec::<T>::deserialize(deserializer)?
+                .into_iter()
+                .for_each(|elem| list.push_back(elem));
+
+            Ok(list)
+        }
     }
 }
 
 #[cfg(test)]
 mod test {
-    use super::List;
+    use super::{InvariantError, List, Node};
+    use crate::node_alloc::{Bump, Global, NodeRc};
+    use crate::test_utils::{panics, DropCounter};
     use std::cell::RefMut;
 
+    // The conformance tests build lists of 100,000 elements, which would
+    // take O(n^2) time with debug-invariants checking the whole list after
+    // every push
+    #[cfg(not(feature = "debug-invariants"))]
+    crate::conformance_tests!(Stack, stack_conformance, List::new());
+    #[cfg(not(feature = "debug-invariants"))]
+    crate::conformance_tests!(Deque, deque_conformance, List::new());
+
     #[test]
     fn new_has_no_links() {
         let list: List<i32> = List::new();
 
-        println!("head: {:?}", list.head);
-        println!("tail: {:?}", list.tail);
+        assert!(list.head.is_none());
+        assert!(list.tail.is_none());
     }
 
     #[test]
@@ -383,7 +949,7 @@ mod test {
     #[test]
     fn iter_rev() {
         let mut list = List::new();
-        let xs = vec![1, 2, 3];
+        let xs = [1, 2, 3];
 
         xs.iter().for_each(|&x| list.push_front(x));
 
@@ -397,4 +963,370 @@ mod test {
 
         assert!(iter.next().is_none());
     }
+
+    #[test]
+    fn allocates_nodes_in_supplied_allocator() {
+        let bump = Bump::with_capacity(1024);
+        let mut list = List::new_in(&bump);
+
+        (0..5).for_each(|x| list.push_back(x));
+
+        assert_eq!(bump.allocations(), 5);
+        assert!(list
+            .head
+            .as_ref()
+            .is_some_and(|node| bump.contains(node.as_ptr())));
+
+        assert_eq!(list.pop_front(), Some(0));
+        assert_eq!(list.pop_back(), Some(4));
+        assert_eq!(bump.live(), 3);
+
+        drop(list);
+
+        assert_eq!(bump.live(), 0);
+    }
+
+    #[test]
+    fn display_and_parse() {
+        let mut list = List::new();
+
+        assert_eq!(list.to_string(), "[]");
+
+        list.push_back(2);
+        list.push_back(3);
+        list.push_front(1);
+
+        // front to back
+        assert_eq!(list.to_string(), "[1, 2, 3]");
+
+        let mut parsed: List<i32> = list.to_string().parse().unwrap();
+
+        assert_eq!(parsed.to_string(), "[1, 2, 3]");
+        assert_eq!(parsed.pop_front(), Some(1));
+        assert_eq!(parsed.pop_back(), Some(3));
+
+        let err = "[1, 2, 3".parse::<List<i32>>().err();
+
+        assert_eq!(err.map(|err| err.position()), Some(8));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let mut list = List::new();
+
+        [1, 2, 3].into_iter().for_each(|x| list.push_back(x));
+
+        let json = serde_json::to_string(&list).unwrap();
+
+        assert_eq!(json, "[1,2,3]");
+
+        let mut list: List<i32> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(list.pop_front(), Some(1));
+
+        let bytes = bincode::serialize(&list).unwrap();
+        let mut list: List<i32> = bincode::deserialize(&bytes).unwrap();
+
+        assert_eq!(list.pop_front(), Some(2));
+        assert_eq!(list.pop_back(), Some(3));
+        assert_eq!(list.pop_back(), None);
+    }
+
+    #[test]
+    fn drops_each_element_once() {
+        let counter = DropCounter::new();
+        let mut list = List::new();
+
+        (0..10).for_each(|_| list.push_back(counter.tracked()));
+
+        drop(list.pop_front());
+        drop(list.pop_back());
+
+        assert_eq!(counter.dropped(), 2);
+
+        drop(list);
+
+        counter.assert_all_dropped_once();
+    }
+
+    #[test]
+    fn drop_carries_on_after_a_panic() {
+        let bump = Bump::with_capacity(4096);
+        let counter = DropCounter::new();
+        let mut list = List::new_in(&bump);
+
+        list.push_back(counter.tracked());
+        list.push_back(counter.panicking());
+        list.push_back(counter.tracked());
+        list.push_back(counter.tracked());
+
+        assert!(panics(|| drop(list)));
+
+        // without the guard, the last two nodes would keep each other
+        // alive through their prev and next links, and leak
+        counter.assert_all_dropped_once();
+        assert_eq!(bump.live(), 0);
+    }
+
+    // [1, 2, 3]
+    fn three() -> List<i32> {
+        let mut list = List::new();
+
+        (1..=3).for_each(|x| list.push_back(x));
+
+        list
+    }
+
+    #[test]
+    fn check_passes_after_every_operation() {
+        let mut list = List::new();
+
+        assert_eq!(list.check(), Ok(()));
+
+        for x in 0..10 {
+            if x % 2 == 0 {
+                list.push_front(x);
+            } else {
+                list.push_back(x);
+            }
+
+            assert_eq!(list.check(), Ok(()));
+        }
+
+        while list.pop_back().is_some() {
+            assert_eq!(list.check(), Ok(()));
+
+            list.pop_front();
+            assert_eq!(list.check(), Ok(()));
+        }
+
+        list.debug_assert_invariants();
+    }
+
+    #[test]
+    fn check_finds_missing_ends() {
+        let mut list = three();
+
+        let tail = list.tail.take();
+        assert_eq!(list.check(), Err(InvariantError::MissingTail));
+        list.tail = tail;
+
+        let head = list.head.take();
+        assert_eq!(list.check(), Err(InvariantError::MissingHead));
+        list.head = head;
+
+        assert_eq!(list.check(), Ok(()));
+    }
+
+    #[test]
+    fn check_finds_broken_prev() {
+        let list = three();
+        let middle = list.head.as_ref().unwrap().borrow().next.clone().unwrap();
+
+        // the kind of stale link a buggy pop_back or push_front would leave
+        let prev = middle.borrow_mut().prev.take();
+
+        assert_eq!(list.check(), Err(InvariantError::BrokenPrev { node: 1 }));
+        assert_eq!(
+            list.check().unwrap_err().to_string(),
+            "node 1's prev link doesn't point to node 0"
+        );
+
+        middle.borrow_mut().prev = prev;
+        drop(middle);
+
+        assert_eq!(list.check(), Ok(()));
+    }
+
+    #[test]
+    fn check_finds_cycles() {
+        let list = three();
+        let head = list.head.clone().unwrap();
+        let middle = head.borrow().next.clone().unwrap();
+
+        let next = middle.borrow_mut().next.replace(head);
+
+        assert_eq!(list.check(), Err(InvariantError::Cycle { node: 1 }));
+
+        middle.borrow_mut().next = next;
+        drop(middle);
+
+        assert_eq!(list.check(), Ok(()));
+    }
+
+    #[test]
+    fn check_finds_a_tail_off_the_list() {
+        let mut list = three();
+
+        let tail = list.tail.replace(Node::new(4, Global));
+
+        assert_eq!(list.check(), Err(InvariantError::TailNotLast { len: 3 }));
+
+        list.tail = tail;
+
+        assert_eq!(list.check(), Ok(()));
+    }
+
+    #[test]
+    fn check_finds_extra_references() {
+        let list = three();
+        let head = list.head.clone().unwrap();
+        // a clone of the head through the list's own links, as if pop_front
+        // had forgotten to release one
+        let leaked = NodeRc::clone(&head);
+
+        drop(head);
+
+        assert_eq!(
+            list.check(),
+            Err(InvariantError::StrongCount { node: 0, strong: 3 })
+        );
+
+        drop(leaked);
+
+        assert_eq!(list.check(), Ok(()));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn debug_assert_invariants_panics() {
+        let mut list = three();
+        let tail = list.tail.take();
+
+        assert!(panics(|| list.debug_assert_invariants()));
+
+        list.tail = tail;
+    }
+
+    #[test]
+    #[cfg(any(feature = "debug-invariants", feature = "debug-invariants-ends"))]
+    fn mutations_check_the_list() {
+        let mut list = three();
+        let tail = list.tail.clone().unwrap();
+        let prev = tail.borrow_mut().prev.take();
+
+        drop(tail);
+
+        // popping 1 leaves 2 at the head, whose next no longer points back
+        assert!(panics(|| {
+            list.pop_front();
+        }));
+
+        list.tail.as_ref().unwrap().borrow_mut().prev = prev;
+
+        // the pop itself went through before the check panicked
+        assert_eq!(list.check(), Ok(()));
+        assert_eq!(list.pop_front(), Some(2));
+    }
+
+    // A link broken in the middle is past what check_ends looks at, so
+    // only the full check finds it
+    #[test]
+    #[cfg(feature = "debug-invariants")]
+    fn mutations_check_the_middle_of_the_list() {
+        let mut list = three();
+        list.push_front(0);
+        list.push_back(4);
+
+        // 2, two nodes in from either end
+        let middle = list
+            .head
+            .as_ref()
+            .unwrap()
+            .borrow()
+            .next
+            .as_ref()
+            .unwrap()
+            .borrow()
+            .next
+            .clone()
+            .unwrap();
+        let prev = middle.borrow_mut().prev.take();
+
+        assert!(panics(|| list.push_front(-1)));
+
+        middle.borrow_mut().prev = prev;
+        drop(middle);
+
+        // the push itself went through before the check panicked
+        assert_eq!(list.check(), Ok(()));
+        assert_eq!(list.pop_front(), Some(-1));
+    }
+
+    #[test]
+    fn check_ends_finds_broken_ends() {
+        let mut list = three();
+        // long enough that the node before the tail isn't also the one
+        // after the head
+        list.push_front(0);
+
+        let tail = list.tail.clone().unwrap();
+        let prev = tail.borrow().prev.clone().unwrap();
+        let next = prev.borrow_mut().next.take();
+
+        drop(tail);
+
+        // the broken link is found before the extra count held in `prev`
+        assert_eq!(list.check_ends(), Err(InvariantError::BrokenTailLink));
+
+        prev.borrow_mut().next = next;
+        drop(prev);
+
+        assert_eq!(list.check_ends(), Ok(()));
+
+        // an extra reference to the node before the tail
+        let leaked = list.tail.as_ref().unwrap().borrow().prev.clone();
+
+        assert_eq!(
+            list.check_ends(),
+            Err(InvariantError::StrongCountFromTail { node: 1, strong: 3 })
+        );
+
+        drop(leaked);
+        list.push_back(4);
+
+        assert_eq!(list.check_ends(), Ok(()));
+    }
+
+    // Drop doesn't check the list as it pops, so dropping a broken list
+    // frees what it can rather than panicking
+    #[test]
+    fn drop_doesnt_check() {
+        let list = three();
+        let tail = list.tail.clone().unwrap();
+        let prev = tail.borrow_mut().prev.take();
+
+        drop(tail);
+        drop(list);
+        drop(prev);
+    }
+
+    #[test]
+    fn debug_prints_elements_front_to_back() {
+        let mut list = List::new();
+
+        assert_eq!(format!("{list:?}"), "[]");
+
+        (1..=3).for_each(|x| list.push_back(x));
+
+        assert_eq!(format!("{list:?}"), "[1, 2, 3]");
+        assert_eq!(format!("{list:#?}"), "[\n    1,\n    2,\n    3,\n]");
+    }
+
+    // Deriving Debug overflowed the stack on any list of two or more
+    // nodes, following prev and next back and forth
+    #[test]
+    #[cfg(not(feature = "debug-invariants"))]
+    #[cfg_attr(miri, ignore = "a million nodes takes too long under Miri")]
+    fn debug_long_list() {
+        let mut list = List::new();
+
+        (0..1_000_000).for_each(|x| list.push_back(x));
+
+        let debug = format!("{list:?}");
+
+        assert!(debug.starts_with("[0, 1, 2, "));
+        assert!(debug.ends_with(", 999998, 999999]"));
+    }
 }
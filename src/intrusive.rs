@@ -0,0 +1,724 @@
+// An intrusive doubly-linked list - the links live inside the elements
+//
+// Every other list in this crate allocates a node per element and moves
+// the element into it. An intrusive list turns that inside out: the user's
+// struct embeds a Links field, and the list threads its pointers through
+// those fields. That means:
+//  - no allocation at all - objects can live wherever they already live,
+//      e.g. in an arena or on the stack
+//  - an object can be on several lists at once, one per Links field
+//  - given a reference to an object we can unlink it in O(1), without
+//      searching for it first
+//
+//      struct Task {
+//          id: u32,
+//          ready: Links,    // on the ready queue?
+//          all: Links,      // on the list of every task?
+//      }
+//
+//      enum Ready {}
+//      enum All {}
+//
+//      impl_linked!(Task, ready, Ready);
+//      impl_linked!(Task, all, All);
+//
+//      let mut ready: List<Task, Ready> = List::new();
+//      let mut all: List<Task, All> = List::new();
+//
+// The Tag type parameter says which Links field a list uses, so the two
+// lists above can't get their fields mixed up. Lists that only need one
+// field can leave it as the default, ().
+//
+// The list doesn't own its elements, it borrows them. push_back takes a
+// Pin<&'a T>, so:
+//  - the object outlives the list, because it's borrowed for 'a
+//  - the object can't move while it's linked, because it's pinned. Links
+//      is !Unpin, so a struct containing it can't be pinned by accident
+//      with Pin::new - it has to really be pinned, with pin! or Box::pin
+// Since we only ever hold shared references, the links are Cells, and
+// users get &T back from the list, not &mut T.
+//
+// The invariants the unsafe code below relies on:
+//  - every Links reachable from a list is inside a T that's pinned and
+//      borrowed for the list's 'a, so it's valid for as long as the list is
+//  - a Links' owner is the id of the list it's on, or 0 if it's on none.
+//      Ids are unique, so if owner matches our id the object is on our list
+//  - next and prev point to Links on the same list, with
+//      links.next.prev == links, and head and tail have no prev and next
+use core::cell::Cell;
+use core::fmt;
+use core::marker::{PhantomData, PhantomPinned};
+use core::pin::Pin;
+use core::ptr::NonNull;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::text;
+use crate::traits::{Deque, Queue, Stack};
+
+// Hands out list ids, starting at 1 so 0 can mean "not on a list"
+static NEXT_LIST_ID: AtomicUsize = AtomicUsize::new(1);
+
+// A new, unique list id
+//
+// The counter mustn't wrap: it would hand out 0, or the id of a list that's
+// still alive, and the owner checks in unlink and contains would then
+// mistake one list's objects for another's. A 64-bit counter never gets
+// there, but a 32-bit one could, so running out panics instead
+fn next_list_id() -> usize {
+    NEXT_LIST_ID
+        .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |id| id.checked_add(1))
+        .expect("ran out of intrusive list ids")
+}
+
+// The field users embed in their struct, once per list it can be on
+pub struct Links {
+    next: Cell<Option<NonNull<Links>>>,
+    prev: Cell<Option<NonNull<Links>>>,
+    owner: Cell<usize>,
+    _pinned: PhantomPinned,
+}
+
+impl Links {
+    pub const fn new() -> Self {
+        Self {
+            next: Cell::new(None),
+            prev: Cell::new(None),
+            owner: Cell::new(0),
+            _pinned: PhantomPinned,
+        }
+    }
+
+    pub fn is_linked(&self) -> bool {
+        self.owner.get() != 0
+    }
+
+    fn reset(&self) {
+        self.next.set(None);
+        self.prev.set(None);
+        self.owner.set(0);
+    }
+}
+
+impl Default for Links {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Links is full of pointers that mean nothing to anyone looking at it
+impl fmt::Debug for Links {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Links")
+            .field("linked", &self.is_linked())
+            .finish()
+    }
+}
+
+/// Locates the `Links` field a list with the given `Tag` threads through.
+///
+/// Implement this with [`impl_linked!`](crate::impl_linked) rather than by
+/// hand.
+///
+/// The field has to be a `Links` itself:
+///
+/// ```
+/// use learning_rust_with_entirely_too_many_linked_lists::{impl_linked, intrusive::Links};
+///
+/// struct Task {
+///     id: u32,
+///     links: Links,
+/// }
+///
+/// impl_linked!(Task, links);
+/// ```
+///
+/// Something that only derefs to one, like a `Box<Links>`, doesn't compile -
+/// the list would write its pointers over whatever is really at that offset:
+///
+/// ```compile_fail
+/// use learning_rust_with_entirely_too_many_linked_lists::{impl_linked, intrusive::Links};
+///
+/// struct Task {
+///     id: u32,
+///     links: Box<Links>,
+/// }
+///
+/// impl_linked!(Task, links);
+/// ```
+///
+/// # Safety
+///
+/// `OFFSET` must be the byte offset of a `Links` field within `Self`.
+pub unsafe trait Linked<Tag = ()> {
+    const OFFSET: usize;
+}
+
+// impl_linked!(Type, field) or impl_linked!(Type, field, Tag)
+//
+// offset_of! gives us the offset, and the const _ fails to
+// compile if the field isn't actually a Links. It takes a raw pointer to
+// the field rather than a reference: &item.field would deref-coerce a
+// Box<Links> or &Links field to &Links and compile, but raw pointers never
+// coerce. (Moving the field out by value would also rule out coercion,
+// but not for types that implement Drop)
+#[macro_export]
+macro_rules! impl_linked {
+    ($ty:ty, $field:ident) => {
+        $crate::impl_linked!($ty, $field, ());
+    };
+    ($ty:ty, $field:ident, $tag:ty) => {
+        const _: fn(&$ty) -> *const $crate::intrusive::Links = |item| &raw const item.$field;
+
+        // SAFETY: the const _ above proves the field is a Links
+        unsafe impl $crate::intrusive::Linked<$tag> for $ty {
+            const OFFSET: usize = ::core::mem::offset_of!($ty, $field);
+        }
+    };
+}
+
+// The Links field of `item` that lists tagged `Tag` use. The pointer is
+// derived from a reference to the whole item, so we can get back to the
+// item from it with item_of
+fn links_of<T: Linked<Tag>, Tag>(item: &T) -> NonNull<Links> {
+    // SAFETY: Linked promises there's a Links at OFFSET
+    unsafe { NonNull::from(item).byte_add(T::OFFSET).cast() }
+}
+
+// The other way round: from a Links to the item that contains it
+//
+// SAFETY: `links` must have come from links_of, and the item must be
+// valid and pinned for 'a
+unsafe fn item_of<'a, T: Linked<Tag>, Tag>(links: NonNull<Links>) -> Pin<&'a T> {
+    unsafe { Pin::new_unchecked(links.byte_sub(T::OFFSET).cast::<T>().as_ref()) }
+}
+
+// SAFETY: `links` must be valid for 'a
+unsafe fn deref<'a>(links: NonNull<Links>) -> &'a Links {
+    unsafe { links.as_ref() }
+}
+
+pub struct List<'a, T: Linked<Tag>, Tag = ()> {
+    head: Option<NonNull<Links>>,
+    tail: Option<NonNull<Links>>,
+    len: usize,
+    id: usize,
+    // we borrow pinned T's for 'a...
+    _items: PhantomData<Pin<&'a T>>,
+    // ...and only mention Tag to pick their Links field
+    _tag: PhantomData<fn() -> Tag>,
+}
+
+pub struct Iter<'l, 'a, T: Linked<Tag>, Tag = ()> {
+    front: Option<NonNull<Links>>,
+    back: Option<NonNull<Links>>,
+    remaining: usize,
+    _marker: PhantomData<&'l List<'a, T, Tag>>,
+}
+
+// A cursor pointing at an element of the list, or at the "ghost" position
+// between the tail and the head, as with std's LinkedList cursors. Moving
+// past either end lands on the ghost, and moving again wraps around
+pub struct CursorMut<'l, 'a, T: Linked<Tag>, Tag = ()> {
+    list: &'l mut List<'a, T, Tag>,
+    current: Option<NonNull<Links>>,
+}
+
+impl<'a, T: Linked<Tag>, Tag> List<'a, T, Tag> {
+    pub fn new() -> Self {
+        Self {
+            head: None,
+            tail: None,
+            len: 0,
+            id: next_list_id(),
+            _items: PhantomData,
+            _tag: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // Panics if the item is already on a list with this Tag - give the
+    // struct another Links field to put it on a second list
+    pub fn push_front(&mut self, item: Pin<&'a T>) {
+        self.link_between(None, self.head, item);
+    }
+
+    pub fn push_back(&mut self, item: Pin<&'a T>) {
+        self.link_between(self.tail, None, item);
+    }
+
+    pub fn pop_front(&mut self) -> Option<Pin<&'a T>> {
+        self.head.map(|head| self.unlink_links(head))
+    }
+
+    pub fn pop_back(&mut self) -> Option<Pin<&'a T>> {
+        self.tail.map(|tail| self.unlink_links(tail))
+    }
+
+    pub fn front(&self) -> Option<Pin<&'a T>> {
+        // SAFETY: head is on this list, so it's valid and pinned for 'a
+        self.head.map(|head| unsafe { item_of(head) })
+    }
+
+    pub fn back(&self) -> Option<Pin<&'a T>> {
+        // SAFETY: as above
+        self.tail.map(|tail| unsafe { item_of(tail) })
+    }
+
+    pub fn contains(&self, item: &T) -> bool {
+        // SAFETY: item is a live reference
+        unsafe { deref(links_of(item)) }.owner.get() == self.id
+    }
+
+    // Take `item` off this list in O(1) - no searching, its own Links
+    // know its neighbours. Returns false if it wasn't on this list
+    pub fn unlink(&mut self, item: &T) -> bool {
+        if !self.contains(item) {
+            return false;
+        }
+
+        self.unlink_links(links_of(item));
+
+        true
+    }
+
+    pub fn iter(&self) -> Iter<'_, 'a, T, Tag> {
+        Iter {
+            front: self.head,
+            back: self.tail,
+            remaining: self.len,
+            _marker: PhantomData,
+        }
+    }
+
+    // A cursor starting at the head, or the ghost if the list is empty
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, 'a, T, Tag> {
+        CursorMut {
+            current: self.head,
+            list: self,
+        }
+    }
+
+    pub fn cursor_back_mut(&mut self) -> CursorMut<'_, 'a, T, Tag> {
+        CursorMut {
+            current: self.tail,
+            list: self,
+        }
+    }
+
+    // Link `item` in between prev and next, which must be adjacent on this
+    // list - None meaning the ends
+    fn link_between(
+        &mut self,
+        prev: Option<NonNull<Links>>,
+        next: Option<NonNull<Links>>,
+        item: Pin<&'a T>,
+    ) {
+        let new = links_of(item.get_ref());
+        // SAFETY: item is pinned and borrowed for 'a
+        let links = unsafe { deref(new) };
+
+        assert!(!links.is_linked(), "item is already on a list");
+
+        links.prev.set(prev);
+        links.next.set(next);
+        links.owner.set(self.id);
+
+        // SAFETY: prev and next are on this list
+        match prev {
+            Some(prev) => unsafe { deref(prev) }.next.set(Some(new)),
+            None => self.head = Some(new),
+        }
+
+        match next {
+            Some(next) => unsafe { deref(next) }.prev.set(Some(new)),
+            None => self.tail = Some(new),
+        }
+
+        self.len += 1;
+    }
+
+    // `links` must be on this list
+    fn unlink_links(&mut self, links: NonNull<Links>) -> Pin<&'a T> {
+        // SAFETY: links and its neighbours are on this list
+        let node = unsafe { deref(links) };
+        let prev = node.prev.get();
+        let next = node.next.get();
+
+        match prev {
+            Some(prev) => unsafe { deref(prev) }.next.set(next),
+            None => self.head = next,
+        }
+
+        match next {
+            Some(next) => unsafe { deref(next) }.prev.set(prev),
+            None => self.tail = prev,
+        }
+
+        node.reset();
+        self.len -= 1;
+
+        // SAFETY: it was on this list, so it's valid and pinned for 'a
+        unsafe { item_of(links) }
+    }
+}
+
+impl<'a, T: Linked<Tag>, Tag> Default for List<'a, T, Tag> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, T: Linked<Tag>, Tag> Stack<Pin<&'a T>> for List<'a, T, Tag> {
+    fn push(&mut self, elem: Pin<&'a T>) {
+        List::push_front(self, elem);
+    }
+
+    fn pop(&mut self) -> Option<Pin<&'a T>> {
+        List::pop_front(self)
+    }
+}
+
+impl<'a, T: Linked<Tag>, Tag> Queue<Pin<&'a T>> for List<'a, T, Tag> {
+    fn push_back(&mut self, elem: Pin<&'a T>) {
+        List::push_back(self, elem);
+    }
+
+    fn pop_front(&mut self) -> Option<Pin<&'a T>> {
+        List::pop_front(self)
+    }
+}
+
+impl<'a, T: Linked<Tag>, Tag> Deque<Pin<&'a T>> for List<'a, T, Tag> {
+    fn push_front(&mut self, elem: Pin<&'a T>) {
+        List::push_front(self, elem);
+    }
+
+    fn pop_back(&mut self) -> Option<Pin<&'a T>> {
+        List::pop_back(self)
+    }
+}
+
+// Nothing to free, but the items must be told they're no longer on a
+// list, or they could never be put on another one
+impl<'a, T: Linked<Tag>, Tag> Drop for List<'a, T, Tag> {
+    fn drop(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+}
+
+impl<'a, T: Linked<Tag> + fmt::Debug, Tag> fmt::Debug for List<'a, T, Tag> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+// Written front to back. There's no FromStr - the list borrows its
+// elements, so there'd be nowhere for parsed ones to live
+impl<'a, T: Linked<Tag> + fmt::Display, Tag> fmt::Display for List<'a, T, Tag> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        text::write_list(f, self.iter())
+    }
+}
+
+impl<'l, 'a, T: Linked<Tag>, Tag> IntoIterator for &'l List<'a, T, Tag> {
+    type Item = Pin<&'a T>;
+    type IntoIter = Iter<'l, 'a, T, Tag>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'l, 'a, T: Linked<Tag>, Tag> Iterator for Iter<'l, 'a, T, Tag> {
+    type Item = Pin<&'a T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        self.front.map(|links| {
+            self.remaining -= 1;
+            // SAFETY: the list is borrowed, so links is still on it
+            self.front = unsafe { deref(links) }.next.get();
+
+            unsafe { item_of(links) }
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'l, 'a, T: Linked<Tag>, Tag> DoubleEndedIterator for Iter<'l, 'a, T, Tag> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        self.back.map(|links| {
+            self.remaining -= 1;
+            // SAFETY: as in next
+            self.back = unsafe { deref(links) }.prev.get();
+
+            unsafe { item_of(links) }
+        })
+    }
+}
+
+impl<'l, 'a, T: Linked<Tag>, Tag> CursorMut<'l, 'a, T, Tag> {
+    // None at the ghost position
+    pub fn current(&self) -> Option<Pin<&'a T>> {
+        // SAFETY: current is on the list
+        self.current.map(|links| unsafe { item_of(links) })
+    }
+
+    pub fn move_next(&mut self) {
+        self.current = match self.current {
+            // SAFETY: current is on the list
+            Some(links) => unsafe { deref(links) }.next.get(),
+            None => self.list.head,
+        };
+    }
+
+    pub fn move_prev(&mut self) {
+        self.current = match self.current {
+            // SAFETY: as above
+            Some(links) => unsafe { deref(links) }.prev.get(),
+            None => self.list.tail,
+        };
+    }
+
+    // Unlink the current element and move on to the next one
+    pub fn remove_current(&mut self) -> Option<Pin<&'a T>> {
+        let links = self.current?;
+
+        // SAFETY: current is on the list
+        self.current = unsafe { deref(links) }.next.get();
+
+        Some(self.list.unlink_links(links))
+    }
+
+    // At the ghost position, this pushes onto the back of the list
+    pub fn insert_before(&mut self, item: Pin<&'a T>) {
+        let prev = match self.current {
+            // SAFETY: current is on the list
+            Some(links) => unsafe { deref(links) }.prev.get(),
+            None => self.list.tail,
+        };
+
+        self.list.link_between(prev, self.current, item);
+    }
+
+    // At the ghost position, this pushes onto the front of the list
+    pub fn insert_after(&mut self, item: Pin<&'a T>) {
+        let next = match self.current {
+            // SAFETY: current is on the list
+            Some(links) => unsafe { deref(links) }.next.get(),
+            None => self.list.head,
+        };
+
+        self.list.link_between(self.current, next, item);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Linked, Links, List};
+    use std::cell::Cell;
+    use std::pin::pin;
+
+    #[derive(Default)]
+    struct Task {
+        id: u32,
+        runs: Cell<u32>,
+        ready: Links,
+        all: Links,
+    }
+
+    impl Task {
+        fn new(id: u32) -> Self {
+            Self {
+                id,
+                ..Self::default()
+            }
+        }
+    }
+
+    enum Ready {}
+    enum All {}
+
+    crate::impl_linked!(Task, ready, Ready);
+    crate::impl_linked!(Task, all, All);
+
+    fn ids<Tag>(list: &List<Task, Tag>) -> Vec<u32>
+    where
+        Task: Linked<Tag>,
+    {
+        list.iter().map(|task| task.id).collect()
+    }
+
+    #[test]
+    fn basics() {
+        let a = pin!(Task::new(1));
+        let b = pin!(Task::new(2));
+        let c = pin!(Task::new(3));
+        let mut list: List<Task, All> = List::new();
+
+        assert_eq!(list.pop_front().map(|t| t.id), None);
+
+        list.push_back(a.as_ref());
+        list.push_back(b.as_ref());
+        list.push_front(c.as_ref());
+
+        assert_eq!(list.len(), 3);
+        assert_eq!(ids(&list), [3, 1, 2]);
+        assert_eq!(list.front().map(|t| t.id), Some(3));
+        assert_eq!(list.back().map(|t| t.id), Some(2));
+        assert_eq!(
+            list.iter().rev().map(|t| t.id).collect::<Vec<_>>(),
+            [2, 1, 3]
+        );
+
+        assert_eq!(list.pop_back().map(|t| t.id), Some(2));
+        assert_eq!(list.pop_front().map(|t| t.id), Some(3));
+        assert_eq!(list.pop_front().map(|t| t.id), Some(1));
+        assert_eq!(list.pop_front().map(|t| t.id), None);
+        assert!(!a.all.is_linked());
+    }
+
+    #[test]
+    fn on_two_lists_at_once() {
+        let tasks = [pin!(Task::new(1)), pin!(Task::new(2)), pin!(Task::new(3))];
+        let mut all: List<Task, All> = List::new();
+        let mut ready: List<Task, Ready> = List::new();
+
+        tasks.iter().for_each(|task| all.push_back(task.as_ref()));
+        ready.push_back(tasks[2].as_ref());
+        ready.push_back(tasks[0].as_ref());
+
+        assert_eq!(ids(&all), [1, 2, 3]);
+        assert_eq!(ids(&ready), [3, 1]);
+
+        // run whatever is ready - the same objects, seen through all
+        while let Some(task) = ready.pop_front() {
+            task.runs.set(task.runs.get() + 1);
+        }
+
+        let runs: Vec<_> = all.iter().map(|task| task.runs.get()).collect();
+
+        assert_eq!(runs, [1, 0, 1]);
+
+        // taking a task off one list leaves it on the other
+        ready.push_back(tasks[1].as_ref());
+        assert!(all.unlink(&tasks[1]));
+        assert_eq!(ids(&all), [1, 3]);
+        assert_eq!(ids(&ready), [2]);
+    }
+
+    #[test]
+    fn unlink() {
+        let a = pin!(Task::new(1));
+        let b = pin!(Task::new(2));
+        let c = pin!(Task::new(3));
+        let mut list: List<Task, All> = List::new();
+        let mut other: List<Task, All> = List::new();
+
+        list.push_back(a.as_ref());
+        list.push_back(b.as_ref());
+        other.push_back(c.as_ref());
+
+        // not on this list, even though it's on one with the same Tag
+        assert!(!list.contains(&c));
+        assert!(!list.unlink(&c));
+        assert_eq!(other.len(), 1);
+
+        assert!(list.unlink(&b));
+        assert!(!list.unlink(&b));
+        assert!(list.unlink(&a));
+        assert!(list.is_empty());
+
+        // and once it's off, it can go on another list
+        other.push_front(a.as_ref());
+        assert_eq!(ids(&other), [1, 3]);
+    }
+
+    #[test]
+    #[should_panic(expected = "already on a list")]
+    fn push_twice() {
+        let a = pin!(Task::new(1));
+        let mut list: List<Task, All> = List::new();
+
+        list.push_back(a.as_ref());
+        list.push_back(a.as_ref());
+    }
+
+    #[test]
+    fn drop_unlinks() {
+        let a = pin!(Task::new(1));
+
+        {
+            let mut list: List<Task, All> = List::new();
+            list.push_back(a.as_ref());
+            assert!(a.all.is_linked());
+        }
+
+        assert!(!a.all.is_linked());
+    }
+
+    #[test]
+    fn cursor() {
+        let tasks = [
+            pin!(Task::new(1)),
+            pin!(Task::new(2)),
+            pin!(Task::new(3)),
+            pin!(Task::new(4)),
+            pin!(Task::new(5)),
+        ];
+        let mut list: List<Task, All> = List::new();
+
+        list.push_back(tasks[1].as_ref());
+        list.push_back(tasks[3].as_ref());
+
+        let mut cursor = list.cursor_front_mut();
+
+        assert_eq!(cursor.current().map(|t| t.id), Some(2));
+        cursor.insert_before(tasks[0].as_ref());
+        cursor.insert_after(tasks[2].as_ref());
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(cursor.current().map(|t| t.id), Some(4));
+
+        // past the end is the ghost, where insert_before pushes onto the back
+        cursor.move_next();
+        assert_eq!(cursor.current().map(|t| t.id), None);
+        cursor.insert_before(tasks[4].as_ref());
+
+        // and moving on wraps round to the front
+        cursor.move_next();
+        assert_eq!(cursor.current().map(|t| t.id), Some(1));
+        cursor.move_prev();
+        cursor.move_prev();
+        assert_eq!(cursor.current().map(|t| t.id), Some(5));
+
+        assert_eq!(ids(&list), [1, 2, 3, 4, 5]);
+
+        let mut cursor = list.cursor_front_mut();
+
+        cursor.move_next();
+        assert_eq!(cursor.remove_current().map(|t| t.id), Some(2));
+        assert_eq!(cursor.current().map(|t| t.id), Some(3));
+        assert_eq!(cursor.remove_current().map(|t| t.id), Some(3));
+
+        assert_eq!(ids(&list), [1, 4, 5]);
+        assert!(!tasks[1].all.is_linked());
+    }
+}
@@ -1,7 +1,31 @@
+// The crate only needs core and alloc, so it builds without std when the
+// default `std` feature is turned off. Tests always have std
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
 //pub mod fifth_attempt_1;
+pub mod arena;
+pub mod circular;
+mod dot;
 pub mod fifth_attempt_2;
 //pub mod fifth;
 pub mod first;
 pub mod fourth;
+// list ids come from an AtomicUsize, which some embedded targets lack
+#[cfg(target_has_atomic = "ptr")]
+pub mod intrusive;
+// needs std's HashMap
+#[cfg(feature = "std")]
+pub mod lru;
+pub mod node_alloc;
+pub mod rng;
 pub mod second;
+pub mod skip_list;
+#[cfg(test)]
+mod test_utils;
+pub mod text;
 pub mod third;
+pub mod traits;
+pub mod unrolled;
+pub mod xor;
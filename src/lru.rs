@@ -0,0 +1,326 @@
+// A least-recently-used cache - the classic job for a doubly-linked list
+//
+// The cache holds at most `capacity` entries. Reading or writing an entry
+// makes it the most recently used; when a new entry doesn't fit, the least
+// recently used one is thrown out to make room.
+//
+// Two structures work together:
+//  - an arena::List of (key, value) pairs in recency order, most recent at
+//      the front. Moving an entry to the front and dropping the one at the
+//      back are both O(1)
+//  - a HashMap from each key to the NodeHandle of its entry, so we can
+//      find an entry in O(1) without walking the list
+//
+//      map:   "a" -> h1   "b" -> h2   "c" -> h3
+//      list:  front -> ("c", 3) -> ("a", 1) -> ("b", 2) <- back
+//                          h3          h1          h2
+//
+// The handles are why this uses arena::List rather than fourth::List -
+// they stay valid while entries move around, and there's no
+// Rc<RefCell<_>> to borrow on every lookup. Keys are stored in both the
+// map and the list, hence K: Clone
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+
+use crate::arena::{self, NodeHandle};
+
+pub struct LruCache<K, V> {
+    map: HashMap<K, NodeHandle>,
+    list: arena::List<(K, V)>,
+    capacity: usize,
+}
+
+impl<K: Eq + Hash + Clone, V> LruCache<K, V> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            map: HashMap::with_capacity(capacity),
+            list: arena::List::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.map.contains_key(key)
+    }
+
+    // Look up a value and mark it as the most recently used
+    pub fn get<Q>(&mut self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.promote(key)
+            .and_then(|handle| self.list.get(handle))
+            .map(|(_, value)| value)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.promote(key)
+            .and_then(|handle| self.list.get_mut(handle))
+            .map(|(_, value)| value)
+    }
+
+    // Look up a value without changing its place in the queue
+    pub fn peek<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.map
+            .get(key)
+            .and_then(|&handle| self.list.get(handle))
+            .map(|(_, value)| value)
+    }
+
+    // The entry that would be evicted next
+    pub fn peek_lru(&self) -> Option<(&K, &V)> {
+        self.list.peek_back().map(|(key, value)| (key, value))
+    }
+
+    // Insert an entry as the most recently used, returning whatever it
+    // pushed out of the cache:
+    //  - the old entry for the same key, if there was one
+    //  - otherwise the least recently used entry, if the cache was full
+    //  - otherwise None
+    // A cache with capacity 0 keeps nothing, and hands the entry straight
+    // back
+    pub fn put(&mut self, key: K, value: V) -> Option<(K, V)> {
+        if let Some(&handle) = self.map.get(&key) {
+            self.list.move_to_front(handle);
+
+            let old = self
+                .list
+                .get_mut(handle)
+                .map(|entry| std::mem::replace(entry, (key, value)));
+
+            return old;
+        }
+
+        if self.capacity == 0 {
+            return Some((key, value));
+        }
+
+        let evicted = if self.len() == self.capacity {
+            self.pop_lru()
+        } else {
+            None
+        };
+
+        let handle = self.list.push_front((key.clone(), value));
+
+        self.map.insert(key, handle);
+
+        evicted
+    }
+
+    // Remove an entry, wherever it is in the queue
+    pub fn pop<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.map
+            .remove(key)
+            .and_then(|handle| self.list.remove(handle))
+            .map(|(_, value)| value)
+    }
+
+    // Remove the least recently used entry
+    pub fn pop_lru(&mut self) -> Option<(K, V)> {
+        let (key, value) = self.list.pop_back()?;
+
+        self.map.remove(&key);
+
+        Some((key, value))
+    }
+
+    // Change the capacity, evicting least recently used entries if the
+    // cache is now over it
+    pub fn resize(&mut self, capacity: usize) {
+        while self.len() > capacity {
+            self.pop_lru();
+        }
+
+        self.capacity = capacity;
+    }
+
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.list.clear();
+    }
+
+    // Entries from most to least recently used
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter(self.list.iter())
+    }
+
+    // Mark an entry as the most recently used, returning its handle
+    fn promote<Q>(&mut self, key: &Q) -> Option<NodeHandle>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let handle = *self.map.get(key)?;
+
+        self.list.move_to_front(handle);
+
+        Some(handle)
+    }
+}
+
+impl<K: Eq + Hash + Clone + fmt::Debug, V: fmt::Debug> fmt::Debug for LruCache<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, K: Eq + Hash + Clone, V> IntoIterator for &'a LruCache<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+// Double-ended, so .rev() goes from least to most recently used
+pub struct Iter<'a, K, V>(arena::Iter<'a, (K, V)>);
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(key, value)| (key, value))
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Iter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(key, value)| (key, value))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::LruCache;
+
+    fn keys<V>(cache: &LruCache<&'static str, V>) -> Vec<&'static str> {
+        cache.iter().map(|(&key, _)| key).collect()
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let mut cache = LruCache::new(2);
+
+        assert_eq!(cache.put("a", 1), None);
+        assert_eq!(cache.put("b", 2), None);
+        assert_eq!(cache.get("a"), Some(&1));
+
+        // "b" hasn't been used since "a" was read
+        assert_eq!(cache.put("c", 3), Some(("b", 2)));
+        assert_eq!(keys(&cache), ["c", "a"]);
+        assert!(!cache.contains("b"));
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn put_existing_key_replaces_and_promotes() {
+        let mut cache = LruCache::new(2);
+
+        cache.put("a", 1);
+        cache.put("b", 2);
+
+        assert_eq!(cache.put("a", 10), Some(("a", 1)));
+        assert_eq!(keys(&cache), ["a", "b"]);
+        assert_eq!(cache.put("c", 3), Some(("b", 2)));
+        assert_eq!(cache.peek("a"), Some(&10));
+    }
+
+    #[test]
+    fn peek_does_not_promote() {
+        let mut cache = LruCache::new(2);
+
+        cache.put("a", 1);
+        cache.put("b", 2);
+
+        assert_eq!(cache.peek("a"), Some(&1));
+        assert_eq!(cache.peek_lru(), Some((&"a", &1)));
+        assert_eq!(cache.put("c", 3), Some(("a", 1)));
+
+        if let Some(value) = cache.get_mut("b") {
+            *value = 20;
+        }
+
+        assert_eq!(keys(&cache), ["b", "c"]);
+        assert_eq!(cache.peek("b"), Some(&20));
+    }
+
+    #[test]
+    fn pop() {
+        let mut cache = LruCache::new(3);
+
+        cache.put("a", 1);
+        cache.put("b", 2);
+        cache.put("c", 3);
+
+        assert_eq!(cache.pop("b"), Some(2));
+        assert_eq!(cache.pop("b"), None);
+        assert_eq!(cache.pop_lru(), Some(("a", 1)));
+        assert_eq!(cache.pop_lru(), Some(("c", 3)));
+        assert_eq!(cache.pop_lru(), None);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn resize() {
+        let mut cache = LruCache::new(4);
+
+        ["a", "b", "c", "d"].into_iter().for_each(|key| {
+            cache.put(key, ());
+        });
+
+        cache.resize(2);
+
+        assert_eq!(keys(&cache), ["d", "c"]);
+
+        cache.resize(3);
+        cache.put("e", ());
+
+        assert_eq!(keys(&cache), ["e", "d", "c"]);
+        assert_eq!(
+            cache.iter().rev().map(|(&key, _)| key).collect::<Vec<_>>(),
+            ["c", "d", "e"]
+        );
+    }
+
+    #[test]
+    fn zero_capacity() {
+        let mut cache = LruCache::new(0);
+
+        assert_eq!(cache.put("a", 1), Some(("a", 1)));
+        assert!(cache.is_empty());
+        assert_eq!(cache.get("a"), None);
+    }
+}
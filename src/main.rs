@@ -1,3 +1,672 @@
+// An interactive shell for trying out the lists
+//
+// Pick a list with `use`, then type commands at it. Its contents are shown
+// after every command, so you can see exactly what each operation does:
+//
+//      > use fourth
+//      fourth: []
+//      > push 1
+//      fourth: [1]
+//      > push_back 2
+//      fourth: [1, 2]
+//      > pop
+//      popped 1
+//      fourth: [2]
+//      > undo
+//      undid pop
+//      fourth: [1, 2]
+//
+// Not every list can do everything - first has no peek, only fourth has
+// two ends, only third has tail - and asking a list for something it
+// can't do says so, which is half the point.
+//
+// third is persistent, so rather than changing a list in place, push and
+// tail make a new version and leave the old one alone. Every version is
+// kept, named v0, v1, ... in the order they were made, and you can switch
+// between them, or save the current one under a name of your own.
+//
+// Run with `cargo run`, and type `help` for the commands.
+use std::fmt;
+use std::io::{self, BufRead, Write};
+
+use learning_rust_with_entirely_too_many_linked_lists::{first, fourth, second, third};
+
+const HELP: &str = "\
+lists:
+  use NAME       switch to a new, empty list: first, second, third, fourth or fifth
+
+commands:
+  push N         push N onto the front (the top, for a stack)
+  push_back N    push N onto the back
+  pop            pop from the front
+  pop_back       pop from the back
+  peek           show the front element
+  peek_back      show the back element
+  tail           make a new version without the front element (third)
+  iter           show what .iter yields, in order
+  len            count the elements
+  undo           undo the last change
+
+third only:
+  versions       list every version
+  switch NAME    switch to another version
+  save NAME      save the current version under a new name
+
+  help           show this
+  quit           leave";
+
+const FIFTH_UNAVAILABLE: &str = "\
+fifth isn't available: fifth.rs is an unfinished attempt that doesn't compile, and
+fifth_attempt_2::List locks itself up on its first push - push borrows the list
+mutably for the rest of its life. See src/fifth_attempt_2.rs";
+
 fn main() {
-    println!("hello")
+    let stdin = io::stdin();
+    let mut repl = Repl::default();
+
+    println!("type `use second` (or first, third, fourth) to start, `help` for commands");
+
+    loop {
+        print!("> ");
+        // a prompt without a newline needs flushing to be seen
+        io::stdout().flush().expect("failed to write to stdout");
+
+        let mut line = String::new();
+
+        match stdin.lock().read_line(&mut line) {
+            // end of input, e.g. ctrl-d
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(err) => {
+                eprintln!("failed to read input: {err}");
+                break;
+            }
+        }
+
+        match repl.execute(&line) {
+            Some(output) if output.is_empty() => {}
+            Some(output) => println!("{output}"),
+            None => break,
+        }
+    }
+}
+
+// A command typed at one of the lists
+#[derive(Debug, Clone, PartialEq)]
+enum Command {
+    Push(i32),
+    PushBack(i32),
+    Pop,
+    PopBack,
+    Peek,
+    PeekBack,
+    Tail,
+    Iter,
+    Len,
+    Undo,
+    Versions,
+    Switch(String),
+    Save(String),
+}
+
+impl Command {
+    fn parse(command: &str, arg: Option<&str>) -> Result<Self, String> {
+        let number = || {
+            arg.and_then(|arg| arg.parse().ok())
+                .ok_or_else(|| format!("{command} needs a number, e.g. `{command} 3`"))
+        };
+        let name = || {
+            arg.map(str::to_string)
+                .ok_or_else(|| format!("{command} needs a name, e.g. `{command} v1`"))
+        };
+
+        let parsed = match command {
+            "push" => Command::Push(number()?),
+            "push_back" => Command::PushBack(number()?),
+            "pop" => Command::Pop,
+            "pop_back" => Command::PopBack,
+            "peek" => Command::Peek,
+            "peek_back" => Command::PeekBack,
+            "tail" => Command::Tail,
+            "iter" => Command::Iter,
+            "len" => Command::Len,
+            "undo" => Command::Undo,
+            "versions" => Command::Versions,
+            "switch" => Command::Switch(name()?),
+            "save" => Command::Save(name()?),
+            _ => return Err(format!("unknown command `{command}` - try `help`")),
+        };
+
+        Ok(parsed)
+    }
+}
+
+// The changes a list can make in place, recorded so that they can be
+// replayed by undo
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Push(i32),
+    PushBack(i32),
+    Pop,
+    PopBack,
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Op::Push(x) => write!(f, "push {x}"),
+            Op::PushBack(x) => write!(f, "push_back {x}"),
+            Op::Pop => f.write_str("pop"),
+            Op::PopBack => f.write_str("pop_back"),
+        }
+    }
+}
+
+fn unsupported(list: &str, what: &str) -> String {
+    format!("{list}::List has no {what}")
+}
+
+fn one_ended(list: &str) -> String {
+    format!("{list}::List only has one end - push, pop and peek work on the front")
+}
+
+// What the shell needs from a list that changes in place - first, second
+// and fourth. Each one maps the shell's commands onto its own methods
+trait Mutable: Default + fmt::Display {
+    const NAME: &'static str;
+
+    // Apply `op`, returning whatever it popped, or Err if the list can't
+    // do it
+    fn apply(&mut self, op: Op) -> Result<Option<i32>, String>;
+
+    fn peek(&self) -> Result<Option<i32>, String>;
+
+    fn peek_back(&self) -> Result<Option<i32>, String> {
+        Err(one_ended(Self::NAME))
+    }
+
+    fn iter(&self) -> Result<Vec<i32>, String>;
+}
+
+impl Mutable for first::List {
+    const NAME: &'static str = "first";
+
+    fn apply(&mut self, op: Op) -> Result<Option<i32>, String> {
+        match op {
+            Op::Push(x) => self.push(x),
+            Op::Pop => return Ok(self.pop()),
+            Op::PushBack(_) | Op::PopBack => return Err(one_ended(Self::NAME)),
+        }
+
+        Ok(None)
+    }
+
+    fn peek(&self) -> Result<Option<i32>, String> {
+        Err(unsupported(Self::NAME, "peek"))
+    }
+
+    fn iter(&self) -> Result<Vec<i32>, String> {
+        Err(unsupported(Self::NAME, "iter"))
+    }
+}
+
+impl Mutable for second::List<i32> {
+    const NAME: &'static str = "second";
+
+    fn apply(&mut self, op: Op) -> Result<Option<i32>, String> {
+        match op {
+            Op::Push(x) => self.push(x),
+            Op::Pop => return Ok(self.pop()),
+            Op::PushBack(_) | Op::PopBack => return Err(one_ended(Self::NAME)),
+        }
+
+        Ok(None)
+    }
+
+    fn peek(&self) -> Result<Option<i32>, String> {
+        Ok(second::List::peek(self).copied())
+    }
+
+    fn iter(&self) -> Result<Vec<i32>, String> {
+        Ok(second::List::iter(self).copied().collect())
+    }
+}
+
+impl Mutable for fourth::List<i32> {
+    const NAME: &'static str = "fourth";
+
+    fn apply(&mut self, op: Op) -> Result<Option<i32>, String> {
+        match op {
+            Op::Push(x) => self.push_front(x),
+            Op::PushBack(x) => self.push_back(x),
+            Op::Pop => return Ok(self.pop_front()),
+            Op::PopBack => return Ok(self.pop_back()),
+        }
+
+        Ok(None)
+    }
+
+    fn peek(&self) -> Result<Option<i32>, String> {
+        Ok(self.peek_front().map(|elem| *elem))
+    }
+
+    fn peek_back(&self) -> Result<Option<i32>, String> {
+        Ok(fourth::List::peek_back(self).map(|elem| *elem))
+    }
+
+    // peek hands out a Ref that keeps the node's RefCell borrowed, and
+    // there's no way to hand out one for each node while walking the list
+    fn iter(&self) -> Result<Vec<i32>, String> {
+        Err(unsupported(Self::NAME, "iter"))
+    }
+}
+
+// One list being driven by the shell
+trait Shell {
+    // Run a command, returning what to print before the contents
+    fn run(&mut self, command: Command) -> Result<Option<String>, String>;
+
+    // e.g. "second: [2, 1]"
+    fn contents(&self) -> String;
+}
+
+// A list that changes in place, plus every change made to it
+//
+// Undo replays all but the last change onto a new list. It's the only way
+// that works for all three lists - none of them can be cloned, and a pop
+// can't be undone without knowing which end it came from
+#[derive(Default)]
+struct MutableShell<L> {
+    list: L,
+    history: Vec<Op>,
+    // none of the lists keep count, so we do
+    len: usize,
+}
+
+impl<L: Mutable> MutableShell<L> {
+    fn apply(&mut self, op: Op) -> Result<Option<String>, String> {
+        let popped = self.list.apply(op)?;
+
+        let message = match (op, popped) {
+            (Op::Push(_) | Op::PushBack(_), _) => {
+                self.len += 1;
+
+                None
+            }
+            (Op::Pop | Op::PopBack, Some(x)) => {
+                self.len -= 1;
+
+                Some(format!("popped {x}"))
+            }
+            // popping an empty list changes nothing, so there's nothing
+            // to undo
+            (Op::Pop | Op::PopBack, None) => return Ok(Some("nothing to pop".to_string())),
+        };
+
+        self.history.push(op);
+
+        Ok(message)
+    }
+}
+
+impl<L: Mutable> Shell for MutableShell<L> {
+    fn run(&mut self, command: Command) -> Result<Option<String>, String> {
+        let show = |elem: Option<i32>| match elem {
+            Some(x) => x.to_string(),
+            None => "empty".to_string(),
+        };
+
+        match command {
+            Command::Push(x) => self.apply(Op::Push(x)),
+            Command::PushBack(x) => self.apply(Op::PushBack(x)),
+            Command::Pop => self.apply(Op::Pop),
+            Command::PopBack => self.apply(Op::PopBack),
+            Command::Peek => self.list.peek().map(|x| Some(show(x))),
+            Command::PeekBack => self.list.peek_back().map(|x| Some(show(x))),
+            Command::Iter => self
+                .list
+                .iter()
+                .map(|elems| Some(format!("iter yields {elems:?}"))),
+            Command::Len => Ok(Some(self.len.to_string())),
+            Command::Undo => {
+                let undone = self.history.pop().ok_or("nothing to undo")?;
+
+                self.list = L::default();
+                self.len = 0;
+
+                for op in std::mem::take(&mut self.history) {
+                    self.apply(op)?;
+                }
+
+                Ok(Some(format!("undid {undone}")))
+            }
+            Command::Tail => Err(format!(
+                "{}::List has no tail - third does, as it never changes a list in place",
+                L::NAME
+            )),
+            Command::Versions | Command::Switch(_) | Command::Save(_) => {
+                Err("only third keeps versions".to_string())
+            }
+        }
+    }
+
+    fn contents(&self) -> String {
+        format!("{}: {}", L::NAME, self.list)
+    }
+}
+
+// Every version of a third::List made in this session
+//
+// Versions share whatever nodes they have in common, so keeping all of
+// them around is cheap - a push only adds one node, and tail and save add
+// none
+struct ThirdShell {
+    versions: Vec<(String, third::List<i32>)>,
+    current: usize,
+    // the versions we were on before each push, tail, switch or save, for
+    // undo
+    history: Vec<usize>,
+}
+
+impl Default for ThirdShell {
+    fn default() -> Self {
+        Self {
+            versions: vec![("v0".to_string(), third::List::new())],
+            current: 0,
+            history: Vec::new(),
+        }
+    }
+}
+
+impl ThirdShell {
+    fn list(&self) -> &third::List<i32> {
+        &self.versions[self.current].1
+    }
+
+    fn name(&self) -> &str {
+        &self.versions[self.current].0
+    }
+
+    fn find(&self, name: &str) -> Option<usize> {
+        self.versions.iter().position(|(other, _)| other == name)
+    }
+
+    // Add a new version made from the current one, and move to it
+    fn add(&mut self, list: third::List<i32>, how: &str) -> Option<String> {
+        // `save` can take a name like v2 before we get to it, so the new
+        // version gets the first vN that's still free
+        let name = (self.versions.len()..)
+            .map(|n| format!("v{n}"))
+            .find(|name| self.find(name).is_none())
+            .expect("there's always a free name");
+        let message = format!("{name} = {} with {how}", self.name());
+
+        self.versions.push((name, list));
+        self.history.push(self.current);
+        self.current = self.versions.len() - 1;
+
+        Some(message)
+    }
+}
+
+impl Shell for ThirdShell {
+    fn run(&mut self, command: Command) -> Result<Option<String>, String> {
+        match command {
+            Command::Push(x) => {
+                let list = self.versions[self.current].1.prepend(x);
+
+                Ok(self.add(list, &format!("{x} prepended")))
+            }
+            Command::Tail => {
+                let list = self.list().clone().tail();
+
+                Ok(self.add(list, "its head dropped"))
+            }
+            Command::Peek => Ok(Some(match self.list().head() {
+                Some(x) => x.to_string(),
+                None => "empty".to_string(),
+            })),
+            Command::Iter => {
+                let elems: Vec<i32> = self.list().iter().copied().collect();
+
+                Ok(Some(format!("iter yields {elems:?}")))
+            }
+            Command::Len => Ok(Some(self.list().iter().count().to_string())),
+            Command::Undo => {
+                let previous = self.history.pop().ok_or("nothing to undo")?;
+
+                // the version we're leaving is kept - it can still be
+                // switched back to
+                self.current = previous;
+
+                Ok(Some(format!("back on {}", self.name())))
+            }
+            Command::Versions => {
+                let lines: Vec<String> = self
+                    .versions
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (name, list))| {
+                        let marker = if i == self.current { '*' } else { ' ' };
+
+                        format!("{marker} {name}: {list}")
+                    })
+                    .collect();
+
+                Ok(Some(lines.join("\n")))
+            }
+            Command::Switch(name) => {
+                let index = self
+                    .find(&name)
+                    .ok_or_else(|| format!("no version named {name} - try `versions`"))?;
+
+                self.history.push(self.current);
+                self.current = index;
+
+                Ok(None)
+            }
+            Command::Save(name) => {
+                if self.find(&name).is_some() {
+                    return Err(format!("there's already a version named {name}"));
+                }
+
+                // cloning a third::List only copies the pointer to its
+                // head, so the saved version shares every node
+                let list = self.list().clone();
+
+                self.versions.push((name, list));
+                self.history.push(self.current);
+                self.current = self.versions.len() - 1;
+
+                Ok(None)
+            }
+            Command::Pop => Err("third::List has no pop - it never changes in place. \
+                 `tail` makes a new version without the head"
+                .to_string()),
+            Command::PushBack(_) | Command::PopBack | Command::PeekBack => Err(one_ended("third")),
+        }
+    }
+
+    fn contents(&self) -> String {
+        format!("third {}: {}", self.name(), self.list())
+    }
+}
+
+#[derive(Default)]
+struct Repl {
+    shell: Option<Box<dyn Shell>>,
+}
+
+impl Repl {
+    // Run one line of input, returning what to print, or None to quit
+    fn execute(&mut self, line: &str) -> Option<String> {
+        let mut words = line.split_whitespace();
+
+        let Some(command) = words.next() else {
+            return Some(String::new());
+        };
+        let arg = words.next();
+
+        let result = match command {
+            "quit" | "exit" => return None,
+            "help" => return Some(HELP.to_string()),
+            "use" => self.switch_list(arg).map(|()| None),
+            _ => match &mut self.shell {
+                Some(shell) => Command::parse(command, arg).and_then(|command| shell.run(command)),
+                None => Err("pick a list first, e.g. `use second`".to_string()),
+            },
+        };
+
+        let mut output = Vec::new();
+
+        match result {
+            Ok(Some(message)) => output.push(message),
+            Ok(None) => {}
+            Err(err) => output.push(format!("error: {err}")),
+        }
+
+        if let Some(shell) = &self.shell {
+            output.push(shell.contents());
+        }
+
+        Some(output.join("\n"))
+    }
+
+    fn switch_list(&mut self, name: Option<&str>) -> Result<(), String> {
+        let shell: Box<dyn Shell> = match name {
+            Some("first") => Box::<MutableShell<first::List>>::default(),
+            Some("second") => Box::<MutableShell<second::List<i32>>>::default(),
+            Some("third") => Box::<ThirdShell>::default(),
+            Some("fourth") => Box::<MutableShell<fourth::List<i32>>>::default(),
+            Some("fifth") => return Err(FIFTH_UNAVAILABLE.to_string()),
+            Some(other) => return Err(format!("there's no list called {other}")),
+            None => return Err("use needs a list: first, second, third or fourth".to_string()),
+        };
+
+        self.shell = Some(shell);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Repl;
+
+    // Run each line, returning the output of the last one
+    fn run(repl: &mut Repl, lines: &[&str]) -> String {
+        lines
+            .iter()
+            .map(|line| repl.execute(line).expect("didn't expect to quit"))
+            .last()
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn push_pop_and_undo() {
+        let mut repl = Repl::default();
+
+        assert_eq!(
+            run(&mut repl, &["use second", "push 1", "push 2", "push 3"]),
+            "second: [3, 2, 1]"
+        );
+        assert_eq!(run(&mut repl, &["pop"]), "popped 3\nsecond: [2, 1]");
+        assert_eq!(run(&mut repl, &["undo"]), "undid pop\nsecond: [3, 2, 1]");
+        assert_eq!(run(&mut repl, &["undo"]), "undid push 3\nsecond: [2, 1]");
+        assert_eq!(run(&mut repl, &["len"]), "2\nsecond: [2, 1]");
+        assert_eq!(run(&mut repl, &["peek"]), "2\nsecond: [2, 1]");
+        assert_eq!(
+            run(&mut repl, &["iter"]),
+            "iter yields [2, 1]\nsecond: [2, 1]"
+        );
+    }
+
+    #[test]
+    fn fourth_has_two_ends() {
+        let mut repl = Repl::default();
+
+        assert_eq!(
+            run(
+                &mut repl,
+                &["use fourth", "push 1", "push_back 2", "push 0"]
+            ),
+            "fourth: [0, 1, 2]"
+        );
+        assert_eq!(run(&mut repl, &["pop_back"]), "popped 2\nfourth: [0, 1]");
+        assert_eq!(run(&mut repl, &["peek_back"]), "1\nfourth: [0, 1]");
+        assert_eq!(
+            run(&mut repl, &["undo", "undo"]),
+            "undid push 0\nfourth: [1, 2]"
+        );
+    }
+
+    #[test]
+    fn unsupported_commands() {
+        let mut repl = Repl::default();
+
+        assert_eq!(
+            run(&mut repl, &["push 1"]),
+            "error: pick a list first, e.g. `use second`"
+        );
+        assert_eq!(
+            run(&mut repl, &["use first", "push 1", "peek"]),
+            "error: first::List has no peek\nfirst: [1]"
+        );
+        assert_eq!(
+            run(&mut repl, &["push x"]),
+            "error: push needs a number, e.g. `push 3`\nfirst: [1]"
+        );
+        assert_eq!(
+            run(&mut repl, &["pop", "pop", "undo", "undo", "undo"]),
+            "error: nothing to undo\nfirst: []"
+        );
+        assert!(run(&mut repl, &["use fifth"]).starts_with("error: fifth isn't available"));
+        // a failed `use` leaves the current list alone
+        assert!(run(&mut repl, &["use fifth"]).ends_with("first: []"));
+        assert_eq!(repl.execute("quit"), None);
+    }
+
+    #[test]
+    fn third_keeps_every_version() {
+        let mut repl = Repl::default();
+
+        assert_eq!(
+            run(&mut repl, &["use third", "push 1", "push 2"]),
+            "v2 = v1 with 2 prepended\nthird v2: [2, 1]"
+        );
+        assert_eq!(
+            run(&mut repl, &["tail"]),
+            "v3 = v2 with its head dropped\nthird v3: [1]"
+        );
+        assert_eq!(
+            run(&mut repl, &["save mine", "switch v2"]),
+            "third v2: [2, 1]"
+        );
+        assert_eq!(
+            run(&mut repl, &["versions"]),
+            "  v0: []\n  v1: [1]\n* v2: [2, 1]\n  v3: [1]\n  mine: [1]\nthird v2: [2, 1]"
+        );
+        assert_eq!(run(&mut repl, &["undo"]), "back on mine\nthird mine: [1]");
+        assert_eq!(
+            run(&mut repl, &["pop"]).lines().last(),
+            Some("third mine: [1]")
+        );
+    }
+
+    #[test]
+    fn third_saved_names_dont_collide() {
+        let mut repl = Repl::default();
+
+        // v1 is the name the next push would have had
+        assert_eq!(
+            run(&mut repl, &["use third", "save v1", "push 5"]),
+            "v2 = v1 with 5 prepended\nthird v2: [5]"
+        );
+        assert_eq!(run(&mut repl, &["switch v1"]), "third v1: []");
+
+        // save can be undone like any other move between versions
+        assert_eq!(run(&mut repl, &["undo"]), "back on v2\nthird v2: [5]");
+        assert_eq!(run(&mut repl, &["undo"]), "back on v1\nthird v1: []");
+        assert_eq!(run(&mut repl, &["undo"]), "back on v0\nthird v0: []");
+    }
 }
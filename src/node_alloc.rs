@@ -0,0 +1,824 @@
+// Pluggable allocation for list nodes
+//
+// Box::new and Rc::new always go to the global allocator. Std has
+// Box::new_in / Rc::new_in for choosing another allocator, but they're
+// behind the unstable `allocator_api` feature, so on stable we provide the
+// pieces ourselves:
+//  - NodeAllocator - the trait an allocator implements, a cut-down
+//      version of std's Allocator trait
+//  - Global - forwards to the global allocator, and is the default
+//  - Bump - a fixed-size bump/arena allocator
+//  - NodeBox - an owning pointer like Box<T, A>
+//  - SparePool - a stack of empty NodeBox allocations kept for reuse
+//  - NodeRc - a reference-counted pointer like Rc<T, A>, without Weak
+//
+// All the unsafe code needed for custom allocation lives in this module,
+// so that the lists themselves can stay safe Rust
+use ::alloc::alloc::{self, Layout};
+use core::cell::Cell;
+use core::fmt;
+use core::marker::PhantomData;
+use core::mem::{self, ManuallyDrop, MaybeUninit};
+use core::ops::{Deref, DerefMut};
+use core::ptr::{self, NonNull};
+
+/// # Safety
+///
+/// Implementors must return memory that:
+///  - is valid for reads and writes of `layout.size()` bytes
+///  - is aligned to `layout.align()`
+///  - stays valid until it's passed to .deallocate on this allocator or a
+///    clone of it
+///
+/// Clones of an allocator must be interchangeable - memory allocated
+/// through one clone may be freed through another, which is what happens
+/// when every node holds its own clone
+pub unsafe trait NodeAllocator: Clone {
+    /// Returns None when the allocator is out of memory
+    fn allocate(&self, layout: Layout) -> Option<NonNull<u8>>;
+
+    /// # Safety
+    ///
+    /// `ptr` must have been returned by .allocate on this allocator (or a
+    /// clone of it) with the same `layout`, and must not be used again
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout);
+}
+
+// The global allocator, i.e. what Box::new and Rc::new use
+//
+// Global is zero-sized, so a NodeBox<T, Global> is the same size as a
+// Box<T>
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Global;
+
+// A dangling but well-aligned pointer, for zero-sized allocations which
+// std::alloc::alloc doesn't allow
+fn dangling(layout: Layout) -> NonNull<u8> {
+    NonNull::new(ptr::without_provenance_mut(layout.align())).expect("alignment is never zero")
+}
+
+unsafe impl NodeAllocator for Global {
+    fn allocate(&self, layout: Layout) -> Option<NonNull<u8>> {
+        if layout.size() == 0 {
+            return Some(dangling(layout));
+        }
+
+        // SAFETY: the layout has a non-zero size
+        NonNull::new(unsafe { alloc::alloc(layout) })
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        if layout.size() != 0 {
+            // SAFETY: the caller guarantees ptr came from .allocate with
+            // this layout, which means it came from alloc::alloc
+            unsafe { alloc::dealloc(ptr.as_ptr(), layout) }
+        }
+    }
+}
+
+// A bump allocator over a single fixed-size buffer
+//
+// Allocating just moves an offset forward, and deallocating does nothing
+// but count - the memory is only handed back when the Bump itself is
+// reset or dropped. This suits lists that live for the length of one
+// request: every node is cheap to allocate, and the whole lot is freed at
+// once at the end
+//
+// Lists borrow the allocator, i.e. the NodeAllocator is &Bump, so the
+// borrow checker makes sure the Bump outlives every node allocated from it
+pub struct Bump {
+    start: NonNull<u8>,
+    capacity: usize,
+    offset: Cell<usize>,
+    allocations: Cell<usize>,
+    deallocations: Cell<usize>,
+}
+
+impl Bump {
+    // the alignment of the buffer itself - allocations needing more than
+    // this are still fine, we just skip ahead to a suitable address
+    const ALIGN: usize = 16;
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        let layout = Self::buffer_layout(capacity);
+
+        // SAFETY: buffer_layout never returns a zero-sized layout
+        let start = NonNull::new(unsafe { alloc::alloc(layout) })
+            .unwrap_or_else(|| alloc::handle_alloc_error(layout));
+
+        Self {
+            start,
+            capacity,
+            offset: Cell::new(0),
+            allocations: Cell::new(0),
+            deallocations: Cell::new(0),
+        }
+    }
+
+    fn buffer_layout(capacity: usize) -> Layout {
+        Layout::from_size_align(capacity.max(1), Self::ALIGN).expect("Bump capacity is too large")
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    // the number of bytes handed out so far, including alignment padding
+    pub fn used(&self) -> usize {
+        self.offset.get()
+    }
+
+    pub fn allocations(&self) -> usize {
+        self.allocations.get()
+    }
+
+    pub fn deallocations(&self) -> usize {
+        self.deallocations.get()
+    }
+
+    // the number of allocations that haven't been given back yet
+    pub fn live(&self) -> usize {
+        self.allocations() - self.deallocations()
+    }
+
+    // Whether `ptr` points into this allocator's buffer
+    pub fn contains<T>(&self, ptr: *const T) -> bool {
+        let start = self.start.as_ptr() as usize;
+        let addr = ptr as usize;
+
+        (start..start + self.capacity).contains(&addr)
+    }
+
+    // Make the whole buffer available again
+    //
+    // Taking &mut self means no list can still be borrowing the Bump, so
+    // there's nothing left that could be pointing into the buffer
+    pub fn reset(&mut self) {
+        self.offset.set(0);
+        self.allocations.set(0);
+        self.deallocations.set(0);
+    }
+}
+
+impl Drop for Bump {
+    fn drop(&mut self) {
+        // SAFETY: start was allocated in with_capacity with this layout
+        unsafe { alloc::dealloc(self.start.as_ptr(), Self::buffer_layout(self.capacity)) }
+    }
+}
+
+impl fmt::Debug for Bump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Bump")
+            .field("capacity", &self.capacity)
+            .field("used", &self.used())
+            .field("live", &self.live())
+            .finish()
+    }
+}
+
+unsafe impl NodeAllocator for &Bump {
+    fn allocate(&self, layout: Layout) -> Option<NonNull<u8>> {
+        let start = self.start.as_ptr() as usize;
+        let offset = self.offset.get();
+
+        // round the next free address up to the required alignment
+        let aligned = (start + offset).checked_next_multiple_of(layout.align())? - start;
+        let end = aligned.checked_add(layout.size())?;
+
+        if end > self.capacity {
+            return None;
+        }
+
+        self.offset.set(end);
+        self.allocations.set(self.allocations.get() + 1);
+
+        // SAFETY: aligned + size <= capacity, so the pointer is in bounds
+        // of the buffer
+        Some(unsafe { self.start.add(aligned) })
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        // a zero-sized allocation from a full buffer - or one with no
+        // capacity at all - is handed out just past its end, which
+        // contains doesn't count as inside it
+        let end = self.start.as_ptr() as usize + self.capacity;
+        let past_the_end = layout.size() == 0 && ptr.as_ptr() as usize == end;
+
+        assert!(
+            self.contains(ptr.as_ptr()) || past_the_end,
+            "pointer was not allocated by this Bump"
+        );
+
+        self.deallocations.set(self.deallocations.get() + 1);
+    }
+}
+
+fn allocate<T, A: NodeAllocator>(alloc: &A) -> NonNull<T> {
+    let layout = Layout::new::<T>();
+
+    alloc
+        .allocate(layout)
+        .unwrap_or_else(|| alloc::handle_alloc_error(layout))
+        .cast()
+}
+
+// An owning pointer to a T allocated by A, i.e. a stable Box<T, A>
+//
+// Like std's Box<T, A>, each NodeBox carries its own copy of the
+// allocator so that it knows where to return its memory when it's
+// dropped. For Global that copy takes up no space at all
+pub struct NodeBox<T, A: NodeAllocator = Global> {
+    ptr: NonNull<T>,
+    alloc: A,
+    // tells the compiler that we own a T, for the sake of drop checking
+    _owns: PhantomData<T>,
+}
+
+// A NodeBox uniquely owns its T, just like Box does
+unsafe impl<T: Send, A: NodeAllocator + Send> Send for NodeBox<T, A> {}
+unsafe impl<T: Sync, A: NodeAllocator + Sync> Sync for NodeBox<T, A> {}
+
+impl<T> NodeBox<T> {
+    pub fn new(value: T) -> Self {
+        Self::new_in(value, Global)
+    }
+}
+
+impl<T, A: NodeAllocator> NodeBox<T, A> {
+    pub fn new_in(value: T, alloc: A) -> Self {
+        let ptr = allocate::<T, A>(&alloc);
+
+        // SAFETY: ptr is freshly allocated for a T
+        unsafe { ptr.write(value) };
+
+        Self {
+            ptr,
+            alloc,
+            _owns: PhantomData,
+        }
+    }
+
+    // Move the value out and free the allocation
+    //
+    // With a Box we could write `let node = *boxed;`, or move fields
+    // straight out of it - that's special compiler magic that only Box
+    // gets, so here it's spelled out as a function
+    pub fn into_inner(this: Self) -> T {
+        let this = ManuallyDrop::new(this);
+
+        // SAFETY: `this` is never used again, so the value is read out
+        // exactly once and the memory is freed exactly once. The
+        // allocator is moved out of the ManuallyDrop, so it gets dropped
+        // normally at the end of this function
+        unsafe {
+            let value = this.ptr.read();
+            let alloc = ptr::read(&this.alloc);
+
+            alloc.deallocate(this.ptr.cast(), Layout::new::<T>());
+
+            value
+        }
+    }
+
+    // Move the value out but keep the allocation, so that it can be
+    // filled with a new value later by NodeBox::write without going back
+    // to the allocator
+    pub fn take(this: Self) -> (T, NodeBox<MaybeUninit<T>, A>) {
+        let this = ManuallyDrop::new(this);
+
+        // SAFETY: the value is read out exactly once, and from then on the
+        // memory is only reachable through the MaybeUninit box, which
+        // won't read or drop it
+        unsafe {
+            let value = this.ptr.read();
+            let empty = NodeBox {
+                ptr: this.ptr.cast(),
+                alloc: ptr::read(&this.alloc),
+                _owns: PhantomData,
+            };
+
+            (value, empty)
+        }
+    }
+
+    pub fn allocator(this: &Self) -> &A {
+        &this.alloc
+    }
+}
+
+impl<T, A: NodeAllocator> NodeBox<MaybeUninit<T>, A> {
+    // Fill an empty allocation, e.g. one from NodeBox::take, the same way
+    // Box::write does
+    pub fn write(this: Self, value: T) -> NodeBox<T, A> {
+        let this = ManuallyDrop::new(this);
+
+        // SAFETY: MaybeUninit<T> has the same layout as T, so once the
+        // value is written the allocation holds a valid T
+        unsafe {
+            this.ptr.cast::<T>().write(value);
+
+            NodeBox {
+                ptr: this.ptr.cast(),
+                alloc: ptr::read(&this.alloc),
+                _owns: PhantomData,
+            }
+        }
+    }
+}
+
+impl<T, A: NodeAllocator> Deref for NodeBox<T, A> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: ptr points to a live T for as long as we exist
+        unsafe { self.ptr.as_ref() }
+    }
+}
+
+impl<T, A: NodeAllocator> DerefMut for NodeBox<T, A> {
+    fn deref_mut(&mut self) -> &mut T {
+        // SAFETY: as above, and &mut self means nobody else can see it
+        unsafe { self.ptr.as_mut() }
+    }
+}
+
+// Frees an allocation when it goes out of scope
+//
+// NodeBox and NodeRc hold one of these while they drop their value, so
+// that the memory still goes back to the allocator if the value's drop
+// panics - Box and Rc behave the same way
+struct Deallocate<'a, A: NodeAllocator> {
+    alloc: &'a A,
+    ptr: NonNull<u8>,
+    layout: Layout,
+}
+
+impl<'a, A: NodeAllocator> Deallocate<'a, A> {
+    // SAFETY: the caller must be allowed to free `ptr` with `layout`, and
+    // must not use it once the guard is dropped
+    unsafe fn new<T>(alloc: &'a A, ptr: NonNull<T>, layout: Layout) -> Self {
+        Self {
+            alloc,
+            ptr: ptr.cast(),
+            layout,
+        }
+    }
+}
+
+impl<A: NodeAllocator> Drop for Deallocate<'_, A> {
+    fn drop(&mut self) {
+        // SAFETY: promised by the caller of Deallocate::new
+        unsafe { self.alloc.deallocate(self.ptr, self.layout) }
+    }
+}
+
+impl<T, A: NodeAllocator> Drop for NodeBox<T, A> {
+    fn drop(&mut self) {
+        // SAFETY: the value is still live, and after this the pointer is
+        // never used again
+        unsafe {
+            let _free = Deallocate::new(&self.alloc, self.ptr, Layout::new::<T>());
+
+            ptr::drop_in_place(self.ptr.as_ptr());
+        }
+    }
+}
+
+impl<T: fmt::Debug, A: NodeAllocator> fmt::Debug for NodeBox<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+// A stack of empty allocations, like the ones NodeBox::take leaves
+// behind, kept so they can be filled again without going back to the
+// allocator
+//
+// A Vec<NodeBox<MaybeUninit<T>, A>> would do the same job, but the Vec's
+// buffer would come from the global allocator, even for a list whose nodes
+// all come from a Bump. Instead the spares are chained together through
+// their own unused memory - each one holds the pointer to the next - so
+// the pool itself needs no memory at all.
+//
+// Each spare also holds on to its own allocator, rather than the pool
+// handing every spare back out with one allocator of its own. Spares can
+// come from different allocators - second::List's splice_after links in
+// nodes from another list - and a spare has to go back to the allocator
+// that made it when it's finally freed.
+//
+// That only works for a T with room for the pointer and the allocator,
+// which a list node always has, as its own link is a NodeBox - a pointer
+// and an allocator. Anything smaller is freed rather than kept
+pub(crate) struct SparePool<T, A: NodeAllocator = Global> {
+    head: Option<NonNull<MaybeUninit<T>>>,
+    len: usize,
+    // the allocators are kept inside the spares
+    _owns: PhantomData<A>,
+}
+
+// What's written at the start of each spare allocation
+struct Spare<T, A> {
+    next: Option<NonNull<MaybeUninit<T>>>,
+    alloc: A,
+}
+
+// Like NodeBox, the pool owns its allocations
+unsafe impl<T: Send, A: NodeAllocator + Send> Send for SparePool<T, A> {}
+unsafe impl<T: Sync, A: NodeAllocator + Sync> Sync for SparePool<T, A> {}
+
+impl<T, A: NodeAllocator> SparePool<T, A> {
+    // whether a T has room for a Spare
+    const CAN_CHAIN: bool = mem::size_of::<T>() >= mem::size_of::<Spare<T, A>>()
+        && mem::align_of::<T>() >= mem::align_of::<Spare<T, A>>();
+
+    pub(crate) fn new() -> Self {
+        Self {
+            head: None,
+            len: 0,
+            _owns: PhantomData,
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    // The spare can come from any allocator, not just one shared by the
+    // rest of the pool - it keeps its own, and pop hands it back with it
+    pub(crate) fn push(&mut self, spare: NodeBox<MaybeUninit<T>, A>) {
+        if !Self::CAN_CHAIN {
+            // dropping the spare here frees it
+            return;
+        }
+
+        let spare = ManuallyDrop::new(spare);
+
+        // SAFETY: the allocation is big and aligned enough for a Spare,
+        // and nothing reads its old contents - it's MaybeUninit. The
+        // NodeBox's allocator is moved into the Spare, and the NodeBox is
+        // never dropped, so it's only ever owned once
+        unsafe {
+            spare.ptr.cast::<Spare<T, A>>().write(Spare {
+                next: self.head,
+                alloc: ptr::read(&spare.alloc),
+            });
+        }
+
+        self.head = Some(spare.ptr);
+        self.len += 1;
+    }
+
+    pub(crate) fn pop(&mut self) -> Option<NodeBox<MaybeUninit<T>, A>> {
+        let ptr = self.head?;
+
+        // SAFETY: push wrote a Spare at the start of the allocation, and
+        // it's read out exactly once, here, as the spare leaves the pool
+        let spare = unsafe { ptr.cast::<Spare<T, A>>().read() };
+
+        self.head = spare.next;
+        self.len -= 1;
+
+        Some(NodeBox {
+            ptr,
+            alloc: spare.alloc,
+            _owns: PhantomData,
+        })
+    }
+
+    // Give every spare back to the allocator it came from
+    pub(crate) fn clear(&mut self) {
+        // a NodeBox<MaybeUninit<T>> only frees its memory when it's
+        // dropped - there's no T in it to drop
+        while self.pop().is_some() {}
+    }
+}
+
+impl<T, A: NodeAllocator> Drop for SparePool<T, A> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+// What a NodeRc points to - the count sits alongside the value in the
+// same allocation, just as it does for Rc
+struct RcBox<T> {
+    strong: Cell<usize>,
+    value: T,
+}
+
+// A reference-counted pointer to a T allocated by A, i.e. a stable
+// Rc<T, A>
+//
+// fourth::List only ever needs strong references, so unlike Rc there's no
+// Weak and no weak count
+pub struct NodeRc<T, A: NodeAllocator = Global> {
+    ptr: NonNull<RcBox<T>>,
+    alloc: A,
+    _owns: PhantomData<RcBox<T>>,
+}
+
+impl<T> NodeRc<T> {
+    pub fn new(value: T) -> Self {
+        Self::new_in(value, Global)
+    }
+}
+
+impl<T, A: NodeAllocator> NodeRc<T, A> {
+    pub fn new_in(value: T, alloc: A) -> Self {
+        let ptr = allocate::<RcBox<T>, A>(&alloc);
+
+        // SAFETY: ptr is freshly allocated for an RcBox<T>
+        unsafe {
+            ptr.write(RcBox {
+                strong: Cell::new(1),
+                value,
+            })
+        };
+
+        Self {
+            ptr,
+            alloc,
+            _owns: PhantomData,
+        }
+    }
+
+    fn inner(&self) -> &RcBox<T> {
+        // SAFETY: the RcBox stays alive while any NodeRc points to it
+        unsafe { self.ptr.as_ref() }
+    }
+
+    pub fn strong_count(this: &Self) -> usize {
+        this.inner().strong.get()
+    }
+
+    // Whether two NodeRcs point to the same allocation
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        this.ptr == other.ptr
+    }
+
+    // A pointer to the value, for comparing identity
+    pub fn as_ptr(this: &Self) -> *const T {
+        &this.inner().value
+    }
+
+    // Move the value out if this is the only reference to it, otherwise
+    // hand the NodeRc back - the same contract as Rc::try_unwrap
+    pub fn try_unwrap(this: Self) -> Result<T, Self> {
+        if Self::strong_count(&this) != 1 {
+            return Err(this);
+        }
+
+        let this = ManuallyDrop::new(this);
+
+        // SAFETY: we hold the only reference, and `this` is never used
+        // again, so the value is read out and the memory freed exactly once
+        unsafe {
+            let value = ptr::addr_of!((*this.ptr.as_ptr()).value).read();
+            let alloc = ptr::read(&this.alloc);
+
+            alloc.deallocate(this.ptr.cast(), Layout::new::<RcBox<T>>());
+
+            Ok(value)
+        }
+    }
+}
+
+impl<T, A: NodeAllocator> Clone for NodeRc<T, A> {
+    fn clone(&self) -> Self {
+        let strong = &self.inner().strong;
+
+        strong.set(strong.get() + 1);
+
+        Self {
+            ptr: self.ptr,
+            alloc: self.alloc.clone(),
+            _owns: PhantomData,
+        }
+    }
+}
+
+impl<T, A: NodeAllocator> Deref for NodeRc<T, A> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.inner().value
+    }
+}
+
+impl<T, A: NodeAllocator> Drop for NodeRc<T, A> {
+    fn drop(&mut self) {
+        let strong = &self.inner().strong;
+
+        strong.set(strong.get() - 1);
+
+        if strong.get() == 0 {
+            // SAFETY: we were the last reference, so nothing else can
+            // observe the value or the allocation after this
+            unsafe {
+                let _free = Deallocate::new(&self.alloc, self.ptr, Layout::new::<RcBox<T>>());
+
+                ptr::drop_in_place(self.ptr.as_ptr());
+            }
+        }
+    }
+}
+
+impl<T: fmt::Debug, A: NodeAllocator> fmt::Debug for NodeRc<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Bump, NodeAllocator, NodeBox, NodeRc, SparePool};
+    use std::alloc::Layout;
+
+    #[test]
+    fn bump_aligns_allocations() {
+        let bump = Bump::with_capacity(64);
+        let alloc = &bump;
+
+        let byte = alloc.allocate(Layout::new::<u8>()).unwrap();
+        let word = alloc.allocate(Layout::new::<u64>()).unwrap();
+
+        assert!(bump.contains(byte.as_ptr()));
+        assert!(bump.contains(word.as_ptr()));
+        assert_eq!(word.as_ptr() as usize % std::mem::align_of::<u64>(), 0);
+        assert_eq!(bump.allocations(), 2);
+    }
+
+    #[test]
+    fn bump_runs_out() {
+        let bump = Bump::with_capacity(16);
+        let alloc = &bump;
+
+        assert!(alloc.allocate(Layout::new::<[u8; 16]>()).is_some());
+        assert!(alloc.allocate(Layout::new::<u8>()).is_none());
+    }
+
+    #[test]
+    fn bump_zero_sized_allocations() {
+        let layout = Layout::new::<()>();
+
+        // from a full buffer, and from one with no room at all
+        for (capacity, used) in [(16, 16), (0, 0)] {
+            let bump = Bump::with_capacity(capacity);
+            let alloc = &bump;
+
+            let full = alloc.allocate(Layout::array::<u8>(used).unwrap()).unwrap();
+            let empty = alloc.allocate(layout).unwrap();
+
+            // SAFETY: both were allocated by alloc with these layouts
+            unsafe {
+                alloc.deallocate(empty, layout);
+                alloc.deallocate(full, Layout::array::<u8>(used).unwrap());
+            }
+
+            assert_eq!(bump.live(), 0);
+        }
+
+        drop(NodeBox::new_in((), &Bump::with_capacity(0)));
+    }
+
+    #[test]
+    fn bump_reset() {
+        let mut bump = Bump::with_capacity(16);
+
+        drop(NodeBox::new_in([0u8; 16], &bump));
+        assert_eq!(bump.live(), 0);
+
+        bump.reset();
+
+        let value = NodeBox::new_in([1u8; 16], &bump);
+        assert_eq!(*value, [1; 16]);
+    }
+
+    #[test]
+    fn node_box_returns_memory() {
+        let bump = Bump::with_capacity(1024);
+
+        let mut value = NodeBox::new_in(String::from("a"), &bump);
+        value.push('b');
+
+        assert_eq!(bump.live(), 1);
+        assert_eq!(NodeBox::into_inner(value), "ab");
+        assert_eq!(bump.live(), 0);
+
+        drop(NodeBox::new_in(1, &bump));
+        assert_eq!(bump.allocations(), 2);
+        assert_eq!(bump.deallocations(), 2);
+    }
+
+    #[test]
+    fn node_box_reuses_allocation() {
+        let bump = Bump::with_capacity(1024);
+
+        let value = NodeBox::new_in(String::from("a"), &bump);
+        let (value, empty) = NodeBox::take(value);
+
+        assert_eq!(value, "a");
+        assert_eq!(bump.live(), 1);
+
+        let value = NodeBox::write(empty, String::from("b"));
+
+        assert_eq!(*value, "b");
+        assert_eq!(bump.allocations(), 1);
+
+        drop(value);
+        assert_eq!(bump.live(), 0);
+    }
+
+    #[test]
+    fn spare_pool_reuses_allocations() {
+        let bump = Bump::with_capacity(1024);
+        let mut pool = SparePool::new();
+
+        // [u64; 2] has room for the link to the next spare and the &Bump
+        let (_, first) = NodeBox::take(NodeBox::new_in([1u64; 2], &bump));
+        let (_, second) = NodeBox::take(NodeBox::new_in([2u64; 2], &bump));
+        let addresses = [first.as_ptr(), second.as_ptr()];
+
+        pool.push(first);
+        pool.push(second);
+        assert_eq!(pool.len(), 2);
+        assert_eq!(bump.live(), 2);
+
+        // last in, first out
+        let second = pool.pop().unwrap();
+        assert_eq!(second.as_ptr(), addresses[1]);
+
+        let second = NodeBox::write(second, [3u64; 2]);
+        assert_eq!(*second, [3; 2]);
+
+        drop(second);
+        assert_eq!(pool.len(), 1);
+        assert_eq!(bump.live(), 1);
+
+        // the rest are freed along with the pool
+        drop(pool);
+        assert_eq!(bump.live(), 0);
+    }
+
+    #[test]
+    fn spare_pool_keeps_each_spares_allocator() {
+        let (a, b) = (Bump::with_capacity(1024), Bump::with_capacity(1024));
+        let mut pool = SparePool::new();
+
+        let (_, from_a) = NodeBox::take(NodeBox::new_in([1usize; 2], &a));
+        let (_, from_b) = NodeBox::take(NodeBox::new_in([2usize; 2], &b));
+
+        pool.push(from_a);
+        pool.push(from_b);
+
+        // the spare from b goes back to b, not to a
+        let from_b = pool.pop().unwrap();
+        assert!(b.contains(from_b.as_ptr()));
+
+        drop(from_b);
+        drop(pool);
+        assert_eq!((a.live(), b.live()), (0, 0));
+    }
+
+    #[test]
+    fn spare_pool_frees_allocations_too_small_to_chain() {
+        let bump = Bump::with_capacity(1024);
+        let mut pool = SparePool::new();
+
+        let (_, spare) = NodeBox::take(NodeBox::new_in(1u8, &bump));
+
+        pool.push(spare);
+
+        assert_eq!(pool.len(), 0);
+        assert_eq!(bump.live(), 0);
+    }
+
+    #[test]
+    fn node_rc_counts() {
+        let bump = Bump::with_capacity(1024);
+
+        let a = NodeRc::new_in(String::from("a"), &bump);
+        let b = NodeRc::clone(&a);
+
+        assert_eq!(NodeRc::strong_count(&a), 2);
+        assert!(NodeRc::ptr_eq(&a, &b));
+
+        // a second reference exists, so we get the NodeRc back
+        let a = NodeRc::try_unwrap(a).unwrap_err();
+        drop(b);
+
+        assert_eq!(bump.live(), 1);
+        assert_eq!(NodeRc::try_unwrap(a).ok(), Some(String::from("a")));
+        assert_eq!(bump.live(), 0);
+    }
+
+    #[test]
+    fn zero_sized_values() {
+        let value = NodeBox::new(());
+        let shared = NodeRc::new(());
+
+        assert_eq!(*value, ());
+        assert_eq!(NodeRc::try_unwrap(shared).ok(), Some(()));
+    }
+}
@@ -0,0 +1,79 @@
+// A small deterministic pseudo-random number generator
+//
+// The skip list needs random numbers to pick the height of each node, but
+// we don't want a dependency on `rand`, and we want the same seed to build
+// the same list every time so that tests and benchmarks are reproducible.
+//
+// This is SplitMix64 - one u64 of state, a handful of arithmetic ops per
+// number, and good enough statistical quality for picking node levels.
+// It is not suitable for anything security-related
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+
+        z ^ (z >> 31)
+    }
+
+    // A number in 0..bound, for bound > 0
+    //
+    // Taking the high half of a 128 bit multiply is less biased than
+    // next_u64() % bound, and doesn't need a division
+    pub fn below(&mut self, bound: u64) -> u64 {
+        assert!(bound > 0, "bound must be greater than 0");
+
+        ((self.next_u64() as u128 * bound as u128) >> 64) as u64
+    }
+}
+
+impl Default for Rng {
+    fn default() -> Self {
+        Self::new(0x5EED)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Rng;
+
+    #[test]
+    fn same_seed_same_sequence() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        let mut c = Rng::new(43);
+
+        let xs: Vec<_> = (0..8).map(|_| a.next_u64()).collect();
+        let ys: Vec<_> = (0..8).map(|_| b.next_u64()).collect();
+        let zs: Vec<_> = (0..8).map(|_| c.next_u64()).collect();
+
+        assert_eq!(xs, ys);
+        assert_ne!(xs, zs);
+    }
+
+    #[test]
+    fn below_stays_in_bounds() {
+        let mut rng = Rng::default();
+        let mut seen = [false; 6];
+
+        for _ in 0..1000 {
+            let x = rng.below(6) as usize;
+
+            assert!(x < 6);
+            seen[x] = true;
+        }
+
+        assert!(seen.iter().all(|&x| x));
+    }
+}
@@ -1,6 +1,18 @@
-struct Node<T> {
+use alloc::format;
+use alloc::string::String;
+use core::error::Error;
+use core::fmt;
+use core::mem;
+use core::str::FromStr;
+
+use crate::dot;
+use crate::node_alloc::{Global, NodeAllocator, NodeBox, SparePool};
+use crate::text::{self, ParseListError};
+use crate::traits::Stack;
+
+struct Node<T, A: NodeAllocator> {
     elem: T,
-    next: Link<T>,
+    next: Link<T, A>,
 }
 
 // This is actually a poor reimplementation of Option
@@ -10,13 +22,32 @@ struct Node<T> {
 //}
 
 // replace it with a type alias
-type Link<T> = Option<Box<Node<T>>>;
+//
+// Nodes are allocated by A rather than always by the global allocator, so
+// instead of Box<Node<T>> we use NodeBox<Node<T, A>, A> - our stand-in for
+// std's unstable Box<T, A>. NodeBox derefs to the node just like Box does,
+// so the rest of the list reads the same as it would with Box
+type Link<T, A> = Option<NodeBox<Node<T, A>, A>>;
+
+// Node allocations with nothing in them, kept around so that the next
+// .push doesn't need to ask the allocator for memory. The pool chains them
+// together through their own memory, so it doesn't allocate anything
+// itself - a list built on a Bump never touches the global heap
+type NodePool<T, A> = SparePool<Node<T, A>, A>;
 
-pub struct List<T> {
-    head: Link<T>,
+// A defaults to Global, so List<T> is still a list that allocates through
+// the global allocator
+pub struct List<T, A: NodeAllocator = Global> {
+    head: Link<T, A>,
+    // nodes freed by .pop, waiting to be reused by .push
+    pool: NodePool<T, A>,
+    // how many spare nodes the pool may hold - 0 turns the pool off
+    pool_cap: usize,
+    // every new node gets a clone of this
+    alloc: A,
 }
 
-impl<T> Drop for List<T> {
+impl<T, A: NodeAllocator> Drop for List<T, A> {
     fn drop(&mut self) {
         //let mut cur_link = mem::replace(&mut self.head, None);
         //
@@ -37,39 +68,125 @@ impl<T> Drop for List<T> {
         //          mem::replace(&mut option, None)
         // one should replace it with
         //          option.take()
-        let mut cur_link = self.head.take();
+        //
+        // If an element's drop panics, we unwind out of the loop with the
+        // rest of the list still to free. std's LinkedList deals with this
+        // by wrapping itself in a guard whose own Drop carries on with the
+        // same loop while the panic unwinds, and we do the same. If a
+        // second element panics while the guard is running, that's a panic
+        // during a panic, and the process aborts
+        struct DropGuard<'a, T, A: NodeAllocator>(&'a mut List<T, A>);
 
-        while let Some(mut boxed_node) = cur_link {
-            cur_link = boxed_node.next.take();
+        impl<T, A: NodeAllocator> Drop for DropGuard<'_, T, A> {
+            fn drop(&mut self) {
+                self.0.drop_nodes();
+            }
         }
+
+        let guard = DropGuard(self);
+
+        guard.0.drop_nodes();
+        mem::forget(guard);
     }
 }
 
 // #Iterator 1 - we create a tuple struct which wraps our List
-pub struct IntoIter<T>(List<T>);
+pub struct IntoIter<T, A: NodeAllocator = Global>(List<T, A>);
 
-pub struct Iter<'a, T> {
-    next: Option<&'a Node<T>>,
+pub struct Iter<'a, T, A: NodeAllocator = Global> {
+    next: Option<&'a Node<T, A>>,
 }
 
-pub struct IterMut<'a, T> {
-    next: Option<&'a mut Node<T>>,
+pub struct IterMut<'a, T, A: NodeAllocator = Global> {
+    next: Option<&'a mut Node<T, A>>,
+}
+
+// A cursor that walks forwards through the list and can change its
+// structure as it goes - insert and remove elements, cut the list in two,
+// or splice another list in - without popping everything in front of the
+// change off and pushing it back on
+//
+// The cursor starts before the first element, on a "ghost" position with
+// no element, and .move_next steps it onto each element in turn. Every
+// change happens just after the cursor, because in a singly-linked list
+// the only link we can reach from a node is the one to the node after it.
+// To remove the 5th element:
+//
+//      let mut cursor = list.cursor_mut();
+//
+//      (0..4).for_each(|_| { cursor.move_next(); });
+//      cursor.remove_next();
+//
+// It only goes forwards - there's no link back to the node before - and
+// it's all safe code: the cursor holds a &mut to the current node, and
+// moving on trades it for a &mut to that node's next
+pub struct CursorMut<'a, T, A: NodeAllocator = Global> {
+    // only None for a moment, while .move_next swaps one position for the
+    // next
+    position: Option<Position<'a, T, A>>,
+    // the rest of the list, borrowed field by field, so that inserting and
+    // removing can use the node pool while we hold a &mut into the nodes
+    pool: &'a mut NodePool<T, A>,
+    pool_cap: usize,
+    alloc: &'a A,
+}
+
+enum Position<'a, T, A: NodeAllocator> {
+    // before the first element - we hold the list's head, which is the link
+    // to the node after us
+    Ghost(&'a mut Link<T, A>),
+    Node(&'a mut Node<T, A>),
 }
 
 impl<T> List<T> {
     pub fn new() -> Self {
-        Self { head: None }
+        Self::new_in(Global)
     }
 
-    // #Iterator 2 - we add an associated function which returns
-    // IntoIter wrapping our List
-    pub fn into_iter(self) -> IntoIter<T> {
-        IntoIter(self)
+    // Create a list that keeps up to `cap` popped nodes around for reuse
+    //
+    // A loop that pushes and pops then only allocates until it's pushed
+    // `cap` more elements than it's popped
+    pub fn with_node_pool(cap: usize) -> Self {
+        Self::with_node_pool_in(cap, Global)
+    }
+}
+
+impl<T, A: NodeAllocator> List<T, A> {
+    // Create a list whose nodes are allocated by `alloc`
+    pub fn new_in(alloc: A) -> Self {
+        Self::with_node_pool_in(0, alloc)
+    }
+
+    pub fn with_node_pool_in(cap: usize, alloc: A) -> Self {
+        Self {
+            head: None,
+            pool: SparePool::new(),
+            pool_cap: cap,
+            alloc,
+        }
+    }
+
+    // The number of spare nodes currently waiting in the pool
+    pub fn pool_len(&self) -> usize {
+        self.pool.len()
+    }
+
+    // Give every spare node in the pool back to the allocator
+    //
+    // The pool keeps its capacity, so it will fill up again as elements
+    // are popped
+    pub fn shrink_pool(&mut self) {
+        self.pool.clear();
+    }
+
+    pub fn allocator(&self) -> &A {
+        &self.alloc
     }
 
     // Instead of entirely eliding the lifetime, we can indicate that there
     // _is_ a lifetime, but because of lifetime elision rules it can be inferred
-    pub fn iter(&self) -> Iter<'_, T> {
+    pub fn iter(&self) -> Iter<'_, T, A> {
         //pub fn iter(&self) -> Iter<T> {
         //
         // We can elide the lifetimes in this method because of the following
@@ -112,7 +229,7 @@ impl<T> List<T> {
     // define when implementing Iterator - that function uses _this_ .next
     // attribute in order to get the value out of the node, and return a mutable
     // reference to the value inside the node
-    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+    pub fn iter_mut(&mut self) -> IterMut<'_, T, A> {
         IterMut {
             // We use Option::as_deref_mut because:
             //  - we want deref coercion to get Node<T> out of Box<Node<T>>
@@ -131,7 +248,7 @@ impl<T> List<T> {
             next: self.head.take(),
         };
 
-        self.head = Some(Box::new(node));
+        self.head = Some(alloc_node(&mut self.pool, &self.alloc, node));
     }
 
     pub fn pop(&mut self) -> Option<T> {
@@ -151,7 +268,14 @@ impl<T> List<T> {
         //      match option.take() { None => None, Some(x) => Some(y) }
         // one should refactor to use
         //      option.map(|x| y)
+        //
+        // With a Box we could move node.next and node.elem straight out of
+        // the box - NodeBox can't do that, so we move the whole node out
+        // with NodeBox::take first, which leaves us with the empty
+        // allocation to put in the pool
         self.head.take().map(|node| {
+            let node = free_node(&mut self.pool, self.pool_cap, node);
+
             self.head = node.next;
 
             node.elem
@@ -176,19 +300,294 @@ impl<T> List<T> {
         // Option::as_mut returns a mutable reference to the caller
         self.head.as_mut().map(|node| &mut node.elem)
     }
+
+    // A cursor before the first element
+    pub fn cursor_mut(&mut self) -> CursorMut<'_, T, A> {
+        CursorMut {
+            position: Some(Position::Ghost(&mut self.head)),
+            pool: &mut self.pool,
+            pool_cap: self.pool_cap,
+            alloc: &self.alloc,
+        }
+    }
+
+    // The node graph in Graphviz's DOT format - see dot.rs. Spare nodes in
+    // the pool aren't part of the list, so they're only counted
+    pub fn to_dot(&self) -> String
+    where
+        T: fmt::Debug,
+    {
+        let mut graph = dot::Graph::new();
+        let mut from = None;
+        let mut next = self.head.as_deref();
+
+        graph.root("list", &format!("second::List\npool: {}", self.pool_len()));
+
+        while let Some(node) = next {
+            let (id, _) = graph.id(node);
+
+            graph.node(id, &format!("{:?}", node.elem));
+
+            match from {
+                Some(from) => graph.edge(from, id, "next"),
+                None => graph.edge("list", id, "head"),
+            }
+
+            from = Some(id);
+            next = node.next.as_deref();
+        }
+
+        graph.finish()
+    }
+
+    // Free the nodes one at a time, rather than letting Box's drop recurse
+    // down the list
+    //
+    // The list is left whole after every step - head always points at the
+    // nodes still to free - so that if dropping an element panics, the
+    // guard in Drop can pick up where we left off
+    fn drop_nodes(&mut self) {
+        while let Some(mut boxed_node) = self.head.take() {
+            self.head = boxed_node.next.take();
+        }
+    }
+
+    // Free a chain of nodes that's been cut off the list, one at a time,
+    // keeping their allocations in the pool as .pop does
+    //
+    // As in Drop, a guard carries on freeing the rest of the chain if an
+    // element's drop panics
+    fn free_chain(&mut self, chain: Link<T, A>) {
+        struct ChainGuard<'a, T, A: NodeAllocator> {
+            list: &'a mut List<T, A>,
+            chain: Link<T, A>,
+        }
+
+        impl<T, A: NodeAllocator> ChainGuard<'_, T, A> {
+            // chain always points at the nodes still to free, and the
+            // element is only dropped after it's moved on
+            fn free(&mut self) {
+                while let Some(node) = self.chain.take() {
+                    let node = free_node(&mut self.list.pool, self.list.pool_cap, node);
+
+                    self.chain = node.next;
+                }
+            }
+        }
+
+        impl<T, A: NodeAllocator> Drop for ChainGuard<'_, T, A> {
+            fn drop(&mut self) {
+                self.free();
+            }
+        }
+
+        let mut guard = ChainGuard { list: self, chain };
+
+        guard.free();
+        mem::forget(guard);
+    }
+}
+
+// Forth's stack words, for when the list is an operand stack
+//
+// Positions count down from the top, which is 0, and each word's effect is
+// written the Forth way, with the top of the stack on the right:
+//
+//      swap    ( a b -- b a )
+//
+// The words that only move elements around - swap, rot and roll - unlink
+// a node and link it back in at the top, so elements never leave their
+// boxes and nothing is allocated. drop_n cuts the top nodes off as one
+// chain and frees them straight into the pool. Those that copy an element
+// - dup, over and pick - need T: Clone and a new node.
+//
+// A word that needs more elements than the stack has returns StackUnderflow
+// and leaves the stack as it was
+impl<T, A: NodeAllocator> List<T, A> {
+    // The element n places below the top - peek_nth(0) is .peek
+    pub fn peek_nth(&self, n: usize) -> Result<&T, StackUnderflow> {
+        self.require_nth(n)?;
+
+        Ok(self
+            .iter()
+            .nth(n)
+            .expect("require checked there are n + 1 elements"))
+    }
+
+    // ( a -- a a )
+    pub fn dup(&mut self) -> Result<(), StackUnderflow>
+    where
+        T: Clone,
+    {
+        self.pick(0)
+    }
+
+    // ( a b -- b a )
+    pub fn swap(&mut self) -> Result<(), StackUnderflow> {
+        self.roll(1)
+    }
+
+    // ( a b -- a b a )
+    pub fn over(&mut self) -> Result<(), StackUnderflow>
+    where
+        T: Clone,
+    {
+        self.pick(1)
+    }
+
+    // ( a b c -- b c a )
+    pub fn rot(&mut self) -> Result<(), StackUnderflow> {
+        self.roll(2)
+    }
+
+    // Drop the top n elements. Their nodes go back to the pool, as with
+    // .pop
+    pub fn drop_n(&mut self, n: usize) -> Result<(), StackUnderflow> {
+        self.require(n)?;
+
+        // walk down to the link after the top n nodes...
+        let mut link = &mut self.head;
+
+        for _ in 0..n {
+            link = &mut link.as_mut().expect("require checked the depth").next;
+        }
+
+        // ...cut the chain off there, and make what's below it the head
+        let rest = link.take();
+        let chain = mem::replace(&mut self.head, rest);
+
+        self.free_chain(chain);
+
+        Ok(())
+    }
+
+    // Push a copy of the element n places down - pick(0) is dup, pick(1)
+    // is over
+    //
+    //      ( xn ... x0 -- xn ... x0 xn )
+    pub fn pick(&mut self, n: usize) -> Result<(), StackUnderflow>
+    where
+        T: Clone,
+    {
+        let elem = self.peek_nth(n)?.clone();
+
+        self.push(elem);
+
+        Ok(())
+    }
+
+    // Move the element n places down to the top - roll(1) is swap, roll(2)
+    // is rot
+    //
+    //      ( xn ... x0 -- xn-1 ... x0 xn )
+    pub fn roll(&mut self, n: usize) -> Result<(), StackUnderflow> {
+        self.require_nth(n)?;
+
+        if n == 0 {
+            return Ok(());
+        }
+
+        // walk down to the link that points at node n...
+        let mut link = &mut self.head;
+
+        for _ in 0..n {
+            link = &mut link.as_mut().expect("require checked the depth").next;
+        }
+
+        // ...unlink the node, closing the gap behind it...
+        let mut node = link.take().expect("require checked the depth");
+
+        *link = node.next.take();
+
+        // ...and link it back in on top
+        node.next = self.head.take();
+        self.head = Some(node);
+
+        Ok(())
+    }
+
+    // Check there's an element n places down - that there are n + 1
+    // elements. n + 1 would overflow for n == usize::MAX, so the count
+    // saturates instead: no list can hold usize::MAX nodes, so that's
+    // always an underflow, just as it should be
+    fn require_nth(&self, n: usize) -> Result<(), StackUnderflow> {
+        self.require(n.saturating_add(1))
+    }
+
+    // Check there are at least `needed` elements, counting no further than
+    // that
+    fn require(&self, needed: usize) -> Result<(), StackUnderflow> {
+        let available = self.iter().take(needed).count();
+
+        if available < needed {
+            Err(StackUnderflow { needed, available })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+// A stack word needed more elements than there were
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StackUnderflow {
+    pub needed: usize,
+    pub available: usize,
+}
+
+impl fmt::Display for StackUnderflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let elements = if self.needed == 1 {
+            "element"
+        } else {
+            "elements"
+        };
+
+        write!(
+            f,
+            "stack underflow: needed {} {elements}, found {}",
+            self.needed, self.available
+        )
+    }
 }
 
+impl Error for StackUnderflow {}
+
 // allow the use of:
 //  let my_list: List<i32> = Default::default();
-impl<T> Default for List<T> {
+impl<T, A: NodeAllocator + Default> Default for List<T, A> {
     fn default() -> Self {
-        Self::new()
+        Self::new_in(A::default())
+    }
+}
+
+impl<T, A: NodeAllocator> Stack<T> for List<T, A> {
+    fn push(&mut self, elem: T) {
+        List::push(self, elem);
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        List::pop(self)
+    }
+}
+
+// #Iterator 2 - we implement IntoIterator, whose .into_iter returns
+// IntoIter wrapping our List
+//
+// An inherent `pub fn into_iter(self)` would work too, but it shadows the
+// std trait of the same name - implementing the trait also lets a List be
+// used directly in `for x in list {}`
+impl<T, A: NodeAllocator> IntoIterator for List<T, A> {
+    type Item = T;
+    type IntoIter = IntoIter<T, A>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self)
     }
 }
 
 // #Iterator 3 - we implement Iterator for IntoIter, IntoIter all
 // the methods that are available to iterators
-impl<T> Iterator for IntoIter<T> {
+impl<T, A: NodeAllocator> Iterator for IntoIter<T, A> {
     // This is an associated type that Iterator expects us to define
     // This indicates to the implementation what the type inside the
     // iterator is
@@ -205,7 +604,7 @@ impl<T> Iterator for IntoIter<T> {
     }
 }
 
-impl<'a, T> Iterator for Iter<'a, T> {
+impl<'a, T, A: NodeAllocator> Iterator for Iter<'a, T, A> {
     type Item = &'a T;
 
     //fn next(&mut self) -> Option<Self::Item> {
@@ -229,7 +628,7 @@ impl<'a, T> Iterator for Iter<'a, T> {
     }
 }
 
-impl<'a, T> Iterator for IterMut<'a, T> {
+impl<'a, T, A: NodeAllocator> Iterator for IterMut<'a, T, A> {
     type Item = &'a mut T;
 
     // For IterMut::next we need to return a mutable reference
@@ -277,9 +676,220 @@ impl<'a, T> Iterator for IterMut<'a, T> {
     }
 }
 
+// Box up a node, reusing a spare allocation from the pool if there is one
+//
+// These take the list's fields rather than the list, so that CursorMut can
+// call them while it has the list's nodes borrowed
+fn alloc_node<T, A: NodeAllocator>(
+    pool: &mut NodePool<T, A>,
+    alloc: &A,
+    node: Node<T, A>,
+) -> NodeBox<Node<T, A>, A> {
+    match pool.pop() {
+        Some(spare) => NodeBox::write(spare, node),
+        None => NodeBox::new_in(node, alloc.clone()),
+    }
+}
+
+// Move a node out of its allocation, keeping the allocation in the pool if
+// there's room for it
+fn free_node<T, A: NodeAllocator>(
+    pool: &mut NodePool<T, A>,
+    pool_cap: usize,
+    node: NodeBox<Node<T, A>, A>,
+) -> Node<T, A> {
+    let (node, spare) = NodeBox::take(node);
+
+    if pool.len() < pool_cap {
+        pool.push(spare);
+    }
+
+    node
+}
+
+impl<'a, T, A: NodeAllocator> CursorMut<'a, T, A> {
+    // The element the cursor is on - None before the first element
+    pub fn current(&mut self) -> Option<&mut T> {
+        match self.position.as_mut()? {
+            Position::Ghost(_) => None,
+            Position::Node(node) => Some(&mut node.elem),
+        }
+    }
+
+    // The element after the cursor, without moving
+    pub fn peek_next(&mut self) -> Option<&mut T> {
+        self.next_link().as_mut().map(|node| &mut node.elem)
+    }
+
+    // Step onto the next element, returning false if there isn't one. The
+    // cursor stays on the last element rather than running off the end,
+    // so that .insert_after can still append there
+    pub fn move_next(&mut self) -> bool {
+        // check before giving up the current position - once it's been
+        // swapped for its next link there's no way back to it
+        if self.next_link().is_none() {
+            return false;
+        }
+
+        // we have to take the position by value to get at its 'a
+        // reference - going through &mut self would only give us a borrow
+        // as long as this call
+        let link = match self.position.take() {
+            Some(Position::Ghost(head)) => head,
+            Some(Position::Node(node)) => &mut node.next,
+            None => return false,
+        };
+
+        self.position = link.as_deref_mut().map(Position::Node);
+
+        true
+    }
+
+    // Insert `elem` just after the cursor, without moving. Before the first
+    // element, that makes it the new first element
+    pub fn insert_after(&mut self, elem: T) {
+        let (link, pool, alloc) = self.link_and_pool();
+        let node = Node {
+            elem,
+            next: link.take(),
+        };
+
+        *link = Some(alloc_node(pool, alloc, node));
+    }
+
+    // Remove and return the element just after the cursor
+    pub fn remove_next(&mut self) -> Option<T> {
+        let pool_cap = self.pool_cap;
+        let (link, pool, _) = self.link_and_pool();
+
+        link.take().map(|node| {
+            let node = free_node(pool, pool_cap, node);
+
+            *link = node.next;
+
+            node.elem
+        })
+    }
+
+    // Cut the list after the cursor, returning everything after it as a new
+    // list with the same allocator and pool size. Before the first
+    // element, that's the whole list
+    pub fn split_after(&mut self) -> List<T, A> {
+        let mut rest = List::with_node_pool_in(self.pool_cap, self.alloc.clone());
+
+        rest.head = self.next_link().take();
+
+        rest
+    }
+
+    // Move every element of `other` in just after the cursor, in order,
+    // without moving the cursor. Each node keeps the allocator it came
+    // from, so `other` may use a different one. O(other's length), since we
+    // have to walk to its last node to link it up
+    pub fn splice_after(&mut self, mut other: List<T, A>) {
+        let Some(other_head) = other.head.take() else {
+            return;
+        };
+
+        let link = self.next_link();
+        let rest = link.take();
+        let mut last = link.insert(other_head);
+
+        // `while let Some(next) = last.next.as_mut() { last = next }` reads
+        // better, but today's borrow checker thinks the borrow in the loop
+        // condition is still alive when we assign to last.next below.
+        // Checking first and borrowing after gets around it
+        while last.next.is_some() {
+            last = last.next.as_mut().unwrap();
+        }
+
+        last.next = rest;
+    }
+
+    // The link to the node after the cursor
+    fn next_link(&mut self) -> &mut Link<T, A> {
+        self.link_and_pool().0
+    }
+
+    fn link_and_pool(&mut self) -> (&mut Link<T, A>, &mut NodePool<T, A>, &A) {
+        let link = match self.position.as_mut().expect("cursor has a position") {
+            Position::Ghost(head) => &mut **head,
+            Position::Node(node) => &mut node.next,
+        };
+
+        (link, self.pool, self.alloc)
+    }
+}
+
+// Written top first, i.e. in the order .pop returns elements, so parsing
+// "[3, 2, 1]" gives back a list with 3 on top
+impl<T: fmt::Display, A: NodeAllocator> fmt::Display for List<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        text::write_list(f, self.iter())
+    }
+}
+
+impl<T: FromStr> FromStr for List<T> {
+    type Err = ParseListError<T::Err>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut list = List::new();
+
+        // the last element written is the bottom of the stack, so it has
+        // to be pushed first
+        text::parse_list(s)?
+            .into_iter()
+            .rev()
+            .for_each(|elem| list.push(elem));
+
+        Ok(list)
+    }
+}
+
+// With the `serde` feature, a list serializes as a sequence, top first -
+// the same order as Display
+#[cfg(feature = "serde")]
+mod serde_impls {
+    use alloc::vec::Vec;
+    use serde::ser::SerializeSeq;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::List;
+    use crate::node_alloc::NodeAllocator;
+
+    impl<T: Serialize, A: NodeAllocator> Serialize for List<T, A> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut seq = serializer.serialize_seq(Some(self.iter().count()))?;
+
+            self.iter()
+                .try_for_each(|elem| seq.serialize_element(elem))?;
+
+            seq.end()
+        }
+    }
+
+    impl<'de, T: Deserialize<'de>> Deserialize<'de> for List<T> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let mut list = List::new();
+
+            // the bottom of the stack comes last, and has to be pushed first
+            Vec::<T>::deserialize(deserializer)?
+                .into_iter()
+                .rev()
+                .for_each(|elem| list.push(elem));
+
+            Ok(list)
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use super::List;
+    use super::{List, StackUnderflow};
+    use crate::node_alloc::{Bump, NodeAllocator};
+    use crate::test_utils::{panics, DropCounter};
+
+    crate::conformance_tests!(Stack, stack_conformance, List::new());
 
     #[test]
     fn basics() {
@@ -288,14 +898,14 @@ mod test {
         // popping empty list returns None
         assert_eq!(list.pop(), None);
 
-        [1, 2, 3].map(|x| list.push(x));
+        [1, 2, 3].into_iter().for_each(|x| list.push(x));
 
         // pops work
         assert_eq!(list.pop(), Some(3));
         assert_eq!(list.pop(), Some(2));
 
         // push some more
-        [4, 5].map(|x| list.push(x));
+        [4, 5].into_iter().for_each(|x| list.push(x));
 
         // pop until we stop
         assert_eq!(list.pop(), Some(5));
@@ -390,4 +1000,483 @@ mod test {
             assert_eq!(z, Some(&mut x));
         }
     }
+
+    #[test]
+    fn allocates_nodes_in_supplied_allocator() {
+        let bump = Bump::with_capacity(1024);
+        let mut list = List::new_in(&bump);
+
+        (0..10).for_each(|x| list.push(x));
+
+        assert_eq!(bump.allocations(), 10);
+        assert_eq!(bump.live(), 10);
+        assert!(list.iter().all(|x| bump.contains(x)));
+
+        assert_eq!(list.pop(), Some(9));
+        assert_eq!(bump.live(), 9);
+
+        // dropping the list gives the remaining nodes back - Bump panics
+        // if it's handed a pointer it didn't allocate
+        drop(list);
+
+        assert_eq!(bump.live(), 0);
+    }
+
+    #[test]
+    fn node_pool_reuses_nodes() {
+        let bump = Bump::with_capacity(1024);
+        let mut list = List::with_node_pool_in(2, &bump);
+
+        (0..3).for_each(|x| list.push(x));
+        (0..3).for_each(|_| {
+            list.pop();
+        });
+
+        // only 2 of the 3 popped nodes fit in the pool
+        assert_eq!(list.pool_len(), 2);
+        assert_eq!(bump.live(), 2);
+
+        // the next two pushes come out of the pool...
+        (0..2).for_each(|x| list.push(x));
+        assert_eq!(list.pool_len(), 0);
+        assert_eq!(bump.allocations(), 3);
+
+        // ...and the third goes back to the allocator
+        list.push(2);
+        assert_eq!(bump.allocations(), 4);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [2, 1, 0]);
+    }
+
+    #[test]
+    fn shrink_pool() {
+        let bump = Bump::with_capacity(1024);
+        let mut list = List::with_node_pool_in(8, &bump);
+
+        (0..4).for_each(|x| list.push(x));
+        while list.pop().is_some() {}
+
+        assert_eq!(list.pool_len(), 4);
+        assert_eq!(bump.live(), 4);
+
+        list.shrink_pool();
+
+        assert_eq!(list.pool_len(), 0);
+        assert_eq!(bump.live(), 0);
+    }
+
+    #[test]
+    fn drop_long_list_with_pool() {
+        let mut list = List::with_node_pool(16);
+
+        (0..100_000).for_each(|x| list.push(x));
+        (0..16).for_each(|_| {
+            list.pop();
+        });
+
+        // would overflow the stack if Drop recursed through the nodes
+        drop(list);
+    }
+
+    #[test]
+    fn display_and_parse() {
+        let mut list = List::new();
+
+        assert_eq!(list.to_string(), "[]");
+
+        [1, 2, 3].into_iter().for_each(|x| list.push(x));
+
+        // top first
+        assert_eq!(list.to_string(), "[3, 2, 1]");
+
+        let mut parsed: List<i32> = list.to_string().parse().unwrap();
+
+        assert_eq!(parsed.to_string(), "[3, 2, 1]");
+        assert_eq!(parsed.pop(), Some(3));
+
+        let err = "[3, two, 1]".parse::<List<i32>>().err();
+
+        assert_eq!(err.map(|err| err.position()), Some(4));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let mut list = List::new();
+
+        [1, 2, 3].into_iter().for_each(|x| list.push(x));
+
+        let json = serde_json::to_string(&list).unwrap();
+
+        assert_eq!(json, "[3,2,1]");
+
+        let mut list: List<i32> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(list.pop(), Some(3));
+
+        let bytes = bincode::serialize(&list).unwrap();
+        let mut list: List<i32> = bincode::deserialize(&bytes).unwrap();
+
+        assert_eq!(list.pop(), Some(2));
+        assert_eq!(list.pop(), Some(1));
+        assert_eq!(list.pop(), None);
+    }
+
+    #[test]
+    fn drops_each_element_once() {
+        let counter = DropCounter::new();
+        let mut list = List::with_node_pool(4);
+
+        (0..10).for_each(|_| list.push(counter.tracked()));
+
+        // a popped element belongs to whoever popped it - the node pool
+        // keeps the node, not the element
+        drop(list.pop());
+        drop(list.pop());
+
+        assert_eq!(counter.dropped(), 2);
+
+        drop(list);
+
+        assert_eq!(counter.created(), 10);
+        counter.assert_all_dropped_once();
+    }
+
+    #[test]
+    fn drop_carries_on_after_a_panic() {
+        let bump = Bump::with_capacity(1024);
+        let counter = DropCounter::new();
+        let mut list = List::new_in(&bump);
+
+        list.push(counter.tracked());
+        list.push(counter.panicking());
+        list.push(counter.tracked());
+
+        assert!(panics(|| drop(list)));
+
+        // the element under the panicking one is still dropped, and every
+        // node - including the panicking element's - is given back
+        counter.assert_all_dropped_once();
+        assert_eq!(bump.live(), 0);
+    }
+
+    // A list whose .iter gives `xs` in order
+    fn from_slice(xs: &[i32]) -> List<i32> {
+        let mut list = List::new();
+
+        xs.iter().rev().for_each(|&x| list.push(x));
+
+        list
+    }
+
+    fn to_vec<A: NodeAllocator>(list: &List<i32, A>) -> Vec<i32> {
+        list.iter().copied().collect()
+    }
+
+    #[test]
+    fn cursor_moves_forward() {
+        let mut list = from_slice(&[1, 2, 3]);
+        let mut cursor = list.cursor_mut();
+
+        assert_eq!(cursor.current(), None);
+        assert_eq!(cursor.peek_next(), Some(&mut 1));
+
+        assert!(cursor.move_next());
+        assert_eq!(cursor.current(), Some(&mut 1));
+
+        assert!(cursor.move_next());
+        *cursor.current().unwrap() *= 10;
+
+        assert!(cursor.move_next());
+        assert_eq!(cursor.peek_next(), None);
+
+        // stays on the last element
+        assert!(!cursor.move_next());
+        assert_eq!(cursor.current(), Some(&mut 3));
+
+        assert_eq!(to_vec(&list), [1, 20, 3]);
+        assert!(!List::<i32>::new().cursor_mut().move_next());
+    }
+
+    #[test]
+    fn cursor_insert_and_remove() {
+        let mut list = from_slice(&[1, 2, 3, 4, 5, 6]);
+        let mut cursor = list.cursor_mut();
+
+        // the 5th element, without popping the first four
+        (0..4).for_each(|_| {
+            cursor.move_next();
+        });
+        assert_eq!(cursor.remove_next(), Some(5));
+
+        cursor.insert_after(50);
+        assert_eq!(cursor.current(), Some(&mut 4));
+
+        // at the end
+        (0..2).for_each(|_| {
+            cursor.move_next();
+        });
+        assert_eq!(cursor.remove_next(), None);
+        cursor.insert_after(7);
+
+        assert_eq!(to_vec(&list), [1, 2, 3, 4, 50, 6, 7]);
+
+        // before the first element
+        let mut cursor = list.cursor_mut();
+
+        assert_eq!(cursor.remove_next(), Some(1));
+        cursor.insert_after(0);
+
+        assert_eq!(to_vec(&list), [0, 2, 3, 4, 50, 6, 7]);
+    }
+
+    #[test]
+    fn cursor_uses_the_node_pool() {
+        let bump = Bump::with_capacity(1024);
+        let mut list = List::with_node_pool_in(2, &bump);
+
+        (0..4).for_each(|x| list.push(x));
+
+        let mut cursor = list.cursor_mut();
+
+        cursor.move_next();
+        cursor.remove_next();
+        cursor.remove_next();
+
+        assert_eq!(list.pool_len(), 2);
+        assert_eq!(bump.live(), 4);
+
+        let mut cursor = list.cursor_mut();
+
+        cursor.insert_after(10);
+        cursor.insert_after(11);
+
+        assert_eq!(list.pool_len(), 0);
+        assert_eq!(bump.allocations(), 4);
+        assert_eq!(to_vec(&list), [11, 10, 3, 0]);
+    }
+
+    // A spliced-in node that came from another allocator goes back to that
+    // allocator, even after a trip through the pool
+    #[test]
+    fn pooled_nodes_keep_their_allocator() {
+        let (a, b) = (Bump::with_capacity(1024), Bump::with_capacity(1024));
+        let mut list = List::with_node_pool_in(4, &a);
+        let mut other = List::new_in(&b);
+
+        list.push(1);
+        other.push(2);
+        list.cursor_mut().splice_after(other);
+
+        assert_eq!(list.pop(), Some(2));
+        assert_eq!(list.pop(), Some(1));
+        assert_eq!(list.pool_len(), 2);
+
+        // the pool is last in, first out: 5 goes into a's spare, 6 into
+        // b's, and neither Bump is asked for more
+        list.push(5);
+        list.push(6);
+
+        let from: Vec<_> = list
+            .iter()
+            .map(|x| (a.contains(x), b.contains(x)))
+            .collect();
+
+        assert_eq!(from, [(false, true), (true, false)]);
+        assert_eq!((a.allocations(), b.allocations()), (1, 1));
+
+        drop(list);
+        assert_eq!((a.live(), b.live()), (0, 0));
+    }
+
+    #[test]
+    fn cursor_split_after() {
+        let mut list = from_slice(&[1, 2, 3, 4, 5]);
+        let mut cursor = list.cursor_mut();
+
+        cursor.move_next();
+        cursor.move_next();
+
+        let mut rest = cursor.split_after();
+
+        assert_eq!(cursor.peek_next(), None);
+        assert_eq!(to_vec(&list), [1, 2]);
+        assert_eq!(to_vec(&rest), [3, 4, 5]);
+
+        // before the first element, everything goes
+        let all = rest.cursor_mut().split_after();
+
+        assert!(rest.iter().next().is_none());
+        assert_eq!(to_vec(&all), [3, 4, 5]);
+    }
+
+    #[test]
+    fn cursor_splice_after() {
+        let mut list = from_slice(&[1, 4]);
+        let mut cursor = list.cursor_mut();
+
+        cursor.move_next();
+        cursor.splice_after(from_slice(&[2, 3]));
+        assert_eq!(cursor.current(), Some(&mut 1));
+
+        cursor.splice_after(List::new());
+
+        while cursor.move_next() {}
+        cursor.splice_after(from_slice(&[5]));
+
+        list.cursor_mut().splice_after(from_slice(&[-1, 0]));
+
+        assert_eq!(to_vec(&list), [-1, 0, 1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn cursor_splices_nodes_from_another_allocator() {
+        let (a, b) = (Bump::with_capacity(1024), Bump::with_capacity(1024));
+        let mut list = List::new_in(&a);
+        let mut other = List::new_in(&b);
+
+        (0..2).for_each(|x| list.push(x));
+        (2..4).for_each(|x| other.push(x));
+
+        list.cursor_mut().splice_after(other);
+
+        assert_eq!(to_vec(&list), [3, 2, 1, 0]);
+
+        // each node goes back to the allocator it came from
+        drop(list);
+
+        assert_eq!(a.live(), 0);
+        assert_eq!(b.live(), 0);
+    }
+
+    #[test]
+    fn forth_words() {
+        // the top of the stack is the first element
+        let mut list = from_slice(&[3, 2, 1]);
+
+        assert_eq!(list.peek_nth(2), Ok(&1));
+
+        list.dup().unwrap();
+        assert_eq!(to_vec(&list), [3, 3, 2, 1]);
+
+        list.drop_n(1).unwrap();
+        list.swap().unwrap();
+        assert_eq!(to_vec(&list), [2, 3, 1]);
+
+        list.over().unwrap();
+        assert_eq!(to_vec(&list), [3, 2, 3, 1]);
+
+        list.rot().unwrap();
+        assert_eq!(to_vec(&list), [3, 3, 2, 1]);
+
+        list.pick(3).unwrap();
+        assert_eq!(to_vec(&list), [1, 3, 3, 2, 1]);
+
+        list.roll(3).unwrap();
+        assert_eq!(to_vec(&list), [2, 1, 3, 3, 1]);
+
+        list.roll(0).unwrap();
+        list.drop_n(5).unwrap();
+        assert_eq!(list.peek(), None);
+        assert_eq!(list.drop_n(0), Ok(()));
+    }
+
+    #[test]
+    fn forth_words_underflow() {
+        let mut list = from_slice(&[1, 2]);
+        let underflow = |needed| StackUnderflow {
+            needed,
+            available: 2,
+        };
+
+        assert_eq!(list.peek_nth(2), Err(underflow(3)));
+        assert_eq!(list.rot(), Err(underflow(3)));
+        assert_eq!(list.pick(2), Err(underflow(3)));
+        assert_eq!(list.roll(5), Err(underflow(6)));
+        assert_eq!(list.drop_n(3), Err(underflow(3)));
+
+        // n + 1 doesn't overflow
+        assert_eq!(list.peek_nth(usize::MAX), Err(underflow(usize::MAX)));
+        assert_eq!(list.pick(usize::MAX), Err(underflow(usize::MAX)));
+        assert_eq!(list.roll(usize::MAX), Err(underflow(usize::MAX)));
+        assert_eq!(list.drop_n(usize::MAX), Err(underflow(usize::MAX)));
+
+        // nothing changed
+        assert_eq!(to_vec(&list), [1, 2]);
+
+        assert_eq!(
+            List::<i32>::new().dup().unwrap_err().to_string(),
+            "stack underflow: needed 1 element, found 0"
+        );
+        assert_eq!(
+            List::<i32>::new().swap().unwrap_err().to_string(),
+            "stack underflow: needed 2 elements, found 0"
+        );
+    }
+
+    #[test]
+    fn drop_n_pools_the_dropped_nodes() {
+        let mut list = List::with_node_pool(3);
+
+        (0..5).for_each(|x| list.push(x));
+
+        list.drop_n(2).unwrap();
+
+        assert_eq!(list.pool_len(), 2);
+        assert_eq!(to_vec(&list), [2, 1, 0]);
+
+        // the pool keeps no more than its cap
+        list.drop_n(2).unwrap();
+
+        assert_eq!(list.pool_len(), 3);
+        assert_eq!(to_vec(&list), [0]);
+    }
+
+    #[test]
+    fn drop_n_carries_on_after_a_panic() {
+        let bump = Bump::with_capacity(1024);
+        let counter = DropCounter::new();
+        let mut list = List::new_in(&bump);
+
+        list.push(counter.tracked());
+        list.push(counter.tracked());
+        list.push(counter.panicking());
+        list.push(counter.tracked());
+
+        assert!(panics(|| {
+            list.drop_n(3).unwrap();
+        }));
+
+        // the element under the panicking one is still dropped, and only
+        // the bottom node is left
+        assert_eq!(counter.dropped(), 3);
+        assert_eq!(list.iter().count(), 1);
+
+        drop(list);
+
+        counter.assert_all_dropped_once();
+        assert_eq!(bump.live(), 0);
+    }
+
+    #[test]
+    fn forth_words_relink_rather_than_allocate() {
+        let bump = Bump::with_capacity(1024);
+        let mut list = List::with_node_pool_in(4, &bump);
+
+        (0..4).for_each(|x| list.push(x));
+
+        list.swap().unwrap();
+        list.rot().unwrap();
+        list.roll(3).unwrap();
+
+        assert_eq!(bump.allocations(), 4);
+        assert_eq!(to_vec(&list), [0, 1, 2, 3]);
+
+        // dropped nodes go to the pool, and dup takes them back out
+        list.drop_n(2).unwrap();
+        list.dup().unwrap();
+        list.over().unwrap();
+
+        assert_eq!(bump.allocations(), 4);
+        assert_eq!(to_vec(&list), [2, 2, 2, 3]);
+    }
 }
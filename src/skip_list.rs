@@ -0,0 +1,905 @@
+// An ordered map and set built on a skip list
+//
+// A skip list is a sorted singly-linked list with express lanes. Every
+// node is on level 0, the ordinary list. About half the nodes are also on
+// level 1, a quarter on level 2, and so on - each level is a sorted list
+// skipping over the nodes that aren't tall enough to be on it:
+//
+//      level 2:  head ------------------------> 7 ------------> None
+//      level 1:  head --------> 3 ------------> 7 ----> 9 ----> None
+//      level 0:  head -> 1 ---> 3 -> 4 -> 5 --> 7 ----> 9 -> 12 -> None
+//
+// To find a key we start on the top level and move right while the next
+// key is smaller, then drop down a level and repeat. Each level roughly
+// halves the remaining distance, so searches take O(log n) steps on
+// average, as in a balanced tree, without any rebalancing - a node's
+// height is decided by a coin toss when it's inserted.
+//
+// As in arena::List, nodes live in a Vec and links are indices into it,
+// so there's no unsafe code and no Rc<RefCell<_>>
+use alloc::vec;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::fmt;
+use core::mem;
+use core::ops::{Bound, RangeBounds};
+
+use crate::rng::Rng;
+
+// enough levels for 2^32 elements at the default promotion chance of 1/2
+const MAX_LEVEL: usize = 32;
+
+// An index into SkipListMap::nodes
+type Link = Option<usize>;
+
+struct Node<K, V> {
+    key: K,
+    value: V,
+    // forward[i] is the next node on level i. The node is on levels
+    // 0..forward.len()
+    forward: Vec<Link>,
+}
+
+pub struct SkipListMap<K, V> {
+    nodes: Vec<Option<Node<K, V>>>,
+    // slots in nodes freed by .remove, to be reused by .insert
+    free: Vec<usize>,
+    // the first node on each level
+    head: [Link; MAX_LEVEL],
+    // the number of levels currently in use
+    level: usize,
+    len: usize,
+    rng: Rng,
+}
+
+// For each level, the last node whose key is smaller than the one we're
+// looking for, i.e. the node whose forward link we'd need to change to
+// insert or remove at that level. None means the head
+type Predecessors = [Link; MAX_LEVEL];
+
+impl<K: Ord, V> SkipListMap<K, V> {
+    pub fn new() -> Self {
+        Self::with_rng(Rng::default())
+    }
+
+    // Node heights come from `seed`, so two maps built with the same seed
+    // and the same inserts have exactly the same shape
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_rng(Rng::new(seed))
+    }
+
+    fn with_rng(rng: Rng) -> Self {
+        Self {
+            nodes: Vec::new(),
+            free: Vec::new(),
+            head: [None; MAX_LEVEL],
+            level: 0,
+            len: 0,
+            rng,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.free.clear();
+        self.head = [None; MAX_LEVEL];
+        self.level = 0;
+        self.len = 0;
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).map(|index| &self.node(index).value)
+    }
+
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).map(|index| {
+            let node = self.node(index);
+
+            (&node.key, &node.value)
+        })
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).map(|index| &mut self.node_mut(index).value)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).is_some()
+    }
+
+    // Insert a key-value pair, returning the old value if the key was
+    // already present. As with BTreeMap, the key itself isn't updated
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let (update, candidate) = self.search(&key);
+
+        match candidate.filter(|&index| self.node(index).key == key) {
+            Some(index) => Some(mem::replace(&mut self.node_mut(index).value, value)),
+            None => {
+                self.insert_new(update, key, value);
+
+                None
+            }
+        }
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.remove_entry(key).map(|(_, value)| value)
+    }
+
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (update, candidate) = self.search(key);
+
+        candidate
+            .filter(|&index| self.node(index).key.borrow() == key)
+            .map(|index| self.unlink(&update, index))
+    }
+
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V> {
+        match self.find(&key) {
+            Some(index) => Entry::Occupied(OccupiedEntry { map: self, index }),
+            None => Entry::Vacant(VacantEntry { map: self, key }),
+        }
+    }
+
+    pub fn first(&self) -> Option<(&K, &V)> {
+        self.head[0].map(|index| {
+            let node = self.node(index);
+
+            (&node.key, &node.value)
+        })
+    }
+
+    // There are no back links, but the express lanes get us to the last
+    // node in O(log n) steps: run as far right as possible on each level
+    pub fn last(&self) -> Option<(&K, &V)> {
+        let mut last = None;
+
+        for level in (0..self.level).rev() {
+            while let Some(next) = self.forward(last, level) {
+                last = Some(next);
+            }
+        }
+
+        last.map(|index| {
+            let node = self.node(index);
+
+            (&node.key, &node.value)
+        })
+    }
+
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        // the first node is the first on every level it's on, so its
+        // predecessor is the head everywhere
+        self.head[0].map(|index| self.unlink(&[None; MAX_LEVEL], index))
+    }
+
+    pub fn iter(&self) -> Range<'_, K, V> {
+        Range {
+            map: self,
+            next: self.head[0],
+            end: None,
+        }
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter().map(|(_, value)| value)
+    }
+
+    // Iterate over the entries whose keys fall in `range`, in order
+    pub fn range<Q, R>(&self, range: R) -> Range<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        // the first node in the range...
+        let start = match range.start_bound() {
+            Bound::Included(key) => self.lower_bound(key),
+            Bound::Excluded(key) => self.upper_bound(key),
+            Bound::Unbounded => self.head[0],
+        };
+
+        // ...and the first node after it
+        let end = match range.end_bound() {
+            Bound::Included(key) => self.upper_bound(key),
+            Bound::Excluded(key) => self.lower_bound(key),
+            Bound::Unbounded => None,
+        };
+
+        // if the range is backwards, e.g. 5..3, start is after end, and
+        // walking from start would never reach end
+        let backwards = match (start, end) {
+            (Some(start), Some(end)) => self.node(start).key > self.node(end).key,
+            (None, Some(_)) => true,
+            _ => false,
+        };
+
+        Range {
+            map: self,
+            next: if backwards { None } else { start },
+            end,
+        }
+    }
+
+    fn node(&self, index: usize) -> &Node<K, V> {
+        self.nodes[index]
+            .as_ref()
+            .expect("links only point to occupied slots")
+    }
+
+    fn node_mut(&mut self, index: usize) -> &mut Node<K, V> {
+        self.nodes[index]
+            .as_mut()
+            .expect("links only point to occupied slots")
+    }
+
+    // The node after `from` on `level`, where None is the head
+    fn forward(&self, from: Link, level: usize) -> Link {
+        match from {
+            Some(index) => self.node(index).forward[level],
+            None => self.head[level],
+        }
+    }
+
+    fn set_forward(&mut self, from: Link, level: usize, to: Link) {
+        match from {
+            Some(index) => self.node_mut(index).forward[level] = to,
+            None => self.head[level] = to,
+        }
+    }
+
+    // Walk down from the top level, recording on each level the last node
+    // with a key smaller than `key`. The node after that on level 0 is the
+    // first node whose key is >= key
+    fn search<Q>(&self, key: &Q) -> (Predecessors, Link)
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.search_by(|node_key| node_key.borrow() < key)
+    }
+
+    // The same walk, moving right while `before` holds for the next key
+    fn search_by<F>(&self, mut before: F) -> (Predecessors, Link)
+    where
+        F: FnMut(&K) -> bool,
+    {
+        let mut update = [None; MAX_LEVEL];
+        let mut current = None;
+
+        for level in (0..self.level).rev() {
+            while let Some(next) = self.forward(current, level) {
+                if !before(&self.node(next).key) {
+                    break;
+                }
+
+                current = Some(next);
+            }
+
+            update[level] = current;
+        }
+
+        (update, self.forward(current, 0))
+    }
+
+    fn find<Q>(&self, key: &Q) -> Link
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.search(key)
+            .1
+            .filter(|&index| self.node(index).key.borrow() == key)
+    }
+
+    // the first node with a key >= key
+    fn lower_bound<Q>(&self, key: &Q) -> Link
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.search(key).1
+    }
+
+    // the first node with a key > key
+    fn upper_bound<Q>(&self, key: &Q) -> Link
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.search_by(|node_key| node_key.borrow() <= key).1
+    }
+
+    // Toss coins until we get tails - the number of heads (plus one) is
+    // the node's height. Each trailing 1 bit of a random number is a head
+    fn random_level(&mut self) -> usize {
+        let heads = self.rng.next_u64().trailing_ones() as usize;
+
+        (heads + 1).min(MAX_LEVEL)
+    }
+
+    fn insert_new(&mut self, mut update: Predecessors, key: K, value: V) -> usize {
+        let height = self.random_level();
+
+        // the new node is taller than any before it, so on the new levels
+        // its predecessor is the head
+        if height > self.level {
+            update[self.level..height].fill(None);
+            self.level = height;
+        }
+
+        let node = Node {
+            key,
+            value,
+            forward: vec![None; height],
+        };
+
+        let index = match self.free.pop() {
+            Some(index) => {
+                self.nodes[index] = Some(node);
+                index
+            }
+            None => {
+                self.nodes.push(Some(node));
+                self.nodes.len() - 1
+            }
+        };
+
+        // splice the node in on each of its levels, the same as pushing
+        // into the middle of second::List
+        for (level, &prev) in update.iter().enumerate().take(height) {
+            let next = self.forward(prev, level);
+
+            self.node_mut(index).forward[level] = next;
+            self.set_forward(prev, level, Some(index));
+        }
+
+        self.len += 1;
+
+        index
+    }
+
+    fn unlink(&mut self, update: &Predecessors, index: usize) -> (K, V) {
+        let node = self.nodes[index]
+            .take()
+            .expect("links only point to occupied slots");
+
+        for (level, &next) in node.forward.iter().enumerate() {
+            self.set_forward(update[level], level, next);
+        }
+
+        // drop any levels that are now empty
+        while self.level > 0 && self.head[self.level - 1].is_none() {
+            self.level -= 1;
+        }
+
+        self.free.push(index);
+        self.len -= 1;
+
+        (node.key, node.value)
+    }
+}
+
+impl<K: Ord, V> Default for SkipListMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord + fmt::Debug, V: fmt::Debug> fmt::Debug for SkipListMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for SkipListMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::new();
+
+        map.extend(iter);
+
+        map
+    }
+}
+
+impl<K: Ord, V> Extend<(K, V)> for SkipListMap<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|(key, value)| {
+            self.insert(key, value);
+        });
+    }
+}
+
+impl<'a, K: Ord, V> IntoIterator for &'a SkipListMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Range<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+// An iterator over a run of entries, from `next` up to but not including
+// `end` (None meaning the end of the list)
+pub struct Range<'a, K, V> {
+    map: &'a SkipListMap<K, V>,
+    next: Link,
+    end: Link,
+}
+
+impl<'a, K: Ord, V> Iterator for Range<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next
+            .filter(|&index| Some(index) != self.end)
+            .map(|index| {
+                let node = self.map.node(index);
+
+                self.next = node.forward[0];
+
+                (&node.key, &node.value)
+            })
+    }
+}
+
+// A view into a single entry of a map, which may or may not be occupied,
+// as returned by SkipListMap::entry
+pub enum Entry<'a, K, V> {
+    Occupied(OccupiedEntry<'a, K, V>),
+    Vacant(VacantEntry<'a, K, V>),
+}
+
+pub struct OccupiedEntry<'a, K, V> {
+    map: &'a mut SkipListMap<K, V>,
+    index: usize,
+}
+
+pub struct VacantEntry<'a, K, V> {
+    map: &'a mut SkipListMap<K, V>,
+    key: K,
+}
+
+impl<'a, K: Ord, V> Entry<'a, K, V> {
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    pub fn and_modify<F: FnOnce(&mut V)>(mut self, f: F) -> Self {
+        if let Entry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+
+        self
+    }
+}
+
+impl<'a, K: Ord, V> OccupiedEntry<'a, K, V> {
+    pub fn key(&self) -> &K {
+        &self.map.node(self.index).key
+    }
+
+    pub fn get(&self) -> &V {
+        &self.map.node(self.index).value
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        &mut self.map.node_mut(self.index).value
+    }
+
+    pub fn into_mut(self) -> &'a mut V {
+        &mut self.map.node_mut(self.index).value
+    }
+
+    pub fn insert(&mut self, value: V) -> V {
+        mem::replace(self.get_mut(), value)
+    }
+
+    pub fn remove(self) -> V {
+        self.remove_entry().1
+    }
+
+    pub fn remove_entry(self) -> (K, V) {
+        // we need the predecessors of this exact node - with unique keys,
+        // searching for its key finds them
+        let index = self.index;
+        let map = self.map;
+        let (update, _) = map.search(&map.node(index).key);
+
+        map.unlink(&update, index)
+    }
+}
+
+impl<'a, K: Ord, V> VacantEntry<'a, K, V> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    pub fn insert(self, value: V) -> &'a mut V {
+        let map = self.map;
+        let (update, _) = map.search(&self.key);
+        let index = map.insert_new(update, self.key, value);
+
+        &mut map.node_mut(index).value
+    }
+}
+
+// An ordered set, i.e. a SkipListMap with no values
+pub struct SkipListSet<K> {
+    map: SkipListMap<K, ()>,
+}
+
+impl<K: Ord> SkipListSet<K> {
+    pub fn new() -> Self {
+        Self {
+            map: SkipListMap::new(),
+        }
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            map: SkipListMap::with_seed(seed),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.map.clear()
+    }
+
+    // Returns whether the value was newly inserted
+    pub fn insert(&mut self, value: K) -> bool {
+        match self.map.entry(value) {
+            Entry::Occupied(_) => false,
+            Entry::Vacant(entry) => {
+                entry.insert(());
+                true
+            }
+        }
+    }
+
+    pub fn contains<Q>(&self, value: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.map.contains_key(value)
+    }
+
+    pub fn get<Q>(&self, value: &Q) -> Option<&K>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.map.get_key_value(value).map(|(key, _)| key)
+    }
+
+    // Returns whether the value was present
+    pub fn remove<Q>(&mut self, value: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.map.remove(value).is_some()
+    }
+
+    pub fn take<Q>(&mut self, value: &Q) -> Option<K>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.map.remove_entry(value).map(|(key, _)| key)
+    }
+
+    pub fn first(&self) -> Option<&K> {
+        self.map.first().map(|(key, _)| key)
+    }
+
+    pub fn last(&self) -> Option<&K> {
+        self.map.last().map(|(key, _)| key)
+    }
+
+    pub fn pop_first(&mut self) -> Option<K> {
+        self.map.pop_first().map(|(key, _)| key)
+    }
+
+    pub fn iter(&self) -> SetRange<'_, K> {
+        SetRange(self.map.iter())
+    }
+
+    pub fn range<Q, R>(&self, range: R) -> SetRange<'_, K>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        SetRange(self.map.range(range))
+    }
+}
+
+impl<K: Ord> Default for SkipListSet<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord + fmt::Debug> fmt::Debug for SkipListSet<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl<K: Ord> FromIterator<K> for SkipListSet<K> {
+    fn from_iter<I: IntoIterator<Item = K>>(iter: I) -> Self {
+        let mut set = Self::new();
+
+        set.extend(iter);
+
+        set
+    }
+}
+
+impl<K: Ord> Extend<K> for SkipListSet<K> {
+    fn extend<I: IntoIterator<Item = K>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|value| {
+            self.insert(value);
+        });
+    }
+}
+
+impl<'a, K: Ord> IntoIterator for &'a SkipListSet<K> {
+    type Item = &'a K;
+    type IntoIter = SetRange<'a, K>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub struct SetRange<'a, K>(Range<'a, K, ()>);
+
+impl<'a, K: Ord> Iterator for SetRange<'a, K> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(key, _)| key)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Entry, SkipListMap, SkipListSet};
+    use crate::rng::Rng;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn insert_get_remove() {
+        let mut map = SkipListMap::new();
+
+        assert_eq!(map.get(&1), None);
+        assert_eq!(map.insert(3, "c"), None);
+        assert_eq!(map.insert(1, "a"), None);
+        assert_eq!(map.insert(2, "b"), None);
+        assert_eq!(map.insert(2, "B"), Some("b"));
+
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.get(&2), Some(&"B"));
+
+        if let Some(value) = map.get_mut(&3) {
+            *value = "C";
+        }
+
+        assert_eq!(map.remove(&1), Some("a"));
+        assert_eq!(map.remove(&1), None);
+        assert_eq!(map.iter().collect::<Vec<_>>(), [(&2, &"B"), (&3, &"C")]);
+    }
+
+    #[test]
+    fn borrowed_keys() {
+        let mut map = SkipListMap::new();
+
+        map.insert(String::from("b"), 2);
+        map.insert(String::from("a"), 1);
+
+        assert_eq!(map.get("a"), Some(&1));
+        assert!(map.contains_key("b"));
+        assert_eq!(map.remove("b"), Some(2));
+    }
+
+    #[test]
+    fn first_and_last() {
+        let mut map = SkipListMap::new();
+
+        assert_eq!(map.first(), None);
+        assert_eq!(map.last(), None);
+
+        (0..100).rev().for_each(|x| {
+            map.insert(x, x * 10);
+        });
+
+        assert_eq!(map.first(), Some((&0, &0)));
+        assert_eq!(map.last(), Some((&99, &990)));
+        assert_eq!(map.pop_first(), Some((0, 0)));
+        assert_eq!(map.first(), Some((&1, &10)));
+    }
+
+    #[test]
+    fn range() {
+        let map: SkipListMap<i32, ()> = (0..20).map(|x| (x * 2, ())).collect();
+        let keys = |range: super::Range<'_, i32, ()>| range.map(|(&k, _)| k).collect::<Vec<_>>();
+
+        assert_eq!(keys(map.range(4..10)), [4, 6, 8]);
+        assert_eq!(keys(map.range(3..=10)), [4, 6, 8, 10]);
+        assert_eq!(keys(map.range(..5)), [0, 2, 4]);
+        assert_eq!(keys(map.range(35..)), [36, 38]);
+        assert_eq!(keys(map.range(10..10)), Vec::<i32>::new());
+        assert_eq!(keys(map.range(100..)), Vec::<i32>::new());
+        assert_eq!(keys(map.range(..)).len(), 20);
+
+        use std::ops::Bound::{Excluded, Included};
+
+        assert_eq!(keys(map.range((Excluded(4), Included(8)))), [6, 8]);
+        assert_eq!(
+            keys(map.range((Included(11), Excluded(9)))),
+            Vec::<i32>::new()
+        );
+    }
+
+    #[test]
+    fn entry() {
+        let mut map: SkipListMap<&str, i32> = SkipListMap::new();
+
+        *map.entry("a").or_insert(0) += 1;
+        *map.entry("a").or_insert(0) += 1;
+        *map.entry("b").or_default() += 5;
+        map.entry("b").and_modify(|x| *x *= 2).or_insert(0);
+        map.entry("c").and_modify(|x| *x *= 2).or_insert_with(|| 7);
+
+        assert_eq!(
+            map.iter().collect::<Vec<_>>(),
+            [(&"a", &2), (&"b", &10), (&"c", &7)]
+        );
+
+        match map.entry("b") {
+            Entry::Occupied(mut entry) => {
+                assert_eq!(entry.key(), &"b");
+                assert_eq!(entry.insert(11), 10);
+                assert_eq!(entry.remove(), 11);
+            }
+            Entry::Vacant(_) => unreachable!(),
+        }
+
+        match map.entry("z") {
+            Entry::Vacant(entry) => assert_eq!(entry.into_key(), "z"),
+            Entry::Occupied(_) => unreachable!(),
+        }
+
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn same_seed_same_shape() {
+        let build = |seed| {
+            let mut map = SkipListMap::with_seed(seed);
+
+            (0..50).for_each(|x| {
+                map.insert(x, ());
+            });
+
+            map.nodes
+                .iter()
+                .map(|node| node.as_ref().unwrap().forward.len())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(build(7), build(7));
+    }
+
+    #[test]
+    fn matches_btree_map() {
+        let mut rng = Rng::new(1);
+        let mut map = SkipListMap::with_seed(2);
+        let mut model = BTreeMap::new();
+
+        for _ in 0..2000 {
+            let key = rng.below(200);
+
+            if rng.below(3) == 0 {
+                assert_eq!(map.remove(&key), model.remove(&key));
+            } else {
+                assert_eq!(map.insert(key, key * 2), model.insert(key, key * 2));
+            }
+        }
+
+        assert_eq!(map.len(), model.len());
+        assert!(map.iter().eq(model.iter()));
+        assert!(map.range(50..150).eq(model.range(50..150)));
+        assert_eq!(map.last(), model.last_key_value());
+    }
+
+    #[test]
+    fn set() {
+        let mut set: SkipListSet<i32> = [5, 1, 3].into_iter().collect();
+
+        assert!(set.insert(2));
+        assert!(!set.insert(3));
+        assert!(set.contains(&1));
+        assert!(set.remove(&1));
+        assert!(!set.remove(&1));
+
+        assert_eq!(set.len(), 3);
+        assert_eq!(set.first(), Some(&2));
+        assert_eq!(set.last(), Some(&5));
+        assert_eq!(set.range(3..).collect::<Vec<_>>(), [&3, &5]);
+        assert_eq!(set.take(&5), Some(5));
+        assert_eq!(set.iter().collect::<Vec<_>>(), [&2, &3]);
+    }
+}
@@ -0,0 +1,113 @@
+// Helpers shared by the lists' tests
+//
+// DropCounter hands out Tracked values and counts how many times each one
+// gets dropped. Pushing Tracked values onto a list and then dropping the
+// list checks that the list drops every element exactly once: not zero
+// times (a leak), and not twice (a double free).
+//
+// A Tracked value can also be made to panic when it's dropped, to check
+// what a list does when an element's drop panics halfway through the
+// list's own drop. std's LinkedList keeps going and frees the rest of the
+// nodes while the panic unwinds, and so should we:
+//
+//      let counter = DropCounter::new();
+//      let mut list = List::new();
+//
+//      list.push(counter.tracked());
+//      list.push(counter.panicking());
+//      list.push(counter.tracked());
+//
+//      assert!(panics(|| drop(list)));
+//      counter.assert_all_dropped_once();
+use std::cell::RefCell;
+use std::panic::{self, AssertUnwindSafe};
+use std::rc::Rc;
+
+#[derive(Default)]
+pub(crate) struct DropCounter {
+    // how many times the value with each id has been dropped
+    drops: Rc<RefCell<Vec<usize>>>,
+}
+
+impl DropCounter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // A new value whose drops are counted
+    pub fn tracked(&self) -> Tracked {
+        self.make(false)
+    }
+
+    // A new value whose drops are counted, and which panics when dropped
+    pub fn panicking(&self) -> Tracked {
+        self.make(true)
+    }
+
+    // How many values have been handed out
+    pub fn created(&self) -> usize {
+        self.drops.borrow().len()
+    }
+
+    // How many values have been dropped at least once
+    pub fn dropped(&self) -> usize {
+        self.drops.borrow().iter().filter(|&&n| n > 0).count()
+    }
+
+    // How many times the value with `id` has been dropped
+    pub fn drops_of(&self, id: usize) -> usize {
+        self.drops.borrow()[id]
+    }
+
+    #[track_caller]
+    pub fn assert_all_dropped_once(&self) {
+        let drops = self.drops.borrow();
+
+        for (id, &n) in drops.iter().enumerate() {
+            assert_eq!(n, 1, "value {id} was dropped {n} times");
+        }
+    }
+
+    fn make(&self, panics: bool) -> Tracked {
+        let mut drops = self.drops.borrow_mut();
+        let id = drops.len();
+
+        drops.push(0);
+
+        Tracked {
+            id,
+            panics,
+            drops: Rc::clone(&self.drops),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Tracked {
+    id: usize,
+    panics: bool,
+    drops: Rc<RefCell<Vec<usize>>>,
+}
+
+impl Tracked {
+    pub fn id(&self) -> usize {
+        self.id
+    }
+}
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        // count the drop before panicking, so that the panicking value
+        // still shows up as dropped exactly once
+        self.drops.borrow_mut()[self.id] += 1;
+
+        if self.panics {
+            panic!("value {} panicked while being dropped", self.id);
+        }
+    }
+}
+
+// Run `f`, returning whether it panicked
+pub(crate) fn panics(f: impl FnOnce()) -> bool {
+    panic::catch_unwind(AssertUnwindSafe(f)).is_err()
+}
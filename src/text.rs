@@ -0,0 +1,242 @@
+// Text serialisation shared by every list's Display and FromStr impls
+//
+// All the lists read and write the same syntax as a Vec's Debug output:
+//
+//      []
+//      [1, 2, 3]
+//
+// Elements are written with their Display impl and parsed with their
+// FromStr impl. Whitespace around elements and brackets is ignored when
+// parsing. There's no quoting or escaping, so an element can't contain a
+// comma or a bracket - enough for numbers and simple words, which is what
+// our config files hold.
+//
+// What order the elements come in is up to each list, and each module
+// documents it next to its impls - e.g. a stack is written top first, so
+// that parsing the text gives back the same stack.
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt;
+use core::str::FromStr;
+
+// Write `elems` as [a, b, c]
+pub(crate) fn write_list<I>(f: &mut fmt::Formatter<'_>, elems: I) -> fmt::Result
+where
+    I: IntoIterator,
+    I::Item: fmt::Display,
+{
+    let mut list = ListWriter::new(f);
+
+    for elem in elems {
+        list.entry(&elem);
+    }
+
+    list.finish()
+}
+
+// Writes [a, b, c] an element at a time, for lists that can't hand
+// write_list an iterator. Like Formatter::debug_list, the first error is
+// kept and returned from finish
+pub(crate) struct ListWriter<'a, 'b> {
+    f: &'a mut fmt::Formatter<'b>,
+    result: fmt::Result,
+    empty: bool,
+}
+
+impl<'a, 'b> ListWriter<'a, 'b> {
+    pub(crate) fn new(f: &'a mut fmt::Formatter<'b>) -> Self {
+        let result = f.write_str("[");
+
+        ListWriter {
+            f,
+            result,
+            empty: true,
+        }
+    }
+
+    pub(crate) fn entry(&mut self, elem: &dyn fmt::Display) -> &mut Self {
+        self.result = self.result.and_then(|()| {
+            if !self.empty {
+                self.f.write_str(", ")?;
+            }
+
+            write!(self.f, "{elem}")
+        });
+
+        self.empty = false;
+
+        self
+    }
+
+    pub(crate) fn finish(&mut self) -> fmt::Result {
+        self.result.and_then(|()| self.f.write_str("]"))
+    }
+}
+
+// Parse [a, b, c] into its elements, in the order they're written
+pub fn parse_list<T: FromStr>(s: &str) -> Result<Vec<T>, ParseListError<T::Err>> {
+    let error = |position, kind| ParseListError { position, kind };
+
+    let start = s.len() - s.trim_start().len();
+
+    let inner_start = match s[start..].strip_prefix('[') {
+        Some(_) => start + 1,
+        None => return Err(error(start, ParseListErrorKind::ExpectedOpen)),
+    };
+
+    // the closing bracket is the first ] after the opening one
+    let Some(close) = s[inner_start..].find(']').map(|i| inner_start + i) else {
+        return Err(error(s.len(), ParseListErrorKind::ExpectedClose));
+    };
+
+    let inner = &s[inner_start..close];
+    let mut elems = Vec::new();
+    let mut offset = inner_start;
+
+    // split on commas, unless there's nothing but whitespace in there
+    let parts = inner.split(',').filter(|_| !inner.trim().is_empty());
+
+    for part in parts {
+        let trimmed = part.trim();
+        // point at the element itself, not the whitespace before it
+        let position = offset + (part.len() - part.trim_start().len());
+
+        if let Some(i) = trimmed.find('[') {
+            return Err(error(position + i, ParseListErrorKind::UnexpectedOpen));
+        }
+
+        if trimmed.is_empty() {
+            return Err(error(position, ParseListErrorKind::EmptyElement));
+        }
+
+        match trimmed.parse() {
+            Ok(elem) => elems.push(elem),
+            Err(err) => return Err(error(position, ParseListErrorKind::Element(err))),
+        }
+
+        // skip over this part and its trailing comma
+        offset += part.len() + 1;
+    }
+
+    // anything after the ] other than whitespace is an error
+    let rest = &s[close + 1..];
+
+    if !rest.trim().is_empty() {
+        let position = close + 1 + (rest.len() - rest.trim_start().len());
+
+        return Err(error(position, ParseListErrorKind::TrailingInput));
+    }
+
+    Ok(elems)
+}
+
+// Why a list failed to parse, and where
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseListError<E> {
+    position: usize,
+    kind: ParseListErrorKind<E>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseListErrorKind<E> {
+    // the text didn't start with [
+    ExpectedOpen,
+    // the text ended before the closing ]
+    ExpectedClose,
+    // a [ inside the list - lists don't nest
+    UnexpectedOpen,
+    // nothing between two commas, or after the last one
+    EmptyElement,
+    // something other than whitespace after the closing ]
+    TrailingInput,
+    // an element's FromStr impl failed
+    Element(E),
+}
+
+impl<E> ParseListError<E> {
+    // The byte offset in the input where things went wrong
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn kind(&self) -> &ParseListErrorKind<E> {
+        &self.kind
+    }
+
+    pub fn into_kind(self) -> ParseListErrorKind<E> {
+        self.kind
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for ParseListError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let position = self.position;
+
+        match &self.kind {
+            ParseListErrorKind::ExpectedOpen => write!(f, "expected '[' at position {position}"),
+            ParseListErrorKind::ExpectedClose => write!(f, "expected ']' at position {position}"),
+            ParseListErrorKind::UnexpectedOpen => {
+                write!(f, "unexpected '[' at position {position}")
+            }
+            ParseListErrorKind::EmptyElement => write!(f, "empty element at position {position}"),
+            ParseListErrorKind::TrailingInput => {
+                write!(f, "unexpected input after ']' at position {position}")
+            }
+            ParseListErrorKind::Element(err) => {
+                write!(f, "invalid element at position {position}: {err}")
+            }
+        }
+    }
+}
+
+impl<E: Error + 'static> Error for ParseListError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            ParseListErrorKind::Element(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_list, ParseListErrorKind};
+
+    #[test]
+    fn parses() {
+        assert_eq!(parse_list::<i32>("[]"), Ok(vec![]));
+        assert_eq!(parse_list::<i32>("  [ ]  "), Ok(vec![]));
+        assert_eq!(parse_list::<i32>("[1, 2, 3]"), Ok(vec![1, 2, 3]));
+        assert_eq!(parse_list::<i32>(" [1,2 ,  3 ]\n"), Ok(vec![1, 2, 3]));
+        assert_eq!(
+            parse_list::<String>("[a, b c]"),
+            Ok(vec!["a".to_string(), "b c".to_string()])
+        );
+    }
+
+    #[test]
+    fn reports_positions() {
+        let err = |s| {
+            let err = parse_list::<i32>(s).unwrap_err();
+
+            (err.position(), err.into_kind())
+        };
+
+        assert_eq!(err("1, 2]"), (0, ParseListErrorKind::ExpectedOpen));
+        assert_eq!(err("  x"), (2, ParseListErrorKind::ExpectedOpen));
+        assert_eq!(err("[1, 2"), (5, ParseListErrorKind::ExpectedClose));
+        assert_eq!(err("[1, [2]]"), (4, ParseListErrorKind::UnexpectedOpen));
+        assert_eq!(err("[1, , 3]"), (4, ParseListErrorKind::EmptyElement));
+        assert_eq!(err("[1, 2,]"), (6, ParseListErrorKind::EmptyElement));
+        assert_eq!(err("[1] 2"), (4, ParseListErrorKind::TrailingInput));
+
+        let (position, kind) = err("[1,  x, 3]");
+
+        assert_eq!(position, 5);
+        assert!(matches!(kind, ParseListErrorKind::Element(_)));
+        assert_eq!(
+            parse_list::<i32>("[1,  x, 3]").unwrap_err().to_string(),
+            "invalid element at position 5: invalid digit found in string"
+        );
+    }
+}
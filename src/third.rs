@@ -1,4 +1,17 @@
-use std::rc::Rc;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::mem;
+use core::str::FromStr;
+
+use crate::dot;
+use crate::text::{self, ParseListError};
+use crate::traits;
+
+pub mod snapshot;
 
 pub struct List<T> {
     head: Link<T>,
@@ -20,9 +33,25 @@ pub struct Iter<'a, T> {
     next: Option<&'a Node<T>>,
 }
 
+// Moves the elements out of the list, head first. Nodes only this list
+// points to are unwrapped and their elements moved out. Once we reach a node
+// that's shared with another list, every node after it is shared too - the
+// other list keeps them alive - so from there on elements are cloned,
+// leaving the other list untouched
+pub struct IntoIter<T>(List<T>);
+
+// Like IntoIter, but stops at the first shared node rather than cloning, so
+// it works for any T
+pub struct IntoIterUnique<T>(List<T>);
+
+// Each suffix of a list, starting with the whole list - every one of them
+// shares its nodes with the list it came from
+pub struct Tails<T> {
+    next: Link<T>,
+}
+
 type Link<T> = Option<Rc<Node<T>>>;
 
-#[derive(Debug)]
 struct Node<T> {
     elem: T,
     next: Link<T>,
@@ -84,6 +113,134 @@ impl<T> List<T> {
             // return a reference to the value inside the node
             .map(|node| &node.elem)
     }
+
+    pub fn into_iter_unique(self) -> IntoIterUnique<T> {
+        IntoIterUnique(self)
+    }
+
+    // [1, 2, 3] gives [1, 2, 3], [2, 3] and [3]. Each is an O(1) clone of a
+    // node's Rc rather than a copy
+    pub fn iter_tails(&self) -> Tails<T> {
+        Tails {
+            next: self.head.clone(),
+        }
+    }
+
+    // Take the head node, moving its element out if we're the only list
+    // pointing to it. Otherwise hand it back, leaving the list as it was
+    fn pop_unique(&mut self) -> Option<Result<T, Rc<Node<T>>>> {
+        let node = self.head.take()?;
+
+        Some(match Rc::try_unwrap(node) {
+            Ok(mut node) => {
+                self.head = node.next.take();
+
+                Ok(node.elem)
+            }
+            Err(node) => {
+                self.head = Some(Rc::clone(&node));
+
+                Err(node)
+            }
+        })
+    }
+
+    // The node graph in Graphviz's DOT format - see dot.rs
+    pub fn to_dot(&self) -> String
+    where
+        T: fmt::Debug,
+    {
+        Self::to_dot_many(core::slice::from_ref(self))
+    }
+
+    // One graph for several lists, e.g. different versions built from the
+    // same list. A node shared by several lists is drawn once, with an edge
+    // from each, so the graph shows exactly how much structure is shared.
+    // The lists are named list0, list1, ... in the order they're given
+    pub fn to_dot_many(lists: &[Self]) -> String
+    where
+        T: fmt::Debug,
+    {
+        let mut graph = dot::Graph::new();
+
+        for (i, list) in lists.iter().enumerate() {
+            let root = format!("list{i}");
+            let mut from = None;
+            let mut link = list.head.as_ref();
+
+            graph.root(&root, &format!("third::List #{i}"));
+
+            while let Some(node) = link {
+                let (id, new) = graph.id(Rc::as_ptr(node));
+
+                if new {
+                    graph.node(
+                        id,
+                        &format!(
+                            "{:?}\nstrong: {}, weak: {}",
+                            node.elem,
+                            Rc::strong_count(node),
+                            Rc::weak_count(node)
+                        ),
+                    );
+                }
+
+                match from {
+                    Some(from) => graph.edge(from, id, "next"),
+                    None => graph.edge(&root, id, "head"),
+                }
+
+                // the rest of the list has already been drawn, from an
+                // earlier list that shares it
+                if !new {
+                    break;
+                }
+
+                from = Some(id);
+                link = node.next.as_ref();
+            }
+        }
+
+        graph.finish()
+    }
+
+    // Free the nodes that only this list points to, one at a time, stopping
+    // at the first node that's shared with another list
+    fn drop_unique_nodes(&mut self) {
+        // .take the head so that it is consumed and dropped, for as long as
+        // there is one...
+        while let Some(node_ref) = self.head.take() {
+            // attempt to unwrap the Rc
+            //  - if we get Ok, then:
+            //      - we know there's only 1 reference to the internal value
+            //      - we can take ownership of that value
+            //      - we decrement the reference count to 0
+            //      - Rust then drops node_ref. We can see this by attempting to
+            //          view the Rc::strong_count inside the block:
+            //          - Rust indicates that node_ref has been moved to
+            //              Rc::try_unwrap
+            //          - Rust complains inside the block that we're attempting to
+            //              borrow a moved value
+            //              i.e. the value has been moved into Rc::try_unwrap, and
+            //              then inside the method has been dropped
+            //  - if we don't, then :
+            //      - there's some other list pointing to the node
+            //      - we need to stop dropping values
+            if let Ok(mut node) = Rc::try_unwrap(node_ref) {
+                // we know we're working with a node that has only a single
+                // reference to it - it's safe to drop this node, so we:
+                //  - .take the node's next value and make it the new head,
+                //      so the list is still whole if dropping this node's
+                //      element panics
+                //  - let the node get dropped at the end of this scope
+                self.head = node.next.take();
+            } else {
+                // stop processing the list if there are other references to the
+                // current node!
+                break;
+            }
+        }
+    }
 }
 
 // we implement Iterator for Iter.
@@ -117,53 +274,347 @@ impl<'a, T> Iterator for Iter<'a, T> {
     }
 }
 
+impl<T: Clone> IntoIterator for List<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self)
+    }
+}
+
+impl<T: Clone> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.0.pop_unique()? {
+            Ok(elem) => Some(elem),
+            Err(node) => {
+                // shared - step past it, and let the other list keep it
+                self.0.head = node.next.clone();
+
+                Some(node.elem.clone())
+            }
+        }
+    }
+}
+
+impl<T> Iterator for IntoIterUnique<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.pop_unique()?.ok()
+    }
+}
+
+impl<T> Iterator for Tails<T> {
+    type Item = List<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.next.take()?;
+
+        self.next = node.next.clone();
+
+        Some(List { head: Some(node) })
+    }
+}
+
+// Cloning is O(1) - the clone shares every node with the original
+impl<T> Clone for List<T> {
+    fn clone(&self) -> Self {
+        Self {
+            head: self.head.clone(),
+        }
+    }
+}
+
 impl<T> Default for List<T> {
     fn default() -> Self {
         Self::new()
     }
 }
 
+// The trait's prepend and tail borrow the list rather than taking it, so
+// they start from a clone - which only costs an Rc increment
+//
+// The trait isn't imported here: its prepend takes &self, which method
+// lookup tries before our own &mut self one, so every list.prepend(x) in
+// this module would quietly start going through the trait
+impl<T> traits::PersistentStack<T> for List<T> {
+    fn prepend(&self, elem: T) -> Self {
+        List::prepend(&mut self.clone(), elem)
+    }
+
+    fn tail(&self) -> Self {
+        List::tail(self.clone())
+    }
+
+    fn head(&self) -> Option<&T> {
+        List::head(self)
+    }
+}
+
 impl<T> Drop for List<T> {
     fn drop(&mut self) {
-        // .take the head so that it is consumed and dropped
-        let mut current_node = self.head.take();
+        // If an element's drop panics, the guard carries on freeing the rest
+        // of our nodes while the panic unwinds - see second::List's Drop
+        struct DropGuard<'a, T>(&'a mut List<T>);
 
-        // while the current node is not None...
-        while let Some(node_ref) = current_node {
-            // attempt to unwrap the Rc
-            //  - if we get Ok, then:
-            //      - we know there's only 1 reference to the internal value
-            //      - we can take ownership of that value
-            //      - we decrement the reference count to 0
-            //      - Rust then drops node_ref. We can see this by attempting to
-            //          view the Rc::strong_count inside the block:
-            //          - Rust indicates that node_ref has been moved to
-            //              Rc::try_unwrap
-            //          - Rust complains inside the block that we're attempting to
-            //              borrow a moved value
-            //              i.e. the value has been moved into Rc::try_unwrap, and
-            //              then inside the method has been dropped
-            //  - if we don't, then :
-            //      - there's some other list pointing to the node
-            //      - we need to stop dropping values
-            if let Ok(mut node) = Rc::try_unwrap(node_ref) {
-                // we know we're working with a node that has only a single
-                // reference to it - it's safe to drop this node, so we:
-                //  - .take the node's next value
-                //  - let the node get dropped at the end of this scope
-                current_node = node.next.take();
-            } else {
-                // stop processing the list if there are other references to the
-                // current node!
-                break;
+        impl<T> Drop for DropGuard<'_, T> {
+            fn drop(&mut self) {
+                self.0.drop_unique_nodes();
             }
         }
+
+        let guard = DropGuard(self);
+
+        guard.0.drop_unique_nodes();
+        mem::forget(guard);
+    }
+}
+
+// The elements head first, like a Vec's Debug
+//
+// Node used to derive Debug, which prints a node by printing its next,
+// which prints its next... one level of recursion per element, enough to
+// overflow the stack on a long list. .iter walks the list in a loop
+// instead
+impl<T: fmt::Debug> fmt::Debug for List<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+// Written head first, the order .iter visits them in
+impl<T: fmt::Display> fmt::Display for List<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        text::write_list(f, self.iter())
+    }
+}
+
+impl<T: FromStr> FromStr for List<T> {
+    type Err = ParseListError<T::Err>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // prepend from the last element back, so the first one written
+        // ends up as the head
+        let list = text::parse_list(s)?
+            .into_iter()
+            .rev()
+            .fold(List::new(), |mut list, elem| list.prepend(elem));
+
+        Ok(list)
+    }
+}
+
+// The shared-tail encoding used by snapshot, and by SharedTails with the
+// `serde` feature.
+//
+// Writing lists out one element at a time loses the sharing between them:
+// a thousand lists that share one long tail would write that tail a
+// thousand times, and read back into a thousand copies of it. Instead we
+// flatten a group of lists into every distinct node once, each with the
+// index of its next node, plus the index of each list's head. A node's
+// next always comes before it, so the nodes can be rebuilt in order:
+//
+//      a = [10, 2, 1], b = [20, 2, 1]
+//
+//      nodes: [(1, None), (2, Some(0)), (10, Some(1)), (20, Some(1))]
+//      heads: [Some(2), Some(3)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+struct Flattened<'a, T> {
+    nodes: Vec<(&'a T, Option<usize>)>,
+    heads: Vec<Option<usize>>,
+}
+
+fn flatten<T>(lists: &[List<T>]) -> Flattened<'_, T> {
+    // nodes are identified by address - two lists share a node if they
+    // reach the same Rc allocation. A BTreeMap rather than a HashMap, as
+    // HashMap needs std
+    let mut ids: BTreeMap<*const Node<T>, usize> = BTreeMap::new();
+    let mut nodes = Vec::new();
+
+    let heads = lists
+        .iter()
+        .map(|list| {
+            // walk the list until we reach a node that's already been
+            // written, since everything after it has been too
+            let mut chain = Vec::new();
+            let mut link = list.head.as_deref();
+
+            while let Some(node) = link.filter(|&node| !ids.contains_key(&(node as *const _))) {
+                chain.push(node);
+                link = node.next.as_deref();
+            }
+
+            // write the new nodes back to front, so each one's next already
+            // has an id
+            for node in chain.into_iter().rev() {
+                let next = node.next.as_deref().map(|next| ids[&(next as *const _)]);
+
+                ids.insert(node as *const _, nodes.len());
+                nodes.push((&node.elem, next));
+            }
+
+            list.head.as_deref().map(|head| ids[&(head as *const _)])
+        })
+        .collect();
+
+    Flattened { nodes, heads }
+}
+
+// The other way round: push nodes in their flattened order, then ask for
+// lists by the index of their head
+struct Rebuild<T> {
+    nodes: Vec<Rc<Node<T>>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RebuildError {
+    // a node's next doesn't come before it - which also rules out cycles
+    ForwardLink { node: usize, next: usize },
+    // a head that isn't one of the nodes
+    MissingHead { head: usize },
+}
+
+impl<T> Rebuild<T> {
+    fn with_capacity(capacity: usize) -> Self {
+        Self {
+            nodes: Vec::with_capacity(capacity),
+        }
+    }
+
+    fn push(&mut self, elem: T, next: Option<usize>) -> Result<(), RebuildError> {
+        let node = self.nodes.len();
+
+        let next = match next {
+            Some(next) if next < node => Some(Rc::clone(&self.nodes[next])),
+            Some(next) => return Err(RebuildError::ForwardLink { node, next }),
+            None => None,
+        };
+
+        self.nodes.push(Rc::new(Node { elem, next }));
+
+        Ok(())
+    }
+
+    fn list(&self, head: Option<usize>) -> Result<List<T>, RebuildError> {
+        match head {
+            Some(head) => self
+                .nodes
+                .get(head)
+                .map(|node| List {
+                    head: Some(Rc::clone(node)),
+                })
+                .ok_or(RebuildError::MissingHead { head }),
+            None => Ok(List::new()),
+        }
+    }
+}
+
+// Drop our references from the last node back. Each node's next is still
+// held by `nodes` when the node goes, so nothing is freed recursively -
+// not even long chains that no list reached
+impl<T> Drop for Rebuild<T> {
+    fn drop(&mut self) {
+        while self.nodes.pop().is_some() {}
+    }
+}
+
+impl fmt::Display for RebuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RebuildError::ForwardLink { node, next } => {
+                write!(
+                    f,
+                    "node {node} links to node {next}, which doesn't come before it"
+                )
+            }
+            RebuildError::MissingHead { head } => write!(f, "head {head} is not a node"),
+        }
+    }
+}
+
+// With the `serde` feature, a list serializes as a sequence, head first -
+// the same order as Display. That loses any sharing between lists - to keep
+// it, serialize them together as SharedTails instead
+#[cfg(feature = "serde")]
+pub use serde_impls::SharedTails;
+
+#[cfg(feature = "serde")]
+mod serde_impls {
+    use alloc::vec::Vec;
+    use serde::de::Error;
+    use serde::ser::SerializeSeq;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::{flatten, List, Rebuild};
+
+    impl<T: Serialize> Serialize for List<T> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut seq = serializer.serialize_seq(Some(self.iter().count()))?;
+
+            self.iter()
+                .try_for_each(|elem| seq.serialize_element(elem))?;
+
+            seq.end()
+        }
+    }
+
+    impl<'de, T: Deserialize<'de>> Deserialize<'de> for List<T> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            // prepend from the last element back, so the first one ends up
+            // as the head
+            let list = Vec::<T>::deserialize(deserializer)?
+                .into_iter()
+                .rev()
+                .fold(List::new(), |mut list, elem| list.prepend(elem));
+
+            Ok(list)
+        }
+    }
+
+    // A group of lists that serializes each shared node only once, and
+    // deserializes back into lists that share those nodes in the same way
+    pub struct SharedTails<T>(pub Vec<List<T>>);
+
+    #[derive(Deserialize)]
+    struct Flattened<T> {
+        nodes: Vec<(T, Option<usize>)>,
+        heads: Vec<Option<usize>>,
+    }
+
+    impl<T: Serialize> Serialize for SharedTails<T> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            flatten(&self.0).serialize(serializer)
+        }
+    }
+
+    impl<'de, T: Deserialize<'de>> Deserialize<'de> for SharedTails<T> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let Flattened { nodes, heads } = Flattened::deserialize(deserializer)?;
+            let mut rebuild = Rebuild::with_capacity(nodes.len());
+
+            for (elem, next) in nodes {
+                rebuild.push(elem, next).map_err(D::Error::custom)?;
+            }
+
+            heads
+                .into_iter()
+                .map(|head| rebuild.list(head).map_err(D::Error::custom))
+                .collect::<Result<_, _>>()
+                .map(SharedTails)
+        }
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::List;
+    use crate::test_utils::{panics, DropCounter};
+    use std::rc::Rc;
+
+    crate::conformance_tests!(PersistentStack, persistent_stack_conformance, List::new());
 
     #[test]
     fn basics() {
@@ -199,4 +650,227 @@ mod test {
         assert_eq!(iter.next(), Some(&1));
         assert_eq!(iter.next(), None);
     }
+
+    #[test]
+    fn display_and_parse() {
+        let mut list = List::new();
+
+        assert_eq!(list.to_string(), "[]");
+
+        let list = list.prepend(1).prepend(2).prepend(3);
+
+        // head first
+        assert_eq!(list.to_string(), "[3, 2, 1]");
+
+        let parsed: List<i32> = list.to_string().parse().unwrap();
+
+        assert_eq!(parsed.to_string(), "[3, 2, 1]");
+        assert_eq!(parsed.head(), Some(&3));
+
+        let err = "[3, 2,, 1]".parse::<List<i32>>().err();
+
+        assert_eq!(err.map(|err| err.position()), Some(6));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let mut list = List::new();
+        let list = list.prepend(1).prepend(2).prepend(3);
+
+        let json = serde_json::to_string(&list).unwrap();
+
+        assert_eq!(json, "[3,2,1]");
+
+        let list: List<i32> = serde_json::from_str(&json).unwrap();
+        let bytes = bincode::serialize(&list).unwrap();
+        let list: List<i32> = bincode::deserialize(&bytes).unwrap();
+
+        assert_eq!(list.iter().collect::<Vec<_>>(), [&3, &2, &1]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_shared_tails() {
+        use super::SharedTails;
+
+        let base = (0..1000).fold(List::new(), |mut list, x| list.prepend(x));
+        let a = base.clone().prepend(-1);
+        let b = base.clone().prepend(-2);
+
+        let lists = SharedTails(vec![a, b, base, List::new()]);
+        let json = serde_json::to_string(&lists).unwrap();
+        let SharedTails(lists): SharedTails<i32> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(lists.len(), 4);
+        assert_eq!(lists[0].head(), Some(&-1));
+        assert_eq!(lists[1].head(), Some(&-2));
+        assert_eq!(lists[2].iter().count(), 1000);
+        assert_eq!(lists[3].head(), None);
+
+        // the tails are shared again, not copied
+        let tail = |list: &List<i32>| Rc::clone(list.head.as_ref().unwrap().next.as_ref().unwrap());
+
+        assert!(Rc::ptr_eq(&tail(&lists[0]), &tail(&lists[1])));
+        assert!(Rc::ptr_eq(
+            &tail(&lists[0]),
+            lists[2].head.as_ref().unwrap()
+        ));
+
+        // and bincode agrees
+        let bytes = bincode::serialize(&SharedTails(lists)).unwrap();
+        let SharedTails(lists): SharedTails<i32> = bincode::deserialize(&bytes).unwrap();
+
+        assert!(Rc::ptr_eq(&tail(&lists[0]), &tail(&lists[1])));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_shared_tails_rejects_forward_links() {
+        use super::SharedTails;
+
+        let json = r#"{"nodes":[[1,1],[2,null]],"heads":[0]}"#;
+
+        assert!(serde_json::from_str::<SharedTails<i32>>(json).is_err());
+
+        let json = r#"{"nodes":[[1,null]],"heads":[1]}"#;
+
+        assert!(serde_json::from_str::<SharedTails<i32>>(json).is_err());
+    }
+
+    #[test]
+    fn drop_carries_on_after_a_panic() {
+        let counter = DropCounter::new();
+        let mut shared = List::new().prepend(counter.tracked());
+        let list = shared
+            .prepend(counter.tracked())
+            .prepend(counter.panicking())
+            .prepend(counter.tracked());
+
+        assert!(panics(|| drop(list)));
+
+        // the nodes only `list` pointed to are all freed, and the one it
+        // shares with `shared` is left alone
+        assert_eq!(counter.dropped(), 3);
+        assert_eq!(counter.drops_of(0), 0);
+
+        drop(shared);
+
+        counter.assert_all_dropped_once();
+    }
+
+    #[test]
+    fn debug_prints_elements_head_first() {
+        let mut list = List::new();
+
+        assert_eq!(format!("{list:?}"), "[]");
+
+        list = list.prepend(3).prepend(2).prepend(1);
+
+        // shares 2 and 3 with list
+        let other = list.clone().tail().prepend(0);
+
+        assert_eq!(format!("{list:?}"), "[1, 2, 3]");
+        assert_eq!(format!("{other:?}"), "[0, 2, 3]");
+        assert_eq!(format!("{list:#?}"), "[\n    1,\n    2,\n    3,\n]");
+    }
+
+    // Node's derived Debug recursed once per element
+    #[test]
+    #[cfg_attr(miri, ignore = "a million nodes takes too long under Miri")]
+    fn debug_long_list() {
+        let mut list = List::new();
+
+        for x in (0..1_000_000).rev() {
+            list = list.prepend(x);
+        }
+
+        let debug = format!("{list:?}");
+
+        assert!(debug.starts_with("[0, 1, 2, "));
+        assert!(debug.ends_with(", 999998, 999999]"));
+    }
+
+    #[test]
+    fn into_iter_moves_unique_elements() {
+        let counter = DropCounter::new();
+        let mut list = List::new();
+
+        for _ in 0..3 {
+            list = list.prepend(counter.tracked());
+        }
+
+        // Tracked isn't Clone, so this only works because every node is
+        // unique
+        let ids: Vec<usize> = list.into_iter_unique().map(|x| x.id()).collect();
+
+        assert_eq!(ids, [2, 1, 0]);
+        counter.assert_all_dropped_once();
+    }
+
+    #[test]
+    fn into_iter_clones_shared_elements() {
+        let mut shared = List::new().prepend(3).prepend(2);
+        let list = shared.prepend(1);
+
+        assert_eq!(list.into_iter().collect::<Vec<_>>(), [1, 2, 3]);
+
+        // still whole
+        assert_eq!(shared.iter().collect::<Vec<_>>(), [&2, &3]);
+        assert_eq!(Rc::strong_count(shared.head.as_ref().unwrap()), 1);
+    }
+
+    #[test]
+    fn into_iter_unique_stops_at_shared_nodes() {
+        let counter = DropCounter::new();
+        let mut shared = List::new();
+
+        shared = shared.prepend(counter.tracked()).prepend(counter.tracked());
+
+        let list = shared.prepend(counter.tracked()).prepend(counter.tracked());
+        let ids: Vec<usize> = list.into_iter_unique().map(|x| x.id()).collect();
+
+        assert_eq!(ids, [3, 2]);
+        assert_eq!(counter.dropped(), 2);
+        assert_eq!(shared.head().map(|x| x.id()), Some(1));
+
+        drop(shared);
+
+        counter.assert_all_dropped_once();
+    }
+
+    #[test]
+    fn into_iter_long() {
+        let mut list = List::new();
+
+        for x in 0..100_000 {
+            list = list.prepend(x);
+        }
+
+        let shared = list.clone().tail().tail();
+
+        assert_eq!(list.into_iter().count(), 100_000);
+        assert_eq!(shared.into_iter().count(), 99_998);
+    }
+
+    #[test]
+    fn iter_tails() {
+        let list = List::new().prepend(3).prepend(2).prepend(1);
+
+        let tails: Vec<Vec<i32>> = list
+            .iter_tails()
+            .map(|tail| tail.iter().copied().collect())
+            .collect();
+
+        assert_eq!(tails, [vec![1, 2, 3], vec![2, 3], vec![3]]);
+        assert_eq!(List::<i32>::new().iter_tails().count(), 0);
+
+        // each tail is made of the list's own nodes
+        let second = list.iter_tails().nth(1).unwrap();
+
+        assert!(Rc::ptr_eq(
+            second.head.as_ref().unwrap(),
+            list.head.as_ref().unwrap().next.as_ref().unwrap()
+        ));
+    }
 }
@@ -0,0 +1,639 @@
+// A compact binary format for saving and restoring groups of third::Lists
+//
+// Versions of a persistent list share most of their nodes, so writing each
+// version out in full would repeat those nodes over and over. A snapshot
+// writes each distinct node once - identified by its Rc allocation - and
+// records each list as the index of its head node, so decoding gives back
+// lists that share exactly the nodes the originals did.
+//
+// The layout, with every count and index as an LEB128 varint:
+//
+//      magic       b"TLST"
+//      version     1 byte, currently 1
+//      node count
+//      nodes       for each node: next + 1 (0 for none), then the element
+//      head count
+//      heads       for each list: head + 1 (0 for an empty list)
+//
+// Nodes come in the order third::flatten produces - a node's next always
+// comes before it - so decoding builds them front to back and never needs
+// to patch a link afterwards.
+//
+// Elements are written with the Encode trait and read with Decode, which
+// are implemented for the primitive types, String, Option and Vec. There
+// are no dependencies, so there's no serde here - see SharedTails for that
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt;
+
+use super::{flatten, List, Rebuild, RebuildError};
+
+const MAGIC: &[u8; 4] = b"TLST";
+const VERSION: u8 = 1;
+
+// Write `lists` as a snapshot
+pub fn encode<T: Encode>(lists: &[List<T>]) -> Vec<u8> {
+    let flattened = flatten(lists);
+    let mut writer = Writer::new();
+
+    writer.bytes(MAGIC);
+    writer.u8(VERSION);
+
+    writer.varint(flattened.nodes.len() as u64);
+
+    for (elem, next) in flattened.nodes {
+        writer.varint(next.map_or(0, |next| next as u64 + 1));
+        elem.encode(&mut writer);
+    }
+
+    writer.varint(flattened.heads.len() as u64);
+
+    for head in flattened.heads {
+        writer.varint(head.map_or(0, |head| head as u64 + 1));
+    }
+
+    writer.into_bytes()
+}
+
+// Read the lists back out of a snapshot, in the order they were written
+pub fn decode<T: Decode>(bytes: &[u8]) -> Result<Vec<List<T>>, SnapshotError> {
+    let mut reader = Reader::new(bytes);
+
+    if reader.bytes(MAGIC.len()).ok() != Some(MAGIC.as_slice()) {
+        return Err(SnapshotError::BadMagic);
+    }
+
+    let version = reader.u8()?;
+
+    if version != VERSION {
+        return Err(SnapshotError::UnsupportedVersion(version));
+    }
+
+    // every node takes at least two bytes, so a count larger than that
+    // is corrupt - and we mustn't reserve space for it
+    let node_count = reader.length()?;
+    let mut rebuild = Rebuild::with_capacity(node_count.min(reader.remaining() / 2));
+
+    for _ in 0..node_count {
+        let offset = reader.offset();
+        let next = reader.index()?;
+        let elem = T::decode(&mut reader)?;
+
+        rebuild
+            .push(elem, next)
+            .map_err(|err| SnapshotError::from_rebuild(offset, err))?;
+    }
+
+    let head_count = reader.length()?;
+    let mut lists = Vec::with_capacity(head_count.min(reader.remaining()));
+
+    for _ in 0..head_count {
+        let offset = reader.offset();
+        let head = reader.index()?;
+
+        lists.push(
+            rebuild
+                .list(head)
+                .map_err(|err| SnapshotError::from_rebuild(offset, err))?,
+        );
+    }
+
+    if reader.remaining() > 0 {
+        return Err(SnapshotError::TrailingBytes {
+            offset: reader.offset(),
+        });
+    }
+
+    Ok(lists)
+}
+
+// What went wrong while decoding. Offsets are in bytes from the start of
+// the snapshot
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnapshotError {
+    // the input doesn't start with b"TLST"
+    BadMagic,
+    // written by a version of the format we don't understand
+    UnsupportedVersion(u8),
+    // the input ended in the middle of something
+    Truncated {
+        offset: usize,
+    },
+    // a node whose next doesn't come before it
+    InvalidLink {
+        offset: usize,
+        node: usize,
+        next: usize,
+    },
+    // a list whose head isn't one of the nodes
+    InvalidHead {
+        offset: usize,
+        head: usize,
+    },
+    // bytes that can't be what they're meant to be, e.g. a bool that
+    // isn't 0 or 1, or a String that isn't UTF-8
+    InvalidValue {
+        offset: usize,
+        reason: &'static str,
+    },
+    // more input after the last list
+    TrailingBytes {
+        offset: usize,
+    },
+}
+
+impl SnapshotError {
+    fn from_rebuild(offset: usize, err: RebuildError) -> Self {
+        match err {
+            RebuildError::ForwardLink { node, next } => {
+                SnapshotError::InvalidLink { offset, node, next }
+            }
+            RebuildError::MissingHead { head } => SnapshotError::InvalidHead { offset, head },
+        }
+    }
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::BadMagic => f.write_str("not a list snapshot"),
+            SnapshotError::UnsupportedVersion(version) => {
+                write!(f, "unsupported snapshot version {version}")
+            }
+            SnapshotError::Truncated { offset } => {
+                write!(f, "snapshot ends unexpectedly at byte {offset}")
+            }
+            SnapshotError::InvalidLink { offset, node, next } => write!(
+                f,
+                "node {node} at byte {offset} links to node {next}, which doesn't come before it"
+            ),
+            SnapshotError::InvalidHead { offset, head } => {
+                write!(f, "head {head} at byte {offset} is not a node")
+            }
+            SnapshotError::InvalidValue { offset, reason } => {
+                write!(f, "invalid value at byte {offset}: {reason}")
+            }
+            SnapshotError::TrailingBytes { offset } => {
+                write!(f, "unexpected bytes after the snapshot at byte {offset}")
+            }
+        }
+    }
+}
+
+impl Error for SnapshotError {}
+
+// Appends the building blocks of a snapshot to a byte buffer
+pub struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    fn new() -> Self {
+        Self { bytes: Vec::new() }
+    }
+
+    fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    pub fn u8(&mut self, byte: u8) {
+        self.bytes.push(byte);
+    }
+
+    pub fn bytes(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    // LEB128: 7 bits at a time, low bits first, with the top bit set on
+    // every byte but the last. Small numbers take one byte
+    pub fn varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.u8(value as u8 | 0x80);
+            value >>= 7;
+        }
+
+        self.u8(value as u8);
+    }
+}
+
+// Reads the building blocks of a snapshot back out, keeping track of where
+// it is for error messages
+pub struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, offset: 0 }
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.bytes.len() - self.offset
+    }
+
+    pub fn u8(&mut self) -> Result<u8, SnapshotError> {
+        self.bytes(1).map(|bytes| bytes[0])
+    }
+
+    pub fn bytes(&mut self, len: usize) -> Result<&'a [u8], SnapshotError> {
+        if len > self.remaining() {
+            return Err(SnapshotError::Truncated {
+                offset: self.bytes.len(),
+            });
+        }
+
+        let bytes = &self.bytes[self.offset..self.offset + len];
+
+        self.offset += len;
+
+        Ok(bytes)
+    }
+
+    pub fn varint(&mut self) -> Result<u64, SnapshotError> {
+        let start = self.offset;
+        let mut value = 0u64;
+
+        for shift in (0..64).step_by(7) {
+            let byte = self.u8()?;
+            let bits = u64::from(byte & 0x7F);
+
+            // the tenth byte only has room for the top bit of a u64
+            if shift == 63 && bits > 1 {
+                break;
+            }
+
+            value |= bits << shift;
+
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+
+        Err(SnapshotError::InvalidValue {
+            offset: start,
+            reason: "varint is too long",
+        })
+    }
+
+    // A varint that has to fit in a usize
+    pub fn length(&mut self) -> Result<usize, SnapshotError> {
+        let offset = self.offset;
+
+        usize::try_from(self.varint()?).map_err(|_| SnapshotError::InvalidValue {
+            offset,
+            reason: "length does not fit in a usize",
+        })
+    }
+
+    // An optional index, written as index + 1 with 0 for None
+    fn index(&mut self) -> Result<Option<usize>, SnapshotError> {
+        Ok(self.length()?.checked_sub(1))
+    }
+
+    pub fn invalid(&self, offset: usize, reason: &'static str) -> SnapshotError {
+        SnapshotError::InvalidValue { offset, reason }
+    }
+}
+
+// How an element is written into a snapshot
+pub trait Encode {
+    fn encode(&self, writer: &mut Writer);
+}
+
+// How an element is read back out of one
+pub trait Decode: Sized {
+    fn decode(reader: &mut Reader<'_>) -> Result<Self, SnapshotError>;
+}
+
+// Unsigned integers are varints, so small values stay small
+macro_rules! unsigned {
+    ($($ty:ty),*) => {$(
+        impl Encode for $ty {
+            fn encode(&self, writer: &mut Writer) {
+                writer.varint(*self as u64);
+            }
+        }
+
+        impl Decode for $ty {
+            fn decode(reader: &mut Reader<'_>) -> Result<Self, SnapshotError> {
+                let offset = reader.offset();
+
+                <$ty>::try_from(reader.varint()?)
+                    .map_err(|_| reader.invalid(offset, concat!("out of range for ", stringify!($ty))))
+            }
+        }
+    )*};
+}
+
+// Signed integers are zigzag encoded first - 0, -1, 1, -2, 2... become
+// 0, 1, 2, 3, 4... - so small negative values stay small too
+macro_rules! signed {
+    ($($ty:ty),*) => {$(
+        impl Encode for $ty {
+            fn encode(&self, writer: &mut Writer) {
+                let value = *self as i64;
+
+                writer.varint(((value << 1) ^ (value >> 63)) as u64);
+            }
+        }
+
+        impl Decode for $ty {
+            fn decode(reader: &mut Reader<'_>) -> Result<Self, SnapshotError> {
+                let offset = reader.offset();
+                let zigzag = reader.varint()?;
+                let value = (zigzag >> 1) as i64 ^ -((zigzag & 1) as i64);
+
+                <$ty>::try_from(value)
+                    .map_err(|_| reader.invalid(offset, concat!("out of range for ", stringify!($ty))))
+            }
+        }
+    )*};
+}
+
+unsigned!(u8, u16, u32, u64, usize);
+signed!(i8, i16, i32, i64, isize);
+
+impl Encode for bool {
+    fn encode(&self, writer: &mut Writer) {
+        writer.u8(*self as u8);
+    }
+}
+
+impl Decode for bool {
+    fn decode(reader: &mut Reader<'_>) -> Result<Self, SnapshotError> {
+        let offset = reader.offset();
+
+        match reader.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(reader.invalid(offset, "bool is not 0 or 1")),
+        }
+    }
+}
+
+impl Encode for char {
+    fn encode(&self, writer: &mut Writer) {
+        (*self as u32).encode(writer);
+    }
+}
+
+impl Decode for char {
+    fn decode(reader: &mut Reader<'_>) -> Result<Self, SnapshotError> {
+        let offset = reader.offset();
+
+        char::from_u32(u32::decode(reader)?).ok_or(reader.invalid(offset, "not a char"))
+    }
+}
+
+impl Encode for f32 {
+    fn encode(&self, writer: &mut Writer) {
+        writer.bytes(&self.to_le_bytes());
+    }
+}
+
+impl Decode for f32 {
+    fn decode(reader: &mut Reader<'_>) -> Result<Self, SnapshotError> {
+        let bytes = reader.bytes(4)?;
+
+        Ok(f32::from_le_bytes(bytes.try_into().unwrap()))
+    }
+}
+
+impl Encode for f64 {
+    fn encode(&self, writer: &mut Writer) {
+        writer.bytes(&self.to_le_bytes());
+    }
+}
+
+impl Decode for f64 {
+    fn decode(reader: &mut Reader<'_>) -> Result<Self, SnapshotError> {
+        let bytes = reader.bytes(8)?;
+
+        Ok(f64::from_le_bytes(bytes.try_into().unwrap()))
+    }
+}
+
+// Strings are a length followed by UTF-8
+impl Encode for String {
+    fn encode(&self, writer: &mut Writer) {
+        writer.varint(self.len() as u64);
+        writer.bytes(self.as_bytes());
+    }
+}
+
+impl Decode for String {
+    fn decode(reader: &mut Reader<'_>) -> Result<Self, SnapshotError> {
+        let len = reader.length()?;
+        let offset = reader.offset();
+        let bytes = reader.bytes(len)?;
+
+        String::from_utf8(bytes.to_vec()).map_err(|_| reader.invalid(offset, "string is not UTF-8"))
+    }
+}
+
+// Options are a bool followed by the value, if there is one
+impl<T: Encode> Encode for Option<T> {
+    fn encode(&self, writer: &mut Writer) {
+        self.is_some().encode(writer);
+
+        if let Some(value) = self {
+            value.encode(writer);
+        }
+    }
+}
+
+impl<T: Decode> Decode for Option<T> {
+    fn decode(reader: &mut Reader<'_>) -> Result<Self, SnapshotError> {
+        match bool::decode(reader)? {
+            true => T::decode(reader).map(Some),
+            false => Ok(None),
+        }
+    }
+}
+
+// Vecs are a length followed by the elements
+impl<T: Encode> Encode for Vec<T> {
+    fn encode(&self, writer: &mut Writer) {
+        writer.varint(self.len() as u64);
+        self.iter().for_each(|elem| elem.encode(writer));
+    }
+}
+
+impl<T: Decode> Decode for Vec<T> {
+    fn decode(reader: &mut Reader<'_>) -> Result<Self, SnapshotError> {
+        let len = reader.length()?;
+        // as in decode, don't trust the length to size the allocation
+        let mut elems = Vec::with_capacity(len.min(reader.remaining()));
+
+        for _ in 0..len {
+            elems.push(T::decode(reader)?);
+        }
+
+        Ok(elems)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{decode, encode, Decode, Encode, Reader, SnapshotError, Writer};
+    use crate::third::List;
+    use std::fmt::Debug;
+    use std::rc::Rc;
+
+    fn round_trip<T: Encode + Decode + PartialEq + Debug>(value: T) {
+        let mut writer = Writer::new();
+
+        value.encode(&mut writer);
+
+        let bytes = writer.into_bytes();
+        let mut reader = Reader::new(&bytes);
+
+        assert_eq!(T::decode(&mut reader), Ok(value));
+        assert_eq!(reader.remaining(), 0);
+    }
+
+    fn from_vec<T>(elems: Vec<T>) -> List<T> {
+        elems
+            .into_iter()
+            .rev()
+            .fold(List::new(), |mut list, elem| list.prepend(elem))
+    }
+
+    fn to_vec<T: Clone>(list: &List<T>) -> Vec<T> {
+        list.iter().cloned().collect()
+    }
+
+    #[test]
+    fn values() {
+        [0u64, 1, 127, 128, 300, u64::MAX]
+            .into_iter()
+            .for_each(round_trip);
+        [0i64, -1, 1, -64, 64, i64::MIN, i64::MAX]
+            .into_iter()
+            .for_each(round_trip);
+        round_trip(u8::MAX);
+        round_trip(i16::MIN);
+        round_trip(true);
+        round_trip('λ');
+        round_trip(1.5f64);
+        round_trip(String::from("hello"));
+        round_trip(Some(vec![1u32, 2, 3]));
+        round_trip(None::<String>);
+    }
+
+    #[test]
+    fn restores_sharing() {
+        let base = from_vec((0..100).collect::<Vec<i32>>());
+        let a = base.clone().prepend(-1);
+        let b = base.clone().prepend(-2);
+        let lists = [a, b, base, List::new()];
+
+        let bytes = encode(&lists);
+        let decoded: Vec<List<i32>> = decode(&bytes).unwrap();
+
+        assert_eq!(decoded.len(), 4);
+
+        for (original, decoded) in lists.iter().zip(&decoded) {
+            assert_eq!(to_vec(original), to_vec(decoded));
+        }
+
+        let head = |list: &List<i32>| Rc::clone(list.head.as_ref().unwrap());
+        let tail = |list: &List<i32>| Rc::clone(head(list).next.as_ref().unwrap());
+
+        assert!(Rc::ptr_eq(&tail(&decoded[0]), &tail(&decoded[1])));
+        assert!(Rc::ptr_eq(&tail(&decoded[0]), &head(&decoded[2])));
+
+        // each of the 102 nodes is written once: 1 byte for next and 1 or 2
+        // for the element
+        assert!(bytes.len() < 102 * 3 + 20);
+    }
+
+    #[test]
+    fn same_list_twice() {
+        let list = from_vec(vec![String::from("a"), String::from("b")]);
+        let decoded: Vec<List<String>> = decode(&encode(&[list.clone(), list])).unwrap();
+
+        assert!(Rc::ptr_eq(
+            decoded[0].head.as_ref().unwrap(),
+            decoded[1].head.as_ref().unwrap()
+        ));
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        let bytes = encode(&[from_vec(vec![1u32, 2, 3])]);
+
+        assert_eq!(decode::<u32>(b"nope").err(), Some(SnapshotError::BadMagic));
+        assert_eq!(decode::<u32>(b"").err(), Some(SnapshotError::BadMagic));
+
+        let mut future = bytes.clone();
+        future[4] = 2;
+
+        assert_eq!(
+            decode::<u32>(&future).err(),
+            Some(SnapshotError::UnsupportedVersion(2))
+        );
+
+        // every prefix is truncated
+        for len in 5..bytes.len() {
+            assert!(matches!(
+                decode::<u32>(&bytes[..len]).err(),
+                Some(SnapshotError::Truncated { .. })
+            ));
+        }
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+
+        assert_eq!(
+            decode::<u32>(&trailing).err(),
+            Some(SnapshotError::TrailingBytes {
+                offset: bytes.len()
+            })
+        );
+
+        // magic, version, 1 node linking to node 5, element 7, 1 head
+        let forward = [b'T', b'L', b'S', b'T', 1, 1, 6, 7, 1, 1];
+
+        assert_eq!(
+            decode::<u32>(&forward).err(),
+            Some(SnapshotError::InvalidLink {
+                offset: 6,
+                node: 0,
+                next: 5
+            })
+        );
+
+        // the head points at node 3, but there's only 1
+        let head = [b'T', b'L', b'S', b'T', 1, 1, 0, 7, 1, 4];
+
+        assert_eq!(
+            decode::<u32>(&head).err(),
+            Some(SnapshotError::InvalidHead { offset: 9, head: 3 })
+        );
+
+        // a huge node count doesn't allocate, it just runs out of input
+        let huge = [b'T', b'L', b'S', b'T', 1, 0xFF, 0xFF, 0xFF, 0xFF, 0x0F];
+
+        assert!(matches!(
+            decode::<u32>(&huge).err(),
+            Some(SnapshotError::Truncated { .. })
+        ));
+
+        let not_utf8 = [b'T', b'L', b'S', b'T', 1, 1, 0, 1, 0xFF, 1, 1];
+
+        assert_eq!(
+            decode::<String>(&not_utf8).err(),
+            Some(SnapshotError::InvalidValue {
+                offset: 8,
+                reason: "string is not UTF-8"
+            })
+        );
+    }
+}
@@ -0,0 +1,373 @@
+// Common interfaces over the lists
+//
+// Every list grew its own method names - second has push/pop, third has
+// prepend/tail/head, fourth has push_front/pop_back and friends - so code
+// can't be written once for all of them. These traits give them a shared
+// vocabulary:
+//  - Stack - last in, first out
+//  - Queue - first in, first out: push at the back, pop from the front
+//  - Deque - push and pop at both ends. Every Deque is also a Queue
+//  - PersistentStack - a stack that's never changed in place. Each
+//      operation returns a new version, and the old version stays valid
+//
+// Each list implements whichever of them fit, next to its other trait
+// impls:
+//
+//      list            Stack   Queue   Deque   PersistentStack
+//      first           i32
+//      second          yes
+//      third                                   yes
+//      fourth          yes     yes     yes
+//      arena           yes     yes     yes
+//      unrolled        yes     yes     yes
+//      xor             yes     yes     yes
+//      circular                yes
+//      intrusive       yes     yes     yes
+//
+// first only ever held i32s, so it's a Stack<i32>, and intrusive lists
+// hold pinned references to their items, so they're a Stack<Pin<&T>> etc.
+// The lists that are also a Stack push and pop at the front.
+//
+// There's nothing for fifth: fifth.rs is an unfinished attempt that isn't
+// compiled, and fifth_attempt_2's push takes `&'a mut self`, which borrows
+// the list for the rest of its life - there's no way to call it from a
+// trait method that takes a plain `&mut self`.
+//
+// conformance_tests! turns the traits' contracts into tests that any
+// implementation can be run through.
+//
+// The methods have the same names as the lists' own, so for a list with
+// push_front both List::push_front and Deque::push_front exist. Calls
+// like list.push_front(x) pick the list's own method, which does the same
+// thing - the traits only matter once code is generic over them.
+
+pub trait Stack<T> {
+    fn push(&mut self, elem: T);
+
+    fn pop(&mut self) -> Option<T>;
+}
+
+pub trait Queue<T> {
+    fn push_back(&mut self, elem: T);
+
+    fn pop_front(&mut self) -> Option<T>;
+}
+
+pub trait Deque<T>: Queue<T> {
+    fn push_front(&mut self, elem: T);
+
+    fn pop_back(&mut self) -> Option<T>;
+}
+
+pub trait PersistentStack<T>: Sized {
+    // A new version with `elem` on top of this one
+    fn prepend(&self, elem: T) -> Self;
+
+    // A new version without this one's top element. The tail of an empty
+    // stack is empty
+    fn tail(&self) -> Self;
+
+    fn head(&self) -> Option<&T>;
+}
+
+// Generate a module of tests checking that a list meets a trait's
+// contract:
+//
+//      conformance_tests!(Stack, stack_conformance, second::List::new());
+//      conformance_tests!(Deque, deque_conformance, fourth::List::new());
+//
+// The first argument is the trait - Stack, Queue, Deque or
+// PersistentStack - the second names the generated module, and the third
+// is an expression making a new, empty list of i32s. The expression is
+// evaluated inside the generated module, which glob-imports its parent, so
+// it can use anything in scope where the macro is called. Deque also runs
+// the Queue tests, since every Deque is a Queue
+#[macro_export]
+macro_rules! conformance_tests {
+    (Stack, $name:ident, $new:expr) => {
+        mod $name {
+            #[allow(unused_imports)]
+            use super::*;
+            use $crate::traits::Stack;
+
+            fn new() -> impl Stack<i32> {
+                $new
+            }
+
+            #[test]
+            fn pop_empty() {
+                let mut stack = new();
+
+                assert_eq!(stack.pop(), None);
+            }
+
+            #[test]
+            fn last_in_first_out() {
+                let mut stack = new();
+
+                (0..5).for_each(|x| stack.push(x));
+
+                (0..5).rev().for_each(|x| assert_eq!(stack.pop(), Some(x)));
+                assert_eq!(stack.pop(), None);
+            }
+
+            #[test]
+            fn interleaved() {
+                let mut stack = new();
+
+                stack.push(1);
+                stack.push(2);
+                assert_eq!(stack.pop(), Some(2));
+
+                stack.push(3);
+                assert_eq!(stack.pop(), Some(3));
+                assert_eq!(stack.pop(), Some(1));
+                assert_eq!(stack.pop(), None);
+
+                // still usable once it's been emptied
+                stack.push(4);
+                assert_eq!(stack.pop(), Some(4));
+            }
+
+            #[test]
+            fn drop_long() {
+                let mut stack = new();
+
+                (0..100_000).for_each(|x| stack.push(x));
+            }
+        }
+    };
+
+    (Queue, $name:ident, $new:expr) => {
+        mod $name {
+            #[allow(unused_imports)]
+            use super::*;
+            use $crate::traits::Queue;
+
+            fn new() -> impl Queue<i32> {
+                $new
+            }
+
+            $crate::conformance_tests!(@queue);
+        }
+    };
+
+    (Deque, $name:ident, $new:expr) => {
+        mod $name {
+            #[allow(unused_imports)]
+            use super::*;
+            use $crate::traits::{Deque, Queue};
+
+            fn new() -> impl Deque<i32> {
+                $new
+            }
+
+            $crate::conformance_tests!(@queue);
+
+            #[test]
+            fn pop_back_empty() {
+                let mut deque = new();
+
+                assert_eq!(deque.pop_back(), None);
+            }
+
+            #[test]
+            fn push_front_pop_back() {
+                let mut deque = new();
+
+                (0..5).for_each(|x| deque.push_front(x));
+
+                (0..5).for_each(|x| assert_eq!(deque.pop_back(), Some(x)));
+                assert_eq!(deque.pop_back(), None);
+            }
+
+            #[test]
+            fn both_ends() {
+                let mut deque = new();
+
+                deque.push_back(1);
+                deque.push_front(0);
+                deque.push_back(2);
+
+                assert_eq!(deque.pop_front(), Some(0));
+                assert_eq!(deque.pop_back(), Some(2));
+                assert_eq!(deque.pop_back(), Some(1));
+                assert_eq!(deque.pop_front(), None);
+                assert_eq!(deque.pop_back(), None);
+
+                deque.push_front(3);
+                assert_eq!(deque.pop_front(), Some(3));
+            }
+        }
+    };
+
+    (PersistentStack, $name:ident, $new:expr) => {
+        mod $name {
+            #[allow(unused_imports)]
+            use super::*;
+            use $crate::traits::PersistentStack;
+
+            fn new() -> impl PersistentStack<i32> {
+                $new
+            }
+
+            #[test]
+            fn empty() {
+                let stack = new();
+
+                assert_eq!(stack.head(), None);
+                assert_eq!(stack.tail().head(), None);
+            }
+
+            #[test]
+            fn versions_are_independent() {
+                let empty = new();
+                let one = empty.prepend(1);
+                let two = one.prepend(2);
+                let other = one.prepend(3);
+
+                assert_eq!(empty.head(), None);
+                assert_eq!(one.head(), Some(&1));
+                assert_eq!(two.head(), Some(&2));
+                assert_eq!(other.head(), Some(&3));
+
+                assert_eq!(two.tail().head(), Some(&1));
+                assert_eq!(other.tail().head(), Some(&1));
+                assert_eq!(two.tail().tail().head(), None);
+
+                // taking tails didn't touch the versions they came from
+                assert_eq!(two.head(), Some(&2));
+                assert_eq!(one.head(), Some(&1));
+            }
+
+            #[test]
+            fn drop_long() {
+                let mut stack = new();
+
+                for x in 0..100_000 {
+                    stack = stack.prepend(x);
+                }
+            }
+        }
+    };
+
+    // The tests shared by Queue and Deque, expecting a new() in scope
+    (@queue) => {
+        #[test]
+        fn pop_front_empty() {
+            let mut queue = new();
+
+            assert_eq!(queue.pop_front(), None);
+        }
+
+        #[test]
+        fn first_in_first_out() {
+            let mut queue = new();
+
+            (0..5).for_each(|x| queue.push_back(x));
+
+            (0..5).for_each(|x| assert_eq!(queue.pop_front(), Some(x)));
+            assert_eq!(queue.pop_front(), None);
+        }
+
+        #[test]
+        fn interleaved() {
+            let mut queue = new();
+
+            queue.push_back(1);
+            queue.push_back(2);
+            assert_eq!(queue.pop_front(), Some(1));
+
+            queue.push_back(3);
+            assert_eq!(queue.pop_front(), Some(2));
+            assert_eq!(queue.pop_front(), Some(3));
+            assert_eq!(queue.pop_front(), None);
+
+            // still usable once it's been emptied
+            queue.push_back(4);
+            assert_eq!(queue.pop_front(), Some(4));
+        }
+
+        #[test]
+        fn drop_long() {
+            let mut queue = new();
+
+            (0..100_000).for_each(|x| queue.push_back(x));
+        }
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Deque, Queue};
+    use crate::intrusive::{self, Links};
+    use crate::{arena, fourth, second, xor};
+    use std::pin::pin;
+
+    // Generic code that works with any of the lists
+    fn drain<T>(queue: &mut impl Queue<T>) -> Vec<T> {
+        std::iter::from_fn(|| queue.pop_front()).collect()
+    }
+
+    fn reverse<T>(deque: &mut impl Deque<T>) {
+        let mut stack = second::List::new();
+
+        while let Some(elem) = deque.pop_back() {
+            stack.push(elem);
+        }
+
+        while let Some(elem) = stack.pop() {
+            deque.push_front(elem);
+        }
+    }
+
+    fn fill(deque: &mut impl Deque<i32>) {
+        (0..4).for_each(|x| deque.push_back(x));
+    }
+
+    #[test]
+    fn generic_over_lists() {
+        let mut fourth = fourth::List::new();
+        let mut arena = arena::List::new();
+        let mut xor = xor::List::new();
+
+        fill(&mut fourth);
+        fill(&mut arena);
+        fill(&mut xor);
+
+        reverse(&mut fourth);
+        reverse(&mut arena);
+
+        assert_eq!(drain(&mut fourth), [3, 2, 1, 0]);
+        assert_eq!(drain(&mut arena), [3, 2, 1, 0]);
+        assert_eq!(drain(&mut xor), [0, 1, 2, 3]);
+    }
+
+    struct Item {
+        value: i32,
+        links: Links,
+    }
+
+    crate::impl_linked!(Item, links);
+
+    #[test]
+    fn generic_over_intrusive_lists() {
+        let a = pin!(Item {
+            value: 1,
+            links: Links::new(),
+        });
+        let b = pin!(Item {
+            value: 2,
+            links: Links::new(),
+        });
+        let mut list = intrusive::List::new();
+
+        list.push_back(a.as_ref());
+        list.push_back(b.as_ref());
+        reverse(&mut list);
+
+        let values: Vec<i32> = drain(&mut list).iter().map(|item| item.value).collect();
+
+        assert_eq!(values, [2, 1]);
+    }
+}
@@ -0,0 +1,968 @@
+// An unrolled doubly-linked list - each node holds up to N elements
+//
+// A node in second::List<i32> is an i32 and a pointer, i.e. 16 bytes to
+// store 4, and walking the list touches one cache line per element. Here
+// each node holds a small inline array of up to N elements, so the
+// pointers are shared between N elements and most steps of an iteration
+// stay inside the same node.
+//
+// The elements of a node are always kept together at the start of its
+// array, and nodes are kept at least half full where possible:
+//  - inserting into a full node splits it into two half-full nodes
+//  - removing from a node that drops below half full merges it with a
+//      neighbour, if the two fit into one node
+//
+// Nodes are linked with raw pointers, as the two-way links would
+// otherwise need Rc<RefCell<_>> as in fourth::List. Every unsafe block
+// relies on the same invariants:
+//  - every pointer in head, tail, next and prev came from Box::leak,
+//      and is freed exactly once with Box::from_raw when the node is
+//      unlinked
+//  - no node is ever empty while it's linked into the list
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use core::fmt;
+use core::iter::DoubleEndedIterator;
+use core::marker::PhantomData;
+use core::mem::{self, MaybeUninit};
+use core::ptr::{self, NonNull};
+use core::slice;
+use core::str::FromStr;
+
+use crate::dot;
+use crate::text::{self, ParseListError};
+use crate::traits::{Deque, Queue, Stack};
+
+// Up to N elements stored inline, in elems[..len]
+struct Chunk<T, const N: usize> {
+    len: usize,
+    elems: [MaybeUninit<T>; N],
+}
+
+impl<T, const N: usize> Chunk<T, N> {
+    fn new() -> Self {
+        Self {
+            len: 0,
+            elems: [const { MaybeUninit::uninit() }; N],
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    fn as_slice(&self) -> &[T] {
+        // SAFETY: elems[..len] are initialised
+        unsafe { slice::from_raw_parts(self.elems.as_ptr().cast(), self.len) }
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [T] {
+        // SAFETY: elems[..len] are initialised
+        unsafe { slice::from_raw_parts_mut(self.elems.as_mut_ptr().cast(), self.len) }
+    }
+
+    fn ptr_at(&mut self, index: usize) -> *mut T {
+        self.elems.as_mut_ptr().wrapping_add(index).cast()
+    }
+
+    // Insert at `index`, shifting everything after it one to the right
+    fn insert(&mut self, index: usize, elem: T) {
+        assert!(index <= self.len && !self.is_full());
+
+        let len = self.len;
+        let at = self.ptr_at(index);
+
+        // SAFETY: there's room for one more element, so shifting
+        // elems[index..len] right by one stays within the array
+        unsafe {
+            ptr::copy(at, at.add(1), len - index);
+            at.write(elem);
+        }
+
+        self.len += 1;
+    }
+
+    // Remove from `index`, shifting everything after it one to the left
+    fn remove(&mut self, index: usize) -> T {
+        assert!(index < self.len);
+
+        let len = self.len;
+        let at = self.ptr_at(index);
+
+        // SAFETY: elems[index] is initialised and is read out exactly
+        // once, then the elements after it are moved over the gap
+        unsafe {
+            let elem = at.read();
+            ptr::copy(at.add(1), at, len - index - 1);
+            self.len -= 1;
+
+            elem
+        }
+    }
+
+    // Move elems[at..] into a new chunk
+    fn split_off(&mut self, at: usize) -> Self {
+        assert!(at <= self.len);
+
+        let mut other = Self::new();
+        let count = self.len - at;
+
+        // SAFETY: the elements are moved, not copied - self stops
+        // counting them before anyone can see both copies
+        unsafe {
+            ptr::copy_nonoverlapping(self.ptr_at(at), other.ptr_at(0), count);
+        }
+
+        self.len = at;
+        other.len = count;
+
+        other
+    }
+
+    // Move every element of `other` onto the end of self
+    fn append(&mut self, other: &mut Self) {
+        assert!(self.len + other.len <= N);
+
+        let len = self.len;
+
+        // SAFETY: there's room for all of them, and other stops counting
+        // the elements it's given away
+        unsafe {
+            ptr::copy_nonoverlapping(other.ptr_at(0), self.ptr_at(len), other.len);
+        }
+
+        self.len += other.len;
+        other.len = 0;
+    }
+}
+
+impl<T, const N: usize> Drop for Chunk<T, N> {
+    fn drop(&mut self) {
+        // SAFETY: drop the initialised elements, and only those
+        unsafe { ptr::drop_in_place(self.as_mut_slice()) }
+    }
+}
+
+type Link<T, const N: usize> = Option<NonNull<Node<T, N>>>;
+
+struct Node<T, const N: usize> {
+    chunk: Chunk<T, N>,
+    next: Link<T, N>,
+    prev: Link<T, N>,
+}
+
+// A pointer to element `index` of a node, made without creating a
+// reference to the node or its array - IterMut uses this so that it never
+// invalidates the &mut T it has already handed out for the same node
+//
+// SAFETY: node must be live and index < N
+unsafe fn elem_ptr<T, const N: usize>(node: NonNull<Node<T, N>>, index: usize) -> *mut T {
+    // SAFETY: guaranteed by the caller
+    unsafe {
+        ptr::addr_of_mut!((*node.as_ptr()).chunk.elems)
+            .cast::<T>()
+            .add(index)
+    }
+}
+
+pub struct List<T, const N: usize> {
+    head: Link<T, N>,
+    tail: Link<T, N>,
+    len: usize,
+    // we own Boxed nodes, as far as the drop checker is concerned
+    _owns: PhantomData<Box<Node<T, N>>>,
+}
+
+// The raw pointers make List neither Send nor Sync by default, but it owns
+// its nodes the same way a Box would
+unsafe impl<T: Send, const N: usize> Send for List<T, N> {}
+unsafe impl<T: Sync, const N: usize> Sync for List<T, N> {}
+
+pub struct IntoIter<T, const N: usize>(List<T, N>);
+
+pub struct Iter<'a, T, const N: usize> {
+    front: Link<T, N>,
+    front_index: usize,
+    back: Link<T, N>,
+    // one past the last element still to be yielded from the back node
+    back_index: usize,
+    len: usize,
+    _marker: PhantomData<&'a T>,
+}
+
+pub struct IterMut<'a, T, const N: usize> {
+    front: Link<T, N>,
+    front_index: usize,
+    back: Link<T, N>,
+    back_index: usize,
+    len: usize,
+    _marker: PhantomData<&'a mut T>,
+}
+
+impl<T, const N: usize> List<T, N> {
+    pub fn new() -> Self {
+        // splitting a full node in half needs at least 2 elements per node
+        const { assert!(N >= 2, "unrolled::List needs N >= 2") };
+
+        Self {
+            head: None,
+            tail: None,
+            len: 0,
+            _owns: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn push_front(&mut self, elem: T) {
+        // start a new node if there isn't room in the head
+        let head = match self.head {
+            // SAFETY: head is a live node
+            Some(head) if unsafe { !head.as_ref().chunk.is_full() } => head,
+            _ => self.link_after(None),
+        };
+
+        // SAFETY: head is a live node, and nothing else borrows it
+        unsafe { (*head.as_ptr()).chunk.insert(0, elem) };
+        self.len += 1;
+    }
+
+    pub fn push_back(&mut self, elem: T) {
+        let tail = match self.tail {
+            // SAFETY: tail is a live node
+            Some(tail) if unsafe { !tail.as_ref().chunk.is_full() } => tail,
+            _ => self.link_after(self.tail),
+        };
+
+        // SAFETY: tail is a live node, and nothing else borrows it
+        unsafe {
+            let chunk = &mut (*tail.as_ptr()).chunk;
+            chunk.insert(chunk.len, elem);
+        }
+        self.len += 1;
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.head.map(|head| self.remove_at(head, 0))
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        // SAFETY: tail is a live node, and is never empty
+        self.tail
+            .map(|tail| self.remove_at(tail, unsafe { tail.as_ref().chunk.len } - 1))
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.iter().next()
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.iter().next_back()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.find(index)
+            // SAFETY: find only returns live nodes and in-bounds offsets,
+            // and &self keeps the list from changing while we hold the
+            // reference
+            .map(|(node, offset)| unsafe { &(*node.as_ptr()).chunk.as_slice()[offset] })
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.find(index)
+            // SAFETY: as for get, with &mut self making the reference unique
+            .map(|(node, offset)| unsafe { &mut (*node.as_ptr()).chunk.as_mut_slice()[offset] })
+    }
+
+    // Insert at `index`, so that the element ends up at list.get(index)
+    //
+    // Panics if index > len, the same as Vec::insert
+    pub fn insert(&mut self, index: usize, elem: T) {
+        assert!(
+            index <= self.len,
+            "insertion index {index} is out of bounds"
+        );
+
+        if index == self.len {
+            return self.push_back(elem);
+        }
+
+        let (node, mut offset) = self.find(index).expect("index is in bounds");
+
+        // SAFETY: node is live, and the new node created by the split is
+        // linked in straight after it
+        unsafe {
+            let mut target = node;
+
+            if node.as_ref().chunk.is_full() {
+                // split the full node into two half-full ones, and insert
+                // into whichever half the index now falls in
+                let half = N / 2;
+                let new_node = self.link_after(Some(node));
+
+                (*new_node.as_ptr()).chunk = (*node.as_ptr()).chunk.split_off(half);
+
+                if offset > half {
+                    target = new_node;
+                    offset -= half;
+                }
+            }
+
+            (*target.as_ptr()).chunk.insert(offset, elem);
+        }
+
+        self.len += 1;
+    }
+
+    // Remove the element at `index`, or None if it's out of bounds
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        self.find(index)
+            .map(|(node, offset)| self.remove_at(node, offset))
+    }
+
+    pub fn iter(&self) -> Iter<'_, T, N> {
+        Iter {
+            front: self.head,
+            front_index: 0,
+            back: self.tail,
+            // SAFETY: tail is a live node
+            back_index: self
+                .tail
+                .map_or(0, |tail| unsafe { tail.as_ref().chunk.len }),
+            len: self.len,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T, N> {
+        IterMut {
+            front: self.head,
+            front_index: 0,
+            back: self.tail,
+            // SAFETY: tail is a live node
+            back_index: self
+                .tail
+                .map_or(0, |tail| unsafe { tail.as_ref().chunk.len }),
+            len: self.len,
+            _marker: PhantomData,
+        }
+    }
+
+    // The node holding element `index`, and its offset within that node,
+    // walking in from whichever end is closer
+    fn find(&self, index: usize) -> Option<(NonNull<Node<T, N>>, usize)> {
+        if index >= self.len {
+            return None;
+        }
+
+        // SAFETY: every node we step through is live, and since index <
+        // len we never step off either end
+        unsafe {
+            if index < self.len / 2 {
+                let mut node = self.head?;
+                let mut offset = index;
+
+                while offset >= node.as_ref().chunk.len {
+                    offset -= node.as_ref().chunk.len;
+                    node = node.as_ref().next?;
+                }
+
+                Some((node, offset))
+            } else {
+                let mut node = self.tail?;
+                // count how far back from the end we are
+                let mut from_back = self.len - index;
+
+                while from_back > node.as_ref().chunk.len {
+                    from_back -= node.as_ref().chunk.len;
+                    node = node.as_ref().prev?;
+                }
+
+                Some((node, node.as_ref().chunk.len - from_back))
+            }
+        }
+    }
+
+    // Allocate an empty node and link it in after `prev`, or at the front
+    // when prev is None
+    fn link_after(&mut self, prev: Link<T, N>) -> NonNull<Node<T, N>> {
+        let next = match prev {
+            // SAFETY: prev is a live node
+            Some(prev) => unsafe { prev.as_ref().next },
+            None => self.head,
+        };
+
+        let node = NonNull::from(Box::leak(Box::new(Node {
+            chunk: Chunk::new(),
+            next,
+            prev,
+        })));
+
+        // SAFETY: prev and next are live nodes, and node has just been
+        // created
+        unsafe {
+            match prev {
+                Some(prev) => (*prev.as_ptr()).next = Some(node),
+                None => self.head = Some(node),
+            }
+
+            match next {
+                Some(next) => (*next.as_ptr()).prev = Some(node),
+                None => self.tail = Some(node),
+            }
+        }
+
+        node
+    }
+
+    // Unlink a node from its neighbours and free it
+    //
+    // SAFETY: node must be live and linked into this list, and must not be
+    // used again
+    unsafe fn unlink(&mut self, node: NonNull<Node<T, N>>) {
+        // SAFETY: the caller guarantees node is live, and it came from
+        // Box::leak in link_after
+        unsafe {
+            let node = Box::from_raw(node.as_ptr());
+
+            match node.prev {
+                Some(prev) => (*prev.as_ptr()).next = node.next,
+                None => self.head = node.next,
+            }
+
+            match node.next {
+                Some(next) => (*next.as_ptr()).prev = node.prev,
+                None => self.tail = node.prev,
+            }
+        }
+    }
+
+    // Remove the element at `offset` in `node`, then fix up the node so
+    // that it's neither empty nor (if it can be helped) less than half full
+    fn remove_at(&mut self, node: NonNull<Node<T, N>>, offset: usize) -> T {
+        // SAFETY: node is live and offset is in bounds, as checked by the
+        // callers. A node is only unlinked once nothing points to it
+        unsafe {
+            let elem = (*node.as_ptr()).chunk.remove(offset);
+            self.len -= 1;
+
+            let len = node.as_ref().chunk.len;
+
+            if len == 0 {
+                self.unlink(node);
+            } else if len < N / 2 {
+                // merge the next node into this one if they fit together,
+                // otherwise merge this one into the previous node
+                if let Some(next) = node.as_ref().next {
+                    if len + next.as_ref().chunk.len <= N {
+                        (*node.as_ptr()).chunk.append(&mut (*next.as_ptr()).chunk);
+                        self.unlink(next);
+
+                        return elem;
+                    }
+                }
+
+                if let Some(prev) = node.as_ref().prev {
+                    if len + prev.as_ref().chunk.len <= N {
+                        (*prev.as_ptr()).chunk.append(&mut (*node.as_ptr()).chunk);
+                        self.unlink(node);
+                    }
+                }
+            }
+
+            elem
+        }
+    }
+
+    // The node graph in Graphviz's DOT format - see dot.rs. Each node is
+    // labelled with the elements in its chunk, and how full the chunk is
+    pub fn to_dot(&self) -> String
+    where
+        T: fmt::Debug,
+    {
+        let mut graph = dot::Graph::new();
+        let mut from = None;
+        let mut current = self.head;
+
+        graph.root("list", "unrolled::List");
+
+        while let Some(node) = current {
+            let (id, _) = graph.id(node.as_ptr());
+            // SAFETY: every node we step through is live, and &self means
+            // nothing can change it while we look
+            let node = unsafe { node.as_ref() };
+
+            graph.node(
+                id,
+                &format!("{:?}\n{}/{N}", node.chunk.as_slice(), node.chunk.len),
+            );
+
+            match from {
+                Some(from) => graph.edge(from, id, "next"),
+                None => graph.edge("list", id, "head"),
+            }
+
+            if let Some(prev) = node.prev {
+                let (prev_id, _) = graph.id(prev.as_ptr());
+
+                graph.edge(id, prev_id, "prev");
+            }
+
+            from = Some(id);
+            current = node.next;
+        }
+
+        if let Some(tail) = self.tail {
+            let (id, _) = graph.id(tail.as_ptr());
+
+            graph.edge("list", id, "tail");
+        }
+
+        graph.finish()
+    }
+
+    #[cfg(test)]
+    fn node_lens(&self) -> Vec<usize> {
+        let mut lens = Vec::new();
+        let mut current = self.head;
+
+        // SAFETY: every node we step through is live
+        while let Some(node) = current {
+            unsafe {
+                lens.push(node.as_ref().chunk.len);
+                current = node.as_ref().next;
+            }
+        }
+
+        lens
+    }
+
+    // Free the nodes one at a time, so that a long list doesn't recurse -
+    // dropping each node's Chunk drops its elements
+    //
+    // head moves past each node before it's freed, so if an element's drop
+    // panics, Drop's guard can pick up where we left off
+    fn free_nodes(&mut self) {
+        while let Some(node) = self.head {
+            // SAFETY: every node is live, and is freed exactly once here
+            let node = unsafe { Box::from_raw(node.as_ptr()) };
+
+            self.head = node.next;
+        }
+    }
+}
+
+impl<T, const N: usize> Default for List<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Stack<T> for List<T, N> {
+    fn push(&mut self, elem: T) {
+        List::push_front(self, elem);
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        List::pop_front(self)
+    }
+}
+
+impl<T, const N: usize> Queue<T> for List<T, N> {
+    fn push_back(&mut self, elem: T) {
+        List::push_back(self, elem);
+    }
+
+    fn pop_front(&mut self) -> Option<T> {
+        List::pop_front(self)
+    }
+}
+
+impl<T, const N: usize> Deque<T> for List<T, N> {
+    fn push_front(&mut self, elem: T) {
+        List::push_front(self, elem);
+    }
+
+    fn pop_back(&mut self) -> Option<T> {
+        List::pop_back(self)
+    }
+}
+
+impl<T, const N: usize> Drop for List<T, N> {
+    fn drop(&mut self) {
+        // If an element's drop panics, the guard carries on freeing the
+        // rest of the nodes while the panic unwinds, as in second::List's
+        // Drop. The panicking element's own chunk is taken care of by
+        // drop_in_place, which keeps dropping the rest of a slice after one
+        // of its elements panics
+        struct DropGuard<'a, T, const N: usize>(&'a mut List<T, N>);
+
+        impl<T, const N: usize> Drop for DropGuard<'_, T, N> {
+            fn drop(&mut self) {
+                self.0.free_nodes();
+            }
+        }
+
+        let guard = DropGuard(self);
+
+        guard.0.free_nodes();
+        mem::forget(guard);
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for List<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+// Written front to back
+impl<T: fmt::Display, const N: usize> fmt::Display for List<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        text::write_list(f, self.iter())
+    }
+}
+
+impl<T: FromStr, const N: usize> FromStr for List<T, N> {
+    type Err = ParseListError<T::Err>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut list = Self::new();
+
+        text::parse_list(s)?
+            .into_iter()
+            .for_each(|elem| list.push_back(elem));
+
+        Ok(list)
+    }
+}
+
+impl<T, const N: usize> IntoIterator for List<T, N> {
+    type Item = T;
+    type IntoIter = IntoIter<T, N>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self)
+    }
+}
+
+impl<T, const N: usize> Iterator for IntoIter<T, N> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.pop_front()
+    }
+}
+
+impl<T, const N: usize> DoubleEndedIterator for IntoIter<T, N> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.pop_back()
+    }
+}
+
+impl<'a, T, const N: usize> Iterator for Iter<'a, T, N> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+
+        // SAFETY: len > 0, so front is a live node with an element at
+        // front_index, and the list is borrowed for 'a
+        self.front.map(|node| unsafe {
+            let node = &*node.as_ptr();
+            let elem = &node.chunk.as_slice()[self.front_index];
+
+            self.front_index += 1;
+            self.len -= 1;
+
+            if self.front_index == node.chunk.len {
+                self.front = node.next;
+                self.front_index = 0;
+            }
+
+            elem
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T, const N: usize> DoubleEndedIterator for Iter<'_, T, N> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+
+        // SAFETY: as for next, from the other end
+        self.back.map(|node| unsafe {
+            let node = &*node.as_ptr();
+
+            self.back_index -= 1;
+            self.len -= 1;
+
+            let elem = &node.chunk.as_slice()[self.back_index];
+
+            if self.back_index == 0 {
+                self.back = node.prev;
+                self.back_index = node.prev.map_or(0, |prev| prev.as_ref().chunk.len);
+            }
+
+            elem
+        })
+    }
+}
+
+impl<'a, T, const N: usize> Iterator for IterMut<'a, T, N> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+
+        // SAFETY: as for Iter, and len makes sure no element is handed out
+        // twice, so the &mut references never alias. We only go through
+        // raw pointers to the node, as a &mut Node would overlap the
+        // elements we've already handed out
+        self.front.map(|node| unsafe {
+            let next = (*node.as_ptr()).next;
+            let len = (*node.as_ptr()).chunk.len;
+            let elem = &mut *elem_ptr(node, self.front_index);
+
+            self.front_index += 1;
+            self.len -= 1;
+
+            if self.front_index == len {
+                self.front = next;
+                self.front_index = 0;
+            }
+
+            elem
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T, const N: usize> DoubleEndedIterator for IterMut<'_, T, N> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+
+        // SAFETY: as for next
+        self.back.map(|node| unsafe {
+            let prev = (*node.as_ptr()).prev;
+
+            self.back_index -= 1;
+            self.len -= 1;
+
+            let elem = &mut *elem_ptr(node, self.back_index);
+
+            if self.back_index == 0 {
+                self.back = prev;
+                self.back_index = prev.map_or(0, |prev| (*prev.as_ptr()).chunk.len);
+            }
+
+            elem
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::List;
+    use crate::test_utils::{panics, DropCounter};
+    use std::rc::Rc;
+
+    crate::conformance_tests!(Stack, stack_conformance, List::<i32, 4>::new());
+    crate::conformance_tests!(Deque, deque_conformance, List::<i32, 4>::new());
+
+    #[test]
+    fn push_and_pop() {
+        let mut list: List<i32, 4> = List::new();
+
+        assert_eq!(list.pop_front(), None);
+        assert_eq!(list.pop_back(), None);
+
+        (0..10).for_each(|x| list.push_back(x));
+        (10..20).for_each(|x| list.push_front(x));
+
+        assert_eq!(list.len(), 20);
+        assert_eq!(list.front(), Some(&19));
+        assert_eq!(list.back(), Some(&9));
+
+        assert_eq!(list.pop_front(), Some(19));
+        assert_eq!(list.pop_back(), Some(9));
+
+        while list.pop_back().is_some() {}
+
+        assert!(list.is_empty());
+        assert!(list.node_lens().is_empty());
+    }
+
+    #[test]
+    fn get() {
+        let mut list: List<i32, 3> = List::new();
+
+        (0..10).for_each(|x| list.push_back(x));
+
+        for x in 0..10 {
+            assert_eq!(list.get(x as usize), Some(&x));
+        }
+
+        assert_eq!(list.get(10), None);
+
+        if let Some(x) = list.get_mut(7) {
+            *x = 70;
+        }
+
+        assert_eq!(list.get(7), Some(&70));
+    }
+
+    #[test]
+    fn insert_splits_full_nodes() {
+        let mut list: List<i32, 4> = List::new();
+
+        (0..4).for_each(|x| list.push_back(x));
+        assert_eq!(list.node_lens(), [4]);
+
+        list.insert(1, 10);
+
+        assert_eq!(list.node_lens(), [3, 2]);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [0, 10, 1, 2, 3]);
+
+        list.insert(4, 20);
+        list.insert(0, 30);
+        list.insert(list.len(), 40);
+
+        assert_eq!(
+            list.iter().copied().collect::<Vec<_>>(),
+            [30, 0, 10, 1, 2, 20, 3, 40]
+        );
+    }
+
+    #[test]
+    fn remove_merges_nodes() {
+        let mut list: List<i32, 4> = List::new();
+
+        (0..8).for_each(|x| list.push_back(x));
+        assert_eq!(list.node_lens(), [4, 4]);
+
+        assert_eq!(list.remove(0), Some(0));
+        assert_eq!(list.remove(0), Some(1));
+        assert_eq!(list.node_lens(), [2, 4]);
+
+        // dropping the first node below half full merges it with the next
+        // one, as soon as they fit
+        assert_eq!(list.remove(4), Some(6));
+        assert_eq!(list.remove(0), Some(2));
+        assert_eq!(list.node_lens(), [4]);
+
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [3, 4, 5, 7]);
+        assert_eq!(list.remove(4), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn insert_out_of_bounds() {
+        let mut list: List<i32, 4> = List::new();
+
+        list.insert(1, 0);
+    }
+
+    #[test]
+    fn iter_both_ends() {
+        let mut list: List<i32, 2> = List::new();
+
+        (0..5).for_each(|x| list.push_back(x));
+
+        let mut iter = list.iter();
+
+        assert_eq!(iter.next(), Some(&0));
+        assert_eq!(iter.next_back(), Some(&4));
+        assert_eq!(iter.next_back(), Some(&3));
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.next(), Some(&2));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+
+        assert_eq!(
+            list.iter().rev().copied().collect::<Vec<_>>(),
+            [4, 3, 2, 1, 0]
+        );
+    }
+
+    #[test]
+    fn iter_mut() {
+        let mut list: List<i32, 3> = List::new();
+
+        (0..7).for_each(|x| list.push_back(x));
+        list.iter_mut().for_each(|x| *x *= 10);
+        list.iter_mut().rev().take(1).for_each(|x| *x += 1);
+
+        assert_eq!(
+            list.iter().copied().collect::<Vec<_>>(),
+            [0, 10, 20, 30, 40, 50, 61]
+        );
+    }
+
+    #[test]
+    fn into_iter() {
+        let mut list: List<i32, 4> = List::new();
+
+        (0..6).for_each(|x| list.push_back(x));
+
+        let mut iter = list.into_iter();
+
+        assert_eq!(iter.next(), Some(0));
+        assert_eq!(iter.next_back(), Some(5));
+        assert_eq!(iter.collect::<Vec<_>>(), [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn drops_every_element_once() {
+        let value = Rc::new(());
+        let mut list: List<Rc<()>, 4> = List::new();
+
+        (0..10).for_each(|_| list.push_back(Rc::clone(&value)));
+        list.insert(3, Rc::clone(&value));
+        list.remove(5);
+        list.pop_front();
+
+        assert_eq!(Rc::strong_count(&value), 10);
+
+        drop(list);
+
+        assert_eq!(Rc::strong_count(&value), 1);
+    }
+
+    #[test]
+    fn drop_carries_on_after_a_panic() {
+        let counter = DropCounter::new();
+        let mut list: List<_, 4> = List::new();
+
+        // the panicking element sits in the middle of the first of three
+        // chunks, so there's more of its chunk, and more chunks, to drop
+        (0..2).for_each(|_| list.push_back(counter.tracked()));
+        list.push_back(counter.panicking());
+        (0..7).for_each(|_| list.push_back(counter.tracked()));
+
+        assert!(panics(|| drop(list)));
+
+        counter.assert_all_dropped_once();
+    }
+}
@@ -0,0 +1,709 @@
+// A doubly-linked deque with one link per node - an XOR linked list
+//
+// fourth::List gives every node two links, .next and .prev. Here each node
+// stores a single number instead:
+//
+//      link = address(prev) ^ address(next)
+//
+// with a missing neighbour counting as address 0. On its own that number
+// is useless, but XOR undoes itself - x ^ y ^ y == x - so as long as we
+// know the address of one neighbour, we can recover the other:
+//
+//      address(next) = link ^ address(prev)
+//      address(prev) = link ^ address(next)
+//
+// Walking from the head we know the previous node (nothing, i.e. 0), so we
+// can find the next one, and so on. Walking from the tail works the same
+// way in the other direction. Because the link doesn't say which
+// neighbour is which, reversing the whole list is just a matter of
+// swapping head and tail.
+//
+// The catch is that an address we get out of an XOR isn't a pointer as
+// far as Rust is concerned - integers don't carry the "provenance" that
+// says which allocation a pointer may access. So:
+//  - every node's address is turned into an integer with
+//      .expose_provenance(), which tells the compiler (and Miri) that the
+//      node may later be accessed through an integer address
+//  - addresses are turned back into pointers with
+//      ptr::with_exposed_provenance_mut, which picks up that exposed
+//      provenance
+//
+// Miri understands exposed provenance, but warns that it can't check it as
+// strictly as ordinary pointers - an XOR list can't avoid that, so run the
+// tests with MIRIFLAGS=-Zmiri-permissive-provenance to silence it:
+//
+//      MIRIFLAGS=-Zmiri-permissive-provenance cargo +nightly miri test xor
+//
+// The invariants the unsafe code below relies on:
+//  - head, tail and every non-zero address we compute point to live nodes
+//      that came from Box::leak, and each is freed exactly once with
+//      Box::from_raw when it's popped
+//  - head.link == address(second node) and tail.link == address(second
+//      to last node), because the missing outer neighbours count as 0
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use core::fmt;
+use core::iter::DoubleEndedIterator;
+use core::marker::PhantomData;
+use core::mem;
+use core::ptr::{self, NonNull};
+use core::str::FromStr;
+
+use crate::dot;
+use crate::text::{self, ParseListError};
+use crate::traits::{Deque, Queue, Stack};
+
+struct Node<T> {
+    elem: T,
+    // address(prev) ^ address(next)
+    link: usize,
+}
+
+type Link<T> = Option<NonNull<Node<T>>>;
+
+// The integer address of a node, 0 for no node
+//
+// This also exposes the node's provenance, so that the pointer we rebuild
+// from the address in `from_addr` is allowed to access it
+fn addr<T>(link: Link<T>) -> usize {
+    link.map_or(0, |node| node.as_ptr().expose_provenance())
+}
+
+// The inverse of `addr`
+fn from_addr<T>(addr: usize) -> Link<T> {
+    NonNull::new(ptr::with_exposed_provenance_mut(addr))
+}
+
+pub struct List<T> {
+    head: Link<T>,
+    tail: Link<T>,
+    len: usize,
+    // we own Boxed nodes, as far as the drop checker is concerned
+    _owns: PhantomData<Box<Node<T>>>,
+}
+
+// List owns its nodes like a Box would, but raw pointers opt us out of
+// Send and Sync
+unsafe impl<T: Send> Send for List<T> {}
+unsafe impl<T: Sync> Sync for List<T> {}
+
+pub struct IntoIter<T>(List<T>);
+
+// Iterating needs two nodes at each end - the next node to yield, and the
+// address of the node we came from, to decode the next node's link
+pub struct Iter<'a, T> {
+    front: Link<T>,
+    front_prev: usize,
+    back: Link<T>,
+    back_next: usize,
+    len: usize,
+    _marker: PhantomData<&'a T>,
+}
+
+pub struct IterMut<'a, T> {
+    front: Link<T>,
+    front_prev: usize,
+    back: Link<T>,
+    back_next: usize,
+    len: usize,
+    _marker: PhantomData<&'a mut T>,
+}
+
+impl<T> List<T> {
+    pub fn new() -> Self {
+        Self {
+            head: None,
+            tail: None,
+            len: 0,
+            _owns: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn push_front(&mut self, elem: T) {
+        // the new node's prev is nothing (0), and its next is the old head
+        let node = NonNull::from(Box::leak(Box::new(Node {
+            elem,
+            link: addr(self.head),
+        })));
+
+        match self.head {
+            // the old head's prev was nothing (0), and is now the new node:
+            //      (0 ^ next) ^ node == node ^ next
+            //
+            // SAFETY: head is a live node
+            Some(old_head) => unsafe { (*old_head.as_ptr()).link ^= addr(Some(node)) },
+            None => self.tail = Some(node),
+        }
+
+        self.head = Some(node);
+        self.len += 1;
+    }
+
+    pub fn push_back(&mut self, elem: T) {
+        // the mirror image of push_front
+        let node = NonNull::from(Box::leak(Box::new(Node {
+            elem,
+            link: addr(self.tail),
+        })));
+
+        match self.tail {
+            // SAFETY: tail is a live node
+            Some(old_tail) => unsafe { (*old_tail.as_ptr()).link ^= addr(Some(node)) },
+            None => self.head = Some(node),
+        }
+
+        self.tail = Some(node);
+        self.len += 1;
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.head.map(|old_head| {
+            // SAFETY: head is a live node, which we take back ownership of
+            // here, and nothing points to it once we've unlinked it
+            let old_head = unsafe { Box::from_raw(old_head.as_ptr()) };
+
+            // the head's prev is 0, so its link is just address(next)
+            let new_head = from_addr::<T>(old_head.link);
+
+            match new_head {
+                // the new head's prev was the old head, and is now nothing:
+                //      (old_head ^ next) ^ old_head == next
+                //
+                // SAFETY: new_head is a live node
+                Some(node) => unsafe {
+                    (*node.as_ptr()).link ^= addr(Some(NonNull::from(&*old_head)))
+                },
+                None => self.tail = None,
+            }
+
+            self.head = new_head;
+            self.len -= 1;
+
+            old_head.elem
+        })
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.tail.map(|old_tail| {
+            // SAFETY: as for pop_front
+            let old_tail = unsafe { Box::from_raw(old_tail.as_ptr()) };
+            let new_tail = from_addr::<T>(old_tail.link);
+
+            match new_tail {
+                // SAFETY: new_tail is a live node
+                Some(node) => unsafe {
+                    (*node.as_ptr()).link ^= addr(Some(NonNull::from(&*old_tail)))
+                },
+                None => self.head = None,
+            }
+
+            self.tail = new_tail;
+            self.len -= 1;
+
+            old_tail.elem
+        })
+    }
+
+    pub fn peek_front(&self) -> Option<&T> {
+        // SAFETY: head is a live node, borrowed for as long as self is
+        self.head.map(|node| unsafe { &(*node.as_ptr()).elem })
+    }
+
+    pub fn peek_front_mut(&mut self) -> Option<&mut T> {
+        // SAFETY: as above, and &mut self makes the reference unique
+        self.head.map(|node| unsafe { &mut (*node.as_ptr()).elem })
+    }
+
+    pub fn peek_back(&self) -> Option<&T> {
+        // SAFETY: tail is a live node, borrowed for as long as self is
+        self.tail.map(|node| unsafe { &(*node.as_ptr()).elem })
+    }
+
+    pub fn peek_back_mut(&mut self) -> Option<&mut T> {
+        // SAFETY: as above, and &mut self makes the reference unique
+        self.tail.map(|node| unsafe { &mut (*node.as_ptr()).elem })
+    }
+
+    // Reverse the list in O(1)
+    //
+    // Every link is prev ^ next, and XOR doesn't care about order, so the
+    // links read just as well in the other direction. All we need to do is
+    // start from the other end
+    pub fn reverse(&mut self) {
+        mem::swap(&mut self.head, &mut self.tail);
+    }
+
+    // The node graph in Graphviz's DOT format - see dot.rs
+    //
+    // A node only stores prev ^ next, so the next and prev edges are the
+    // neighbours decoded while walking from the head - what the link means
+    // rather than what it holds
+    pub fn to_dot(&self) -> String
+    where
+        T: fmt::Debug,
+    {
+        let mut graph = dot::Graph::new();
+        let mut from = None;
+        let (mut next, mut prev) = (self.head, 0);
+
+        graph.root("list", "xor::List");
+
+        for _ in 0..self.len {
+            let Some(node) = next else { break };
+            let (id, _) = graph.id(node.as_ptr());
+
+            // SAFETY: the first len nodes from the head are live
+            let elem = unsafe { &(*node.as_ptr()).elem };
+
+            graph.node(id, &format!("{elem:?}"));
+
+            match from {
+                Some(from) => {
+                    graph.edge(from, id, "next");
+                    graph.edge(id, from, "prev");
+                }
+                None => graph.edge("list", id, "head"),
+            }
+
+            from = Some(id);
+            // SAFETY: node is live, and prev is the neighbour we came from
+            (next, prev) = unsafe { step(node, prev) };
+        }
+
+        if let Some(tail) = self.tail {
+            let (id, _) = graph.id(tail.as_ptr());
+
+            graph.edge("list", id, "tail");
+        }
+
+        graph.finish()
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            front: self.head,
+            front_prev: 0,
+            back: self.tail,
+            back_next: 0,
+            len: self.len,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            front: self.head,
+            front_prev: 0,
+            back: self.tail,
+            back_next: 0,
+            len: self.len,
+            _marker: PhantomData,
+        }
+    }
+}
+
+// Step one node along, given the node we're on and the address of the
+// node we came from. Returns the node to go to next, and the address to
+// remember as "where we came from" for the step after that
+//
+// SAFETY: node must be live, and `from` must be the address of one of its
+// neighbours (or 0 if it's at the end we came from)
+unsafe fn step<T>(node: NonNull<Node<T>>, from: usize) -> (Link<T>, usize) {
+    // SAFETY: guaranteed by the caller. We only read the link field, never
+    // creating a reference to the whole node, so that IterMut doesn't
+    // invalidate the &mut T it's already handed out for this node
+    let link = unsafe { (*node.as_ptr()).link };
+
+    (from_addr(link ^ from), addr(Some(node)))
+}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Stack<T> for List<T> {
+    fn push(&mut self, elem: T) {
+        List::push_front(self, elem);
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        List::pop_front(self)
+    }
+}
+
+impl<T> Queue<T> for List<T> {
+    fn push_back(&mut self, elem: T) {
+        List::push_back(self, elem);
+    }
+
+    fn pop_front(&mut self) -> Option<T> {
+        List::pop_front(self)
+    }
+}
+
+impl<T> Deque<T> for List<T> {
+    fn push_front(&mut self, elem: T) {
+        List::push_front(self, elem);
+    }
+
+    fn pop_back(&mut self) -> Option<T> {
+        List::pop_back(self)
+    }
+}
+
+impl<T> Drop for List<T> {
+    fn drop(&mut self) {
+        // pop every node, so that each is freed exactly once and without
+        // recursion. If an element's drop panics, the guard carries on
+        // popping while the panic unwinds, as in second::List's Drop
+        struct DropGuard<'a, T>(&'a mut List<T>);
+
+        impl<T> Drop for DropGuard<'_, T> {
+            fn drop(&mut self) {
+                while self.0.pop_front().is_some() {}
+            }
+        }
+
+        let guard = DropGuard(self);
+
+        while guard.0.pop_front().is_some() {}
+        mem::forget(guard);
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for List<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+// Written front to back - or back to front after a .reverse()
+impl<T: fmt::Display> fmt::Display for List<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        text::write_list(f, self.iter())
+    }
+}
+
+impl<T: FromStr> FromStr for List<T> {
+    type Err = ParseListError<T::Err>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut list = Self::new();
+
+        text::parse_list(s)?
+            .into_iter()
+            .for_each(|elem| list.push_back(elem));
+
+        Ok(list)
+    }
+}
+
+impl<T> IntoIterator for List<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self)
+    }
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.pop_front()
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.pop_back()
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // len, rather than front being None, tells us when the two ends
+        // have met in the middle
+        if self.len == 0 {
+            return None;
+        }
+
+        self.front.map(|node| {
+            // SAFETY: len > 0, so front is a live node, and front_prev is
+            // the neighbour we reached it from
+            unsafe {
+                (self.front, self.front_prev) = step(node, self.front_prev);
+                self.len -= 1;
+
+                &(*node.as_ptr()).elem
+            }
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+
+        self.back.map(|node| {
+            // SAFETY: as for next, from the other end
+            unsafe {
+                (self.back, self.back_next) = step(node, self.back_next);
+                self.len -= 1;
+
+                &(*node.as_ptr()).elem
+            }
+        })
+    }
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+
+        self.front.map(|node| {
+            // SAFETY: as for Iter, and len makes sure each node is yielded
+            // once, so the &mut references never alias
+            unsafe {
+                (self.front, self.front_prev) = step(node, self.front_prev);
+                self.len -= 1;
+
+                &mut (*node.as_ptr()).elem
+            }
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IterMut<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+
+        self.back.map(|node| {
+            // SAFETY: as for next, from the other end
+            unsafe {
+                (self.back, self.back_next) = step(node, self.back_next);
+                self.len -= 1;
+
+                &mut (*node.as_ptr()).elem
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::List;
+    use crate::test_utils::{panics, DropCounter};
+    use std::cell::Cell;
+    use std::mem;
+
+    crate::conformance_tests!(Stack, stack_conformance, List::new());
+    crate::conformance_tests!(Deque, deque_conformance, List::new());
+
+    #[test]
+    fn push_and_pop_front() {
+        let mut list = List::new();
+        let xs = [0, 1, 2];
+
+        xs.into_iter().for_each(|x| list.push_front(x));
+
+        for &x in xs.iter().rev() {
+            assert_eq!(list.pop_front(), Some(x));
+        }
+
+        assert_eq!(list.pop_front(), None);
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn push_and_pop_back() {
+        let mut list = List::new();
+
+        [1, 2, 3].into_iter().for_each(|x| list.push_back(x));
+
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.pop_back(), Some(3));
+        assert_eq!(list.pop_front(), Some(1));
+        assert_eq!(list.pop_back(), Some(2));
+        assert_eq!(list.pop_back(), None);
+        assert_eq!(list.pop_front(), None);
+    }
+
+    #[test]
+    fn peek() {
+        let mut list = List::new();
+
+        assert_eq!(list.peek_front(), None);
+        assert_eq!(list.peek_back(), None);
+
+        [1, 2, 3].into_iter().for_each(|x| list.push_back(x));
+
+        if let Some(x) = list.peek_front_mut() {
+            *x *= 10;
+        }
+
+        if let Some(x) = list.peek_back_mut() {
+            *x *= 10;
+        }
+
+        assert_eq!(list.peek_front(), Some(&10));
+        assert_eq!(list.peek_back(), Some(&30));
+    }
+
+    #[test]
+    fn reverse() {
+        let mut list = List::new();
+
+        (0..5).for_each(|x| list.push_back(x));
+        list.reverse();
+
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [4, 3, 2, 1, 0]);
+
+        // the links still work for every operation after reversing
+        list.push_front(5);
+        list.push_back(-1);
+
+        assert_eq!(list.pop_back(), Some(-1));
+        assert_eq!(list.pop_back(), Some(0));
+        assert_eq!(list.pop_front(), Some(5));
+
+        list.reverse();
+
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn iter_both_ends() {
+        let mut list = List::new();
+
+        (0..5).for_each(|x| list.push_back(x));
+
+        let mut iter = list.iter();
+
+        assert_eq!(iter.next(), Some(&0));
+        assert_eq!(iter.next_back(), Some(&4));
+        assert_eq!(iter.next_back(), Some(&3));
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.next(), Some(&2));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+    }
+
+    #[test]
+    fn iter_mut() {
+        let mut list = List::new();
+
+        (0..4).for_each(|x| list.push_back(x));
+
+        // hold on to every reference at once, from both ends
+        let mut iter = list.iter_mut();
+        let first = iter.next().unwrap();
+        let last = iter.next_back().unwrap();
+        let rest: Vec<_> = iter.collect();
+
+        *first += 10;
+        *last += 10;
+        rest.into_iter().for_each(|x| *x *= 2);
+
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [10, 2, 4, 13]);
+    }
+
+    #[test]
+    fn into_iter() {
+        let mut list = List::new();
+
+        (0..4).for_each(|x| list.push_front(x));
+
+        assert_eq!(list.into_iter().rev().collect::<Vec<_>>(), [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn zero_sized_elements() {
+        let mut list = List::new();
+
+        (0..10).for_each(|_| list.push_back(()));
+        list.reverse();
+
+        assert_eq!(list.iter().count(), 10);
+        assert_eq!(list.iter().rev().count(), 10);
+        assert_eq!(list.pop_front(), Some(()));
+        assert_eq!(list.pop_back(), Some(()));
+        assert_eq!(list.len(), 8);
+    }
+
+    #[test]
+    fn drops_zero_sized_elements() {
+        thread_local! {
+            static DROPS: Cell<usize> = const { Cell::new(0) };
+        }
+
+        // a zero-sized type that counts how many times it's dropped - the
+        // count has to live outside it, as it has no room for a reference
+        struct Counted;
+
+        impl Drop for Counted {
+            fn drop(&mut self) {
+                DROPS.with(|drops| drops.set(drops.get() + 1));
+            }
+        }
+
+        assert_eq!(mem::size_of::<Counted>(), 0);
+
+        let drops = || DROPS.with(Cell::get);
+        let mut list = List::new();
+
+        (0..6).for_each(|_| list.push_front(Counted));
+
+        drop(list.pop_back());
+        assert_eq!(drops(), 1);
+
+        drop(list);
+        assert_eq!(drops(), 6);
+    }
+
+    #[test]
+    fn drop_carries_on_after_a_panic() {
+        let counter = DropCounter::new();
+        let mut list = List::new();
+
+        list.push_back(counter.tracked());
+        list.push_back(counter.panicking());
+        list.push_back(counter.tracked());
+        list.push_back(counter.tracked());
+        list.reverse();
+
+        assert!(panics(|| drop(list)));
+
+        counter.assert_all_dropped_once();
+    }
+}
@@ -0,0 +1,175 @@
+// Checks every list's to_dot output against the golden files in
+// tests/golden
+//
+// After an intended change to the output, regenerate the files with
+//
+//      UPDATE_GOLDEN=1 cargo test --test dot
+//
+// and check the diff. If Graphviz is installed, each graph is also run
+// through `dot` to make sure it renders.
+//
+// Reading the golden files needs Miri's isolation turned off:
+//
+//      MIRIFLAGS="-Zmiri-permissive-provenance -Zmiri-disable-isolation" \
+//          cargo +nightly miri test --test dot
+use learning_rust_with_entirely_too_many_linked_lists::{
+    arena, circular, first, fourth, second, third, unrolled, xor,
+};
+use std::env;
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+fn check(name: &str, dot: &str) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{name}.dot"));
+
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(&path, dot).unwrap();
+    }
+
+    let golden = fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("couldn't read {}: {e}", path.display()));
+
+    assert_eq!(dot, golden, "{name}.dot doesn't match");
+
+    renders(dot);
+}
+
+// Run the graph through Graphviz, if it's installed. Miri can't spawn
+// processes, so there's nothing to do under Miri
+fn renders(dot: &str) {
+    if cfg!(miri) {
+        return;
+    }
+
+    let child = Command::new("dot")
+        .arg("-Tsvg")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn();
+
+    let mut child = match child {
+        Ok(child) => child,
+        Err(e) if e.kind() == ErrorKind::NotFound => return,
+        Err(e) => panic!("failed to run dot: {e}"),
+    };
+
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(dot.as_bytes())
+        .unwrap();
+
+    let output = child.wait_with_output().unwrap();
+
+    assert!(
+        output.status.success(),
+        "dot couldn't render the graph:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn first() {
+    let mut list = first::List::new();
+
+    (1..=3).for_each(|x| list.push(x));
+
+    check("first", &list.to_dot());
+}
+
+#[test]
+fn second() {
+    let mut list = second::List::with_node_pool(4);
+
+    (1..=3).for_each(|x| list.push(x));
+    // the popped node is kept in the pool
+    list.pop();
+
+    check("second", &list.to_dot());
+}
+
+#[test]
+fn third() {
+    let list = third::List::new().prepend(1).prepend(2).prepend(3);
+
+    check("third", &list.to_dot());
+}
+
+#[test]
+fn third_shared() {
+    let mut base = third::List::new().prepend(1).prepend(2);
+    let a = base.prepend(3);
+    let b = base.prepend(4);
+
+    check("third_shared", &third::List::to_dot_many(&[a, b, base]));
+}
+
+#[test]
+fn fourth() {
+    let mut list = fourth::List::new();
+
+    (1..=3).for_each(|x| list.push_back(x));
+
+    check("fourth", &list.to_dot());
+}
+
+#[test]
+fn arena() {
+    let mut list = arena::List::new();
+
+    (1..=3).for_each(|x| {
+        list.push_back(x);
+    });
+    // 0 reuses the slot 1 was popped from
+    list.pop_front();
+    list.push_front(0);
+
+    check("arena", &list.to_dot());
+}
+
+#[test]
+fn unrolled() {
+    let mut list = unrolled::List::<_, 4>::new();
+
+    (1..=6).for_each(|x| list.push_back(x));
+
+    check("unrolled", &list.to_dot());
+}
+
+#[test]
+fn xor() {
+    let mut list = xor::List::new();
+
+    (1..=3).for_each(|x| list.push_back(x));
+
+    check("xor", &list.to_dot());
+}
+
+#[test]
+fn circular() {
+    let mut list = circular::List::new();
+
+    (1..=3).for_each(|x| list.push_back(x));
+
+    check("circular", &list.to_dot());
+}
+
+#[test]
+fn empty() {
+    check("empty", &fourth::List::<i32>::new().to_dot());
+}
+
+#[test]
+fn escapes_labels() {
+    let mut list = xor::List::new();
+
+    list.push_back("say \"hi\"");
+
+    check("escaped", &list.to_dot());
+}
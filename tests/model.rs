@@ -0,0 +1,562 @@
+// Model-based tests: every list against a std collection that does the
+// same job
+//
+// proptest generates random sequences of operations. We apply each one to
+// the list and to a model - a Vec for the stacks, a VecDeque for the
+// deques, a BTreeMap for the skip list - and check after every step that
+// they returned the same thing and hold the same elements. The contents
+// are compared through Display, which every list has, so we see the whole
+// list even where there's no borrowing iterator (fourth::List).
+//
+// When a sequence fails, proptest shrinks it - dropping operations and
+// making values smaller - until it finds the shortest sequence that still
+// fails, and prints that. Run a single model with e.g.
+// `cargo test --test model fourth`, and set PROPTEST_CASES to run more
+// sequences than the default 256.
+use learning_rust_with_entirely_too_many_linked_lists::{
+    arena, circular, first, fourth, second, skip_list, third, unrolled, xor,
+};
+use proptest::collection::vec;
+use proptest::prelude::*;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt::Display;
+
+// Render a model the way the lists render themselves
+fn render<T: Display>(elems: impl IntoIterator<Item = T>) -> String {
+    let elems: Vec<String> = elems.into_iter().map(|elem| elem.to_string()).collect();
+
+    format!("[{}]", elems.join(", "))
+}
+
+#[derive(Debug, Clone)]
+enum StackOp {
+    Push(i32),
+    Pop,
+    Peek,
+    // add to the top element in place
+    PeekMut(i32),
+}
+
+fn stack_op() -> impl Strategy<Value = StackOp> {
+    prop_oneof![
+        3 => any::<i32>().prop_map(StackOp::Push),
+        2 => Just(StackOp::Pop),
+        1 => Just(StackOp::Peek),
+        1 => (-100..100).prop_map(StackOp::PeekMut),
+    ]
+}
+
+#[derive(Debug, Clone)]
+enum DequeOp {
+    PushFront(i32),
+    PushBack(i32),
+    PopFront,
+    PopBack,
+    PeekFront,
+    PeekBack,
+}
+
+fn deque_op() -> impl Strategy<Value = DequeOp> {
+    prop_oneof![
+        any::<i32>().prop_map(DequeOp::PushFront),
+        any::<i32>().prop_map(DequeOp::PushBack),
+        Just(DequeOp::PopFront),
+        Just(DequeOp::PopBack),
+        Just(DequeOp::PeekFront),
+        Just(DequeOp::PeekBack),
+    ]
+}
+
+// The deque operations every deque in the crate has, so each test only
+// has to map the names
+trait Deque: Display {
+    fn push_front(&mut self, elem: i32);
+    fn push_back(&mut self, elem: i32);
+    fn pop_front(&mut self) -> Option<i32>;
+    fn pop_back(&mut self) -> Option<i32>;
+    fn peek_front(&self) -> Option<i32>;
+    fn peek_back(&self) -> Option<i32>;
+}
+
+macro_rules! impl_deque {
+    ($ty:ty, $push_front:ident, $push_back:ident, $peek_front:ident, $peek_back:ident) => {
+        impl Deque for $ty {
+            fn push_front(&mut self, elem: i32) {
+                self.$push_front(elem);
+            }
+
+            fn push_back(&mut self, elem: i32) {
+                self.$push_back(elem);
+            }
+
+            fn pop_front(&mut self) -> Option<i32> {
+                <$ty>::pop_front(self)
+            }
+
+            fn pop_back(&mut self) -> Option<i32> {
+                <$ty>::pop_back(self)
+            }
+
+            fn peek_front(&self) -> Option<i32> {
+                self.$peek_front().map(|elem| *elem)
+            }
+
+            fn peek_back(&self) -> Option<i32> {
+                self.$peek_back().map(|elem| *elem)
+            }
+        }
+    };
+}
+
+impl_deque!(
+    fourth::List<i32>,
+    push_front,
+    push_back,
+    peek_front,
+    peek_back
+);
+impl_deque!(xor::List<i32>, push_front, push_back, peek_front, peek_back);
+impl_deque!(unrolled::List<i32, 4>, push_front, push_back, front, back);
+
+// arena's pushes return a handle we don't need here
+impl Deque for arena::List<i32> {
+    fn push_front(&mut self, elem: i32) {
+        arena::List::push_front(self, elem);
+    }
+
+    fn push_back(&mut self, elem: i32) {
+        arena::List::push_back(self, elem);
+    }
+
+    fn pop_front(&mut self) -> Option<i32> {
+        arena::List::pop_front(self)
+    }
+
+    fn pop_back(&mut self) -> Option<i32> {
+        arena::List::pop_back(self)
+    }
+
+    fn peek_front(&self) -> Option<i32> {
+        arena::List::peek_front(self).copied()
+    }
+
+    fn peek_back(&self) -> Option<i32> {
+        arena::List::peek_back(self).copied()
+    }
+}
+
+fn apply_deque(
+    list: &mut impl Deque,
+    model: &mut VecDeque<i32>,
+    op: &DequeOp,
+) -> Result<(), TestCaseError> {
+    match *op {
+        DequeOp::PushFront(x) => {
+            list.push_front(x);
+            model.push_front(x);
+        }
+        DequeOp::PushBack(x) => {
+            list.push_back(x);
+            model.push_back(x);
+        }
+        DequeOp::PopFront => prop_assert_eq!(list.pop_front(), model.pop_front()),
+        DequeOp::PopBack => prop_assert_eq!(list.pop_back(), model.pop_back()),
+        DequeOp::PeekFront => prop_assert_eq!(list.peek_front(), model.front().copied()),
+        DequeOp::PeekBack => prop_assert_eq!(list.peek_back(), model.back().copied()),
+    }
+
+    Ok(())
+}
+
+fn check_deque(list: &mut impl Deque, ops: &[DequeOp]) -> Result<(), TestCaseError> {
+    let mut model = VecDeque::new();
+
+    for op in ops {
+        apply_deque(list, &mut model, op)?;
+        prop_assert_eq!(list.to_string(), render(&model));
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+enum ThirdOp {
+    // prepend to one of the versions so far, making a new version
+    Prepend(usize, i32),
+    // take the tail of one of the versions so far, making a new version
+    Tail(usize),
+}
+
+fn third_op() -> impl Strategy<Value = ThirdOp> {
+    prop_oneof![
+        2 => (any::<usize>(), any::<i32>()).prop_map(|(v, x)| ThirdOp::Prepend(v, x)),
+        1 => any::<usize>().prop_map(ThirdOp::Tail),
+    ]
+}
+
+#[derive(Debug, Clone)]
+enum UnrolledOp {
+    Deque(DequeOp),
+    // indices are taken modulo len + 1, so they're in range or just past
+    // the end
+    Insert(usize, i32),
+    Remove(usize),
+    Get(usize),
+}
+
+fn unrolled_op() -> impl Strategy<Value = UnrolledOp> {
+    prop_oneof![
+        2 => deque_op().prop_map(UnrolledOp::Deque),
+        2 => (any::<usize>(), any::<i32>()).prop_map(|(i, x)| UnrolledOp::Insert(i, x)),
+        1 => any::<usize>().prop_map(UnrolledOp::Remove),
+        1 => any::<usize>().prop_map(UnrolledOp::Get),
+    ]
+}
+
+#[derive(Debug, Clone)]
+enum ArenaOp {
+    Deque(DequeOp),
+    // the nth element from the front, by handle
+    Remove(usize),
+    MoveToFront(usize),
+}
+
+fn arena_op() -> impl Strategy<Value = ArenaOp> {
+    prop_oneof![
+        3 => deque_op().prop_map(ArenaOp::Deque),
+        1 => any::<usize>().prop_map(ArenaOp::Remove),
+        1 => any::<usize>().prop_map(ArenaOp::MoveToFront),
+    ]
+}
+
+#[derive(Debug, Clone)]
+enum RingOp {
+    PushBack(i32),
+    InsertAfterCurrent(i32),
+    RemoveCurrent,
+    Advance,
+    RotateForward(usize),
+    RotateBackward(usize),
+}
+
+fn ring_op() -> impl Strategy<Value = RingOp> {
+    prop_oneof![
+        any::<i32>().prop_map(RingOp::PushBack),
+        any::<i32>().prop_map(RingOp::InsertAfterCurrent),
+        Just(RingOp::RemoveCurrent),
+        Just(RingOp::Advance),
+        (0..20usize).prop_map(RingOp::RotateForward),
+        (0..20usize).prop_map(RingOp::RotateBackward),
+    ]
+}
+
+#[derive(Debug, Clone)]
+enum MapOp {
+    Insert(u8, i32),
+    Remove(u8),
+    Get(u8),
+    Range(u8, u8),
+}
+
+fn map_op() -> impl Strategy<Value = MapOp> {
+    // a small key space, so keys get inserted and removed more than once
+    prop_oneof![
+        3 => (any::<u8>(), any::<i32>()).prop_map(|(k, v)| MapOp::Insert(k, v)),
+        2 => any::<u8>().prop_map(MapOp::Remove),
+        1 => any::<u8>().prop_map(MapOp::Get),
+        1 => (any::<u8>(), any::<u8>()).prop_map(|(a, b)| MapOp::Range(a.min(b), a.max(b))),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn first_matches_vec(ops in vec(stack_op(), 0..200)) {
+        let mut list = first::List::new();
+        let mut model = Vec::new();
+
+        for op in ops {
+            match op {
+                StackOp::Push(x) => {
+                    list.push(x);
+                    model.push(x);
+                }
+                StackOp::Pop => prop_assert_eq!(list.pop(), model.pop()),
+                // first::List only has push and pop
+                StackOp::Peek | StackOp::PeekMut(_) => {}
+            }
+
+            // top first
+            prop_assert_eq!(list.to_string(), render(model.iter().rev()));
+        }
+    }
+
+    #[test]
+    fn second_matches_vec(ops in vec(stack_op(), 0..200), pool in 0..8usize) {
+        let mut list = second::List::with_node_pool(pool);
+        let mut model = Vec::new();
+
+        for op in ops {
+            match op {
+                StackOp::Push(x) => {
+                    list.push(x);
+                    model.push(x);
+                }
+                StackOp::Pop => prop_assert_eq!(list.pop(), model.pop()),
+                StackOp::Peek => prop_assert_eq!(list.peek(), model.last()),
+                StackOp::PeekMut(dx) => {
+                    if let Some(top) = list.peek_mut() {
+                        *top = top.wrapping_add(dx);
+                    }
+
+                    if let Some(top) = model.last_mut() {
+                        *top = top.wrapping_add(dx);
+                    }
+                }
+            }
+
+            prop_assert_eq!(list.to_string(), render(model.iter().rev()));
+            prop_assert!(list.pool_len() <= pool);
+        }
+    }
+
+    // Every version stays the same after later operations - that's the
+    // point of a persistent list - so we check all of them after every step
+    #[test]
+    fn third_matches_vec_snapshots(ops in vec(third_op(), 0..100)) {
+        let mut versions = vec![third::List::new()];
+        // each version as a Vec, head first
+        let mut models: Vec<Vec<i32>> = vec![Vec::new()];
+
+        for op in ops {
+            match op {
+                ThirdOp::Prepend(v, x) => {
+                    let v = v % versions.len();
+                    let mut model = vec![x];
+
+                    let version = versions[v].prepend(x);
+
+                    model.extend(&models[v]);
+                    versions.push(version);
+                    models.push(model);
+                }
+                ThirdOp::Tail(v) => {
+                    let v = v % versions.len();
+
+                    versions.push(versions[v].clone().tail());
+                    models.push(models[v].iter().skip(1).copied().collect());
+                }
+            }
+
+            for (list, model) in versions.iter().zip(&models) {
+                prop_assert_eq!(list.head(), model.first());
+                prop_assert_eq!(list.iter().collect::<Vec<_>>(), model.iter().collect::<Vec<_>>());
+            }
+        }
+    }
+
+    #[test]
+    fn fourth_matches_vec_deque(ops in vec(deque_op(), 0..200)) {
+        check_deque(&mut fourth::List::new(), &ops)?;
+    }
+
+    #[test]
+    fn xor_matches_vec_deque(ops in vec(deque_op(), 0..200)) {
+        check_deque(&mut xor::List::new(), &ops)?;
+    }
+
+    #[test]
+    fn xor_reversed_matches_vec_deque(ops in vec(deque_op(), 0..200)) {
+        let mut list = xor::List::new();
+        let mut model = VecDeque::new();
+
+        for op in &ops {
+            // reversing is O(1) and should swap which end is which, so
+            // reverse before every step and do the mirror image operation
+            list.reverse();
+            model.make_contiguous().reverse();
+
+            match *op {
+                DequeOp::PushFront(x) => {
+                    list.push_back(x);
+                    model.push_back(x);
+                }
+                DequeOp::PushBack(x) => {
+                    list.push_front(x);
+                    model.push_front(x);
+                }
+                DequeOp::PopFront => prop_assert_eq!(list.pop_back(), model.pop_back()),
+                DequeOp::PopBack => prop_assert_eq!(list.pop_front(), model.pop_front()),
+                DequeOp::PeekFront => prop_assert_eq!(list.peek_back(), model.back()),
+                DequeOp::PeekBack => prop_assert_eq!(list.peek_front(), model.front()),
+            }
+
+            prop_assert_eq!(list.to_string(), render(&model));
+        }
+    }
+
+    #[test]
+    fn unrolled_matches_vec_deque(ops in vec(unrolled_op(), 0..300)) {
+        // a small N, so nodes split and merge all the time
+        let mut list = unrolled::List::<i32, 4>::new();
+        let mut model = VecDeque::new();
+
+        for op in ops {
+            match op {
+                UnrolledOp::Deque(op) => apply_deque(&mut list, &mut model, &op)?,
+                UnrolledOp::Insert(i, x) => {
+                    let i = i % (model.len() + 1);
+
+                    list.insert(i, x);
+                    model.insert(i, x);
+                }
+                UnrolledOp::Remove(i) => {
+                    let i = i % (model.len() + 1);
+
+                    prop_assert_eq!(list.remove(i), model.remove(i));
+                }
+                UnrolledOp::Get(i) => {
+                    let i = i % (model.len() + 1);
+
+                    prop_assert_eq!(list.get(i), model.get(i));
+                }
+            }
+
+            prop_assert_eq!(list.len(), model.len());
+            prop_assert_eq!(list.to_string(), render(&model));
+        }
+    }
+
+    #[test]
+    fn arena_matches_vec_deque(ops in vec(arena_op(), 0..200)) {
+        let mut list = arena::List::new();
+        // elements are unique ids, so we can find an element's handle
+        let mut model: VecDeque<i32> = VecDeque::new();
+        let mut handles = HashMap::new();
+        let mut removed = Vec::new();
+        let mut next_id = 0;
+
+        for op in ops {
+            match op {
+                ArenaOp::Deque(DequeOp::PushFront(_)) => {
+                    handles.insert(next_id, list.push_front(next_id));
+                    model.push_front(next_id);
+                    next_id += 1;
+                }
+                ArenaOp::Deque(DequeOp::PushBack(_)) => {
+                    handles.insert(next_id, list.push_back(next_id));
+                    model.push_back(next_id);
+                    next_id += 1;
+                }
+                ArenaOp::Deque(DequeOp::PopFront) => {
+                    let popped = list.pop_front();
+
+                    prop_assert_eq!(popped, model.pop_front());
+                    removed.extend(popped.and_then(|id| handles.remove(&id)));
+                }
+                ArenaOp::Deque(DequeOp::PopBack) => {
+                    let popped = list.pop_back();
+
+                    prop_assert_eq!(popped, model.pop_back());
+                    removed.extend(popped.and_then(|id| handles.remove(&id)));
+                }
+                ArenaOp::Deque(DequeOp::PeekFront) => prop_assert_eq!(list.peek_front(), model.front()),
+                ArenaOp::Deque(DequeOp::PeekBack) => prop_assert_eq!(list.peek_back(), model.back()),
+                ArenaOp::Remove(i) if !model.is_empty() => {
+                    let id = model.remove(i % model.len()).unwrap();
+                    let handle = handles.remove(&id).unwrap();
+
+                    prop_assert_eq!(list.remove(handle), Some(id));
+                    removed.push(handle);
+                }
+                ArenaOp::MoveToFront(i) if !model.is_empty() => {
+                    let id = model.remove(i % model.len()).unwrap();
+
+                    model.push_front(id);
+                    prop_assert!(list.move_to_front(handles[&id]));
+                }
+                ArenaOp::Remove(_) | ArenaOp::MoveToFront(_) => {}
+            }
+
+            // handles to removed elements stay dead, even once their slot
+            // has been reused
+            for &handle in &removed {
+                prop_assert!(!list.contains(handle));
+                prop_assert!(!list.move_to_front(handle));
+            }
+
+            prop_assert_eq!(list.to_string(), render(&model));
+        }
+    }
+
+    #[test]
+    fn arena_matches_vec_deque_values(ops in vec(deque_op(), 0..200)) {
+        check_deque(&mut arena::List::new(), &ops)?;
+    }
+
+    // The ring is modelled as a VecDeque whose front is current
+    #[test]
+    fn circular_matches_vec_deque(ops in vec(ring_op(), 0..200)) {
+        let mut list = circular::List::new();
+        let mut model = VecDeque::new();
+
+        for op in ops {
+            match op {
+                RingOp::PushBack(x) => {
+                    list.push_back(x);
+                    model.push_back(x);
+                }
+                RingOp::InsertAfterCurrent(x) => {
+                    list.insert_after_current(x);
+                    model.insert(1.min(model.len()), x);
+                }
+                RingOp::RemoveCurrent => prop_assert_eq!(list.remove_current(), model.pop_front()),
+                RingOp::Advance => {
+                    model.rotate_left(1.min(model.len()));
+                    prop_assert_eq!(list.advance(), model.front());
+                }
+                RingOp::RotateForward(n) => {
+                    list.rotate_forward(n);
+
+                    if !model.is_empty() {
+                        let n = n % model.len();
+                        model.rotate_left(n);
+                    }
+                }
+                RingOp::RotateBackward(n) => {
+                    list.rotate_backward(n);
+
+                    if !model.is_empty() {
+                        let n = n % model.len();
+                        model.rotate_right(n);
+                    }
+                }
+            }
+
+            prop_assert_eq!(list.current(), model.front());
+            prop_assert_eq!(list.to_string(), render(&model));
+        }
+    }
+
+    #[test]
+    fn skip_list_matches_btree_map(ops in vec(map_op(), 0..300), seed in any::<u64>()) {
+        let mut map = skip_list::SkipListMap::with_seed(seed);
+        let mut model = BTreeMap::new();
+
+        for op in ops {
+            match op {
+                MapOp::Insert(k, v) => prop_assert_eq!(map.insert(k, v), model.insert(k, v)),
+                MapOp::Remove(k) => prop_assert_eq!(map.remove(&k), model.remove(&k)),
+                MapOp::Get(k) => prop_assert_eq!(map.get(&k), model.get(&k)),
+                MapOp::Range(a, b) => {
+                    prop_assert!(map.range(a..b).eq(model.range(a..b)));
+                    prop_assert!(map.range(a..=b).eq(model.range(a..=b)));
+                }
+            }
+
+            prop_assert_eq!(map.len(), model.len());
+            prop_assert_eq!(map.first(), model.first_key_value());
+            prop_assert_eq!(map.last(), model.last_key_value());
+            prop_assert!(map.iter().eq(model.iter()));
+        }
+    }
+}
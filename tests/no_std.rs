@@ -0,0 +1,45 @@
+// Checks that the library builds without std
+//
+// With the default `std` feature turned off the lib is #![no_std], but
+// tests always link std - so there's no way to check it from inside a
+// test. Instead this runs cargo to build the lib on its own, without
+// default features, for the host target. Anything that still reaches for
+// std fails to resolve
+use std::env;
+use std::path::Path;
+use std::process::Command;
+
+fn check_without_std(extra_args: &[&str]) {
+    let cargo = env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let manifest = Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+    // a target dir of its own, so that it doesn't wait on the lock held
+    // by the cargo that's running this test
+    let target_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("no_std");
+
+    let output = Command::new(cargo)
+        .args(["check", "--lib", "--no-default-features", "--quiet"])
+        .arg("--manifest-path")
+        .arg(&manifest)
+        .arg("--target-dir")
+        .arg(&target_dir)
+        .args(extra_args)
+        .output()
+        .expect("failed to run cargo");
+
+    assert!(
+        output.status.success(),
+        "cargo check {} failed:\n{}",
+        extra_args.join(" "),
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn builds_without_std() {
+    check_without_std(&[]);
+}
+
+#[test]
+fn builds_without_std_with_serde() {
+    check_without_std(&["--features", "serde"]);
+}
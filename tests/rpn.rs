@@ -0,0 +1,203 @@
+// Runs the scripts in tests/rpn through the rpn binary
+//
+// Each script.rpn is checked against script.out, what it should print to
+// stdout, and - if it's meant to fail - script.err, what it should print to
+// stderr. A script with no .err file has to succeed.
+//
+// Miri can't spawn processes, so these don't run under Miri.
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+const RPN: &str = env!("CARGO_BIN_EXE_rpn");
+
+fn scripts() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/rpn")
+}
+
+fn expected(name: &str, extension: &str) -> Option<String> {
+    fs::read_to_string(scripts().join(name).with_extension(extension)).ok()
+}
+
+fn check(name: &str) {
+    if cfg!(miri) {
+        return;
+    }
+
+    // run from the scripts' directory, so errors name the script the same
+    // way wherever the repo is
+    let output = Command::new(RPN)
+        .arg(format!("{name}.rpn"))
+        .current_dir(scripts())
+        .output()
+        .expect("failed to run rpn");
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    assert_eq!(
+        stdout,
+        expected(name, "out").unwrap_or_else(|| panic!("{name}.out is missing")),
+        "{name}: stdout doesn't match"
+    );
+
+    match expected(name, "err") {
+        Some(err) => {
+            assert_eq!(stderr, err, "{name}: stderr doesn't match");
+            assert_eq!(output.status.code(), Some(1), "{name} should fail");
+        }
+        None => assert!(output.status.success(), "{name} failed:\n{stderr}"),
+    }
+}
+
+fn run_with_stdin(args: &[&str], input: &str) -> Output {
+    let mut child = Command::new(RPN)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to run rpn");
+
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+
+    child.wait_with_output().unwrap()
+}
+
+// A test for each script, and a list of them all in SCRIPTS
+macro_rules! scripts {
+    ($($name:ident),* $(,)?) => {
+        const SCRIPTS: &[&str] = &[$(stringify!($name)),*];
+
+        $(
+            #[test]
+            fn $name() {
+                check(stringify!($name));
+            }
+        )*
+    };
+}
+
+scripts!(
+    arithmetic,
+    floats,
+    comparisons,
+    stack_words,
+    definitions,
+    conditionals,
+    comments,
+    unknown_word,
+    underflow,
+    division_by_zero,
+    overflow,
+    bad_index,
+    missing_name,
+    unterminated_definition,
+    unterminated_if,
+    unterminated_comment,
+    unexpected_then,
+    runaway_recursion,
+);
+
+#[test]
+fn every_script_is_run() {
+    // a new script that isn't added to scripts! above would never be run
+    if cfg!(miri) {
+        return;
+    }
+
+    for entry in fs::read_dir(scripts()).unwrap() {
+        let path = entry.unwrap().path();
+
+        if path.extension().is_some_and(|ext| ext == "rpn") {
+            let name = path.file_stem().unwrap().to_str().unwrap();
+
+            assert!(SCRIPTS.contains(&name), "{name}.rpn isn't in scripts!");
+        }
+    }
+}
+
+#[test]
+fn reads_stdin() {
+    if cfg!(miri) {
+        return;
+    }
+
+    for args in [&[][..], &["-"]] {
+        let output = run_with_stdin(args, "2 3 + 4 * .\n1 0 /");
+
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), "20 ");
+        assert_eq!(
+            String::from_utf8(output.stderr).unwrap(),
+            "<stdin>:2:5: division by zero\n"
+        );
+        assert_eq!(output.status.code(), Some(1));
+    }
+}
+
+#[test]
+fn deeply_nested_ifs() {
+    if cfg!(miri) {
+        return;
+    }
+
+    // an error at the 1001st if, rather than overflowing the stack while
+    // compiling them all
+    let output = run_with_stdin(&[], &"1 if ".repeat(200_000));
+
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "<stdin>:1:5003: calls and ifs nested more than 1000 deep\n"
+    );
+    assert_eq!(output.status.code(), Some(1));
+
+    // fewer ifs compile, but count towards the same limit as calls when
+    // they run
+    let script = format!(
+        ": deep {}deep{} ; deep",
+        "1 if ".repeat(500),
+        " then".repeat(500)
+    );
+    let output = run_with_stdin(&[], &script);
+
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "<stdin>:1:2500: calls and ifs nested more than 1000 deep\n"
+    );
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn missing_file() {
+    if cfg!(miri) {
+        return;
+    }
+
+    let output = run_with_stdin(&["no/such/script.rpn"], "");
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .starts_with("rpn: couldn't read no/such/script.rpn"));
+}
+
+#[test]
+fn usage() {
+    if cfg!(miri) {
+        return;
+    }
+
+    let output = run_with_stdin(&["a.rpn", "b.rpn"], "");
+
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "usage: rpn [FILE]\n"
+    );
+}